@@ -0,0 +1,82 @@
+//! Measures the throughput of timestamp scanning on a large stream.
+//!
+//! The input is generated on the fly, set `ANYLOG_BENCH_MB` to change its
+//! size (defaults to 256 MB):
+//!
+//! ```text
+//! ANYLOG_BENCH_MB=4096 cargo bench --bench scan
+//! ```
+use std::env;
+use std::io::{self, BufReader, Read};
+use std::time::Instant;
+
+use anylog::{Parser, Registry};
+
+const LINES: &[&str] = &[
+    "[pid: 1|app: 0|req: 1/1] 10.0.0.1 () {34 vars in 512 bytes} [Tue Jun  1 12:00:00 2021] GET / => generated 2 bytes",
+    "request finished in 12ms status=200 path=/api/v1/items at 2021-06-01T12:00:00.123Z",
+    "worker-7 processed 4096 items from queue default in 3ms (batch 12/64, retries 0)",
+    "bucket-owner my-bucket [01/Jun/2021:12:00:00 +0000] 192.0.2.3 REST.GET.OBJECT key.txt",
+    "cache miss for key user:12345:profile, falling back to database lookup on shard 3",
+];
+
+/// Repeats a block of lines until `remaining` bytes were produced.
+struct Repeat {
+    block: Vec<u8>,
+    pos: usize,
+    remaining: u64,
+}
+
+impl Read for Repeat {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(self.block.len() - self.pos)
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        buf[..len].copy_from_slice(&self.block[self.pos..self.pos + len]);
+        self.pos = (self.pos + len) % self.block.len();
+        self.remaining -= len as u64;
+        Ok(len)
+    }
+}
+
+fn main() {
+    let megabytes: u64 = env::var("ANYLOG_BENCH_MB")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(256);
+    let mut block = Vec::new();
+    for line in LINES.iter().cycle().take(LINES.len() * 100) {
+        block.extend_from_slice(line.as_bytes());
+        block.push(b'\n');
+    }
+    let input = Repeat {
+        block,
+        pos: 0,
+        remaining: megabytes * 1024 * 1024,
+    };
+
+    let parser = Parser::builder()
+        .registry(Registry::new())
+        .epoch_heuristics(None)
+        .scan_line(true)
+        .build();
+    let start = Instant::now();
+    let (mut entries, mut timestamped) = (0u64, 0u64);
+    for entry in parser.read_entries(BufReader::with_capacity(1 << 16, input)) {
+        let entry = entry.unwrap();
+        entries += 1;
+        if entry.timestamp().is_some() {
+            timestamped += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "scan: {} MB, {} entries ({} timestamped) in {:.2?}, {:.1} MB/s",
+        megabytes,
+        entries,
+        timestamped,
+        elapsed,
+        megabytes as f64 / elapsed.as_secs_f64()
+    );
+}
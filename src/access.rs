@@ -0,0 +1,183 @@
+use std::str;
+
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CLF_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([^\x20]+)
+            \x20([^\x20]+)
+            \x20([^\x20"]+|"[^"]*")
+            \x20\[(
+                ([0-9]{2})/(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/([0-9]{4})
+                :([0-9]{2}):([0-9]{2}):([0-9]{2})
+                \x20([+-])([0-9]{2})([0-9]{2})
+            )\]
+            \x20(.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref REQUEST_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            "(?:
+                ([A-Z]+)\x20([^\x20"]+)(?:\x20([^\x20"]+))?
+                |
+                [^"]*
+            )"
+            \x20([0-9]{3})
+            \x20([0-9]+|-)
+            (?:\x20"((?:[^"\\]|\\.)*)"\x20"((?:[^"\\]|\\.)*)")?
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of an Apache or nginx access log in the Common or
+/// Combined Log Format.
+///
+/// The message is the part after the timestamp, starting with the request
+/// line.  The client address, user, request method, path and protocol,
+/// status, response size, referrer and user agent are exposed as the
+/// `client_ip`, `user`, `method`, `path`, `protocol`, `status`, `bytes`,
+/// `referer` and `user_agent` fields.  Missing values (`-`) are left out.
+///
+/// Example: `1.2.3.4 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.0" 200 2326`
+pub fn parse_clf_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CLF_RE.captures(bytes)?;
+    let message = caps.get(14).unwrap();
+    let request = REQUEST_RE.captures(message.as_bytes())?;
+
+    let num = |idx| -> i32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+    let naive = naive_datetime(
+        num(7),
+        get_month(&caps[6])?,
+        num(5) as u32,
+        num(8) as u32,
+        num(9) as u32,
+        num(10) as u32,
+    )?;
+    let offset = (num(12) * 60 + num(13)) * 60;
+    let offset = FixedOffset::east_opt(if &caps[11] == b"-" { -offset } else { offset })?;
+    let ts = offset.from_local_datetime(&naive).single()?;
+
+    let mut entry = LogEntry::from_fixed_time(ts, message.as_bytes())
+        .with_spans(caps.get(4).unwrap().range(), message.range());
+    let fields = [
+        ("client_ip", caps.get(1)),
+        ("user", caps.get(3)),
+        ("method", request.get(1)),
+        ("path", request.get(2)),
+        ("protocol", request.get(3)),
+        ("status", request.get(4)),
+        ("bytes", request.get(5)),
+        ("referer", request.get(6)),
+        ("user_agent", request.get(7)),
+    ];
+    for (key, value) in fields.iter() {
+        let value = match value {
+            Some(value) if value.as_bytes() != b"-" && value.as_bytes() != b"\"-\"" => value,
+            _ => continue,
+        };
+        entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_clf_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.clone().into_owned(),
+            entry.format(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"1.2.3.4 - - [10/Oct/2000:13:55:36 -0700] \"GET /apache_pb.gif HTTP/1.0\" 200 2326"),
+            parse(b"127.0.0.1 - frank [10/Oct/2000:13:55:36 +0200] \"POST /login HTTP/1.1\" 302 - \"https://example.com/\" \"Mozilla/5.0 (X11)\""),
+            parse(b"::1 - - [10/Oct/2000:13:55:36 +0000] \"\\x16\\x03\" 400 0"),
+        ],
+        @r###"
+    [
+        (
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2000-10-10T13:55:36-07:00,
+                    ),
+                ),
+                message: "\"GET /apache_pb.gif HTTP/1.0\" 200 2326",
+            },
+            Some(
+                Clf,
+            ),
+            [
+                "client_ip=1.2.3.4",
+                "method=GET",
+                "path=/apache_pb.gif",
+                "protocol=HTTP/1.0",
+                "status=200",
+                "bytes=2326",
+            ],
+        ),
+        (
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2000-10-10T13:55:36+02:00,
+                    ),
+                ),
+                message: "\"POST /login HTTP/1.1\" 302 - \"https://example.com/\" \"Mozilla/5.0 (X11)\"",
+            },
+            Some(
+                Clf,
+            ),
+            [
+                "client_ip=127.0.0.1",
+                "user=frank",
+                "method=POST",
+                "path=/login",
+                "protocol=HTTP/1.1",
+                "status=302",
+                "referer=https://example.com/",
+                "user_agent=Mozilla/5.0 (X11)",
+            ],
+        ),
+        (
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2000-10-10T13:55:36+00:00,
+                    ),
+                ),
+                message: "\"\\x16\\x03\" 400 0",
+            },
+            Some(
+                Clf,
+            ),
+            [
+                "client_ip=::1",
+                "status=400",
+                "bytes=0",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref RUNNER_TS_RE: Regex =
+        Regex::new(r#"^[0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}\.[0-9]{7}Z$"#)
+            .unwrap();
+    static ref MARKER_RE: Regex = Regex::new(r#"^##\[([a-z]+)\]"#).unwrap();
+}
+
+/// Parses a line of a GitHub Actions or Azure Pipelines job log.
+///
+/// The runners prefix every line with a UTC timestamp with seven
+/// fractional digits.  The `##[error]`, `##[warning]`, `##[notice]` and
+/// `##[debug]` markers set the level, all `##[...]` markers (including
+/// `##[group]` and `##[command]`) are stripped from the message.
+///
+/// Example: `2021-06-01T12:00:00.1234567Z ##[error]Process completed with exit code 1.`
+pub fn parse_actions_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    if !RUNNER_TS_RE.is_match(&bytes[ts_span.clone()]) {
+        return None;
+    }
+    let message_span = entry.message_span()?;
+    let caps = match MARKER_RE.captures(&bytes[message_span.clone()]) {
+        Some(caps) => caps,
+        None => return Some(entry),
+    };
+
+    let level = match &caps[1] {
+        b"error" => Some("error"),
+        b"warning" => Some("warning"),
+        b"notice" => Some("notice"),
+        b"debug" => Some("debug"),
+        _ => None,
+    };
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message);
+    Some(match level {
+        Some(level) => entry.with_level(Cow::Borrowed(level)),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_actions_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.utc_timestamp().map(|x| x.to_rfc3339()),
+            entry.level(),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"2021-06-01T12:00:00.1234567Z ##[group]Run actions/checkout@v2"),
+            parse(b"2021-06-01T12:00:01.1234567Z Syncing repository: getsentry/rust-anylog"),
+            parse(b"2021-06-01T12:00:02.1234567Z ##[warning]Node.js 12 actions are deprecated."),
+            parse(b"2021-06-01T12:00:03.1234567Z ##[error]Process completed with exit code 1."),
+            parse(b"2021-06-01T12:00:04.123Z ##[error]not a runner timestamp"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Actions,
+            ),
+            Some(
+                "2021-06-01T12:00:00.123456700+00:00",
+            ),
+            None,
+            "Run actions/checkout@v2",
+        ),
+        (
+            Some(
+                Actions,
+            ),
+            Some(
+                "2021-06-01T12:00:01.123456700+00:00",
+            ),
+            None,
+            "Syncing repository: getsentry/rust-anylog",
+        ),
+        (
+            Some(
+                Actions,
+            ),
+            Some(
+                "2021-06-01T12:00:02.123456700+00:00",
+            ),
+            Some(
+                Warning,
+            ),
+            "Node.js 12 actions are deprecated.",
+        ),
+        (
+            Some(
+                Actions,
+            ),
+            Some(
+                "2021-06-01T12:00:03.123456700+00:00",
+            ),
+            Some(
+                Error,
+            ),
+            "Process completed with exit code 1.",
+        ),
+        (
+            Some(
+                Rfc3339,
+            ),
+            Some(
+                "2021-06-01T12:00:04.123+00:00",
+            ),
+            None,
+            "##[error]not a runner timestamp",
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+use chrono::Duration;
+
+/// The time between an entry and a reference point, such as a crash.
+///
+/// Formatting an age yields a compact human readable form like
+/// `3m12s before` or `250ms after`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Age(Duration);
+
+impl Age {
+    pub(crate) fn new(age: Duration) -> Age {
+        Age(age)
+    }
+
+    /// Returns the age as a duration.
+    ///
+    /// The duration is positive for entries before the reference.
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Returns `true` if the entry lies before the reference.
+    pub fn is_before(&self) -> bool {
+        self.0 > Duration::zero()
+    }
+}
+
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let abs = self.0.abs();
+        if abs.is_zero() {
+            return write!(f, "at the same time");
+        }
+        let secs = abs.num_seconds();
+        let (days, hours, minutes, seconds) =
+            (secs / 86_400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+        if days > 0 {
+            write!(f, "{}d{}h", days, hours)?;
+        } else if hours > 0 {
+            write!(f, "{}h{}m", hours, minutes)?;
+        } else if minutes > 0 {
+            write!(f, "{}m{}s", minutes, seconds)?;
+        } else if seconds > 0 {
+            write!(f, "{}s", seconds)?;
+        } else if abs < Duration::milliseconds(1) {
+            write!(f, "<1ms")?;
+        } else {
+            write!(f, "{}ms", abs.num_milliseconds())?;
+        }
+        write!(f, " {}", if self.is_before() { "before" } else { "after" })
+    }
+}
@@ -0,0 +1,170 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref UNIFIED_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (0x[0-9a-f]+)
+            \x20+(Default|Info|Debug|Error|Fault)
+            \x20+(0x[0-9a-f]+)
+            \x20+([0-9]+)
+            \x20+[0-9]+
+            \x20+([^:]+?):
+            (?:\x20\(([^)]*)\))?
+            (?:\x20\[([^\]:]+)(?::([^\]]*))?\])?
+            (?:\x20|$)
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of the Apple unified log as printed by `log show`.
+///
+/// The message type sets the level, with `Default` treated as info and
+/// `Fault` as fatal.  The thread id, message type, activity id, process id
+/// and process name are exposed as the `tid`, `type`, `activity`, `procid`
+/// and `process` fields.  The sender image in parentheses and the subsystem
+/// and category in brackets become the `sender`, `subsystem` and `category`
+/// fields.
+///
+/// Example: `2021-06-01 12:00:00.123456+0200 0x1a2b Default 0x0 123 0 bluetoothd: (CoreUtils) message`
+pub fn parse_unified_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = UNIFIED_RE.captures(&bytes[message_span.clone()])?;
+
+    // the ids and the type only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let level = match text(2) {
+        "Default" => "info",
+        "Fault" => "fatal",
+        other => other,
+    };
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let mut entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_level(Cow::Borrowed(level))
+        .with_field("tid", text(1))
+        .with_field("type", text(2))
+        .with_field("activity", text(3))
+        .with_field("procid", text(4));
+    let fields = [
+        ("process", caps.get(5)),
+        ("sender", caps.get(6)),
+        ("subsystem", caps.get(7)),
+        ("category", caps.get(8)),
+    ];
+    for (key, value) in fields.iter() {
+        if let Some(value) = value {
+            entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+        }
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_unified_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01 12:00:00.123456+0200 0x1a2b     Default     0x0                  123    0    bluetoothd: (CoreUtils) Device connected"[..]),
+            parse(b"2021-06-01 12:00:01.000000+0200 0x2c3d     Fault       0x4f5a               456    14   Safari: (WebKit) [com.apple.WebKit:Process] WebContent crashed"),
+            parse(b"2021-06-01 12:00:02.000000+0200 0x2c3d     Error       0x0                  456    0    Safari: network unreachable"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Unified,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123456+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "Device connected",
+            [
+                "tid=0x1a2b",
+                "type=Default",
+                "activity=0x0",
+                "procid=123",
+                "process=bluetoothd",
+                "sender=CoreUtils",
+            ],
+        ),
+        (
+            Some(
+                Unified,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:01+02:00,
+                ),
+            ),
+            Some(
+                Fatal,
+            ),
+            "WebContent crashed",
+            [
+                "tid=0x2c3d",
+                "type=Fault",
+                "activity=0x4f5a",
+                "procid=456",
+                "process=Safari",
+                "sender=WebKit",
+                "subsystem=com.apple.WebKit",
+                "category=Process",
+            ],
+        ),
+        (
+            Some(
+                Unified,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:02+02:00,
+                ),
+            ),
+            Some(
+                Error,
+            ),
+            "network unreachable",
+            [
+                "tid=0x2c3d",
+                "type=Error",
+                "activity=0x0",
+                "procid=456",
+                "process=Safari",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,145 @@
+use std::str;
+
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::Context;
+use crate::types::{LogEntry, Precision};
+
+lazy_static! {
+    static ref AUDIT_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?:node=([^\x20]+)\x20)?
+            type=([A-Z0-9_]+|UNKNOWN\[[0-9]+\])
+            \x20msg=audit\((([0-9]{1,12})\.([0-9]{3})):([0-9]+)\):
+            \x20?(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a record of the Linux audit log (`/var/log/audit/audit.log`).
+///
+/// The record type, the serial number and the optional node name become
+/// the `type`, `serial` and `node` fields.  The message is the rest of
+/// the record after the `msg=audit(...)` header.
+///
+/// Example: `type=AVC msg=audit(1612345678.123:456): avc:  denied  { read } for pid=1234`
+pub fn parse_audit_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = AUDIT_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> i64 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+    let ts = Utc
+        .timestamp_opt(num(4), num(5) as u32 * 1_000_000)
+        .single()?;
+
+    let message = caps.get(7).unwrap();
+    let fields = [
+        ("type", caps.get(2)),
+        ("serial", caps.get(6)),
+        ("node", caps.get(1)),
+    ];
+    let mut entry = LogEntry::from_utc_time(ts, message.as_bytes())
+        .with_spans(caps.get(3).unwrap().range(), message.range())
+        .with_precision(Precision::Milli);
+    for (key, value) in fields.iter() {
+        if let Some(value) = value {
+            entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+        }
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_audit_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.utc_timestamp().map(|x| x.to_rfc3339()),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"type=AVC msg=audit(1612345678.123:456): avc:  denied  { read } for  pid=1234 comm=\"httpd\" name=\"index.html\" scontext=system_u:system_r:httpd_t:s0 tclass=file permissive=0"[..]),
+            parse(b"node=web01 type=SYSCALL msg=audit(1612345678.123:456): arch=c000003e syscall=257 success=no exit=-13"),
+            parse(b"type=PROCTITLE msg=audit(1612345678.123:456): proctitle=\"/usr/sbin/httpd\""),
+            parse(b"type=AVC msg=audit(1612345678:456): no fraction"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Audit,
+            ),
+            Some(
+                "2021-02-03T09:47:58.123+00:00",
+            ),
+            "avc:  denied  { read } for  pid=1234 comm=\"httpd\" name=\"index.html\" scontext=system_u:system_r:httpd_t:s0 tclass=file permissive=0",
+            [
+                "type=AVC",
+                "serial=456",
+                "pid=1234",
+                "comm=httpd",
+                "name=index.html",
+                "scontext=system_u:system_r:httpd_t:s0",
+                "tclass=file",
+                "permissive=0",
+            ],
+        ),
+        (
+            Some(
+                Audit,
+            ),
+            Some(
+                "2021-02-03T09:47:58.123+00:00",
+            ),
+            "arch=c000003e syscall=257 success=no exit=-13",
+            [
+                "type=SYSCALL",
+                "serial=456",
+                "node=web01",
+                "arch=c000003e",
+                "syscall=257",
+                "success=no",
+                "exit=-13",
+            ],
+        ),
+        (
+            Some(
+                Audit,
+            ),
+            Some(
+                "2021-02-03T09:47:58.123+00:00",
+            ),
+            "proctitle=\"/usr/sbin/httpd\"",
+            [
+                "type=PROCTITLE",
+                "serial=456",
+                "proctitle=/usr/sbin/httpd",
+            ],
+        ),
+        (
+            None,
+            None,
+            "type=AVC msg=audit(1612345678:456): no fraction",
+            [
+                "type=AVC",
+                "msg=audit(1612345678:456):",
+            ],
+        ),
+    ]
+    "###
+    );
+}
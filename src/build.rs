@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref MAVEN_RE: Regex =
+        Regex::new(r#"^\[(DEBUG|INFO|WARNING|WARN|ERROR)\](?:\x20|$)"#).unwrap();
+    static ref GRADLE_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{3}))
+            \x20\[(DEBUG|INFO|LIFECYCLE|WARN|QUIET|ERROR)\]
+            \x20\[([^\]\x20]+)\]
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of Maven build output.
+///
+/// Maven prints no timestamps, the entry only carries the level of the
+/// `[INFO]` style prefix.
+///
+/// Example: `[ERROR] Failed to execute goal on project app`
+pub fn parse_maven_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = MAVEN_RE.captures(bytes)?;
+    // the level only matches ASCII
+    let level = str::from_utf8(caps.get(1).unwrap().as_bytes()).unwrap();
+    let message = caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        LogEntry::from_message_only(&bytes[message.clone()])
+            .with_message_span(message)
+            .with_level(Cow::Borrowed(level)),
+    )
+}
+
+/// Parses a line of Gradle output with `--info` or `--debug`.
+///
+/// The time of day is completed with an inferred date.  The `LIFECYCLE`
+/// and `QUIET` levels are informational and the logger category is
+/// exposed as the `category` field.
+///
+/// Example: `12:00:00.123 [LIFECYCLE] [org.gradle.internal.buildevents.BuildResultLogger] BUILD SUCCESSFUL`
+pub fn parse_gradle_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = GRADLE_RE.captures(bytes)?;
+    // everything except the message only matches ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let (h, m, s) = (num(2), num(3), num(4));
+    let date = ctx.infer_date(h, m, s);
+    let naive = naive_datetime(date.year(), date.month(), date.day(), h, m, s)?
+        + Duration::milliseconds(num(5).into());
+    let level = match text(6) {
+        "LIFECYCLE" | "QUIET" => "info",
+        level => level,
+    };
+    let message = caps.get(8).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(1).unwrap().range(), message)
+            .with_level(Cow::Borrowed(level))
+            .with_field("category", text(7)),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_build_log_entries() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.time().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"[INFO] Building app 1.0-SNAPSHOT"),
+            parse(b"[WARNING] Using platform encoding (UTF-8 actually) to copy filtered resources"),
+            parse(b"[ERROR] Failed to execute goal on project app"),
+            parse(b"[INFO]"),
+            parse(&b"12:00:00.123 [LIFECYCLE] [org.gradle.internal.buildevents.BuildResultLogger] BUILD SUCCESSFUL in 2s"[..]),
+            parse(b"12:00:01.456 [DEBUG] [org.gradle.api.Task] Task :compileJava UP-TO-DATE"),
+            parse(b"12:00:02.789 [WARN] [org.gradle.api.Project] Deprecated Gradle features were used"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Maven,
+            ),
+            None,
+            Some(
+                Info,
+            ),
+            "Building app 1.0-SNAPSHOT",
+            [],
+        ),
+        (
+            Some(
+                Maven,
+            ),
+            None,
+            Some(
+                Warning,
+            ),
+            "Using platform encoding (UTF-8 actually) to copy filtered resources",
+            [],
+        ),
+        (
+            Some(
+                Maven,
+            ),
+            None,
+            Some(
+                Error,
+            ),
+            "Failed to execute goal on project app",
+            [],
+        ),
+        (
+            Some(
+                Maven,
+            ),
+            None,
+            Some(
+                Info,
+            ),
+            "",
+            [],
+        ),
+        (
+            Some(
+                Gradle,
+            ),
+            Some(
+                "12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "BUILD SUCCESSFUL in 2s",
+            [
+                "category=org.gradle.internal.buildevents.BuildResultLogger",
+            ],
+        ),
+        (
+            Some(
+                Gradle,
+            ),
+            Some(
+                "12:00:01.456",
+            ),
+            Some(
+                Debug,
+            ),
+            "Task :compileJava UP-TO-DATE",
+            [
+                "category=org.gradle.api.Task",
+            ],
+        ),
+        (
+            Some(
+                Gradle,
+            ),
+            Some(
+                "12:00:02.789",
+            ),
+            Some(
+                Warning,
+            ),
+            "Deprecated Gradle features were used",
+            [
+                "category=org.gradle.api.Project",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+#[cfg(feature = "json")]
+use crate::json;
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CAPNSLOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([CEWNIDT])\x20\|
+            \x20([A-Za-z0-9_\-./]+):(?:\x20|$)
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line written by zap with a `caller` key, as etcd 3.4 and
+/// later do.
+#[cfg(feature = "json")]
+fn parse_zap_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = json::parse_json_log_entry(bytes, ctx)?;
+    let package = entry
+        .field("caller")?
+        .rsplit_once('/')
+        .map(|(package, _)| package.to_string());
+    Some(match package {
+        Some(package) => entry.with_field("package", package),
+        None => entry,
+    })
+}
+
+/// Parses a line of an etcd server log.
+///
+/// The capnslog text format of etcd before 3.4 is understood and, with the
+/// `json` feature, the zap format of later versions.  The level letter
+/// sets the level and the package is exposed as the `package` field.  In
+/// the zap format the package is the directory of the `caller`.
+///
+/// Example: `2021-06-01 12:00:00.123456 I | etcdserver: published member`
+pub fn parse_capnslog_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    #[cfg(feature = "json")]
+    {
+        if bytes.starts_with(b"{") {
+            return parse_zap_log_entry(bytes, ctx);
+        }
+    }
+
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = CAPNSLOG_RE.captures(&bytes[message_span.clone()])?;
+
+    let level = match &caps[1] {
+        b"C" => "critical",
+        b"E" => "error",
+        b"W" => "warning",
+        b"N" => "notice",
+        b"I" => "info",
+        b"D" => "debug",
+        _ => "trace",
+    };
+    // the package only matches ASCII
+    let package = str::from_utf8(caps.get(2).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        entry
+            .with_message(&bytes[message.clone()])
+            .with_spans(ts_span, message)
+            .with_level(Cow::Borrowed(level))
+            .with_field("package", package),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_capnslog_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01 12:00:00.123456 I | etcdserver: published {Name:infra1 ClientURLs:[http://127.0.0.1:2379]} to cluster 1c45a069f3a1d796"[..]),
+            parse(b"2021-06-01 12:00:01.000000 W | etcdserver/api/v3rpc: grpc: addrConn.createTransport failed to connect"),
+            parse(b"2021-06-01 12:00:02.000000 N | pkg/osutil: received terminated signal, shutting down..."),
+            parse(b"2021-06-01 12:00:03.000000 X | etcdserver: unknown level"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Capnslog,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123456",
+            ),
+            Some(
+                Info,
+            ),
+            "published {Name:infra1 ClientURLs:[http://127.0.0.1:2379]} to cluster 1c45a069f3a1d796",
+            [
+                "package=etcdserver",
+            ],
+        ),
+        (
+            Some(
+                Capnslog,
+            ),
+            Some(
+                "2021-06-01 12:00:01",
+            ),
+            Some(
+                Warning,
+            ),
+            "grpc: addrConn.createTransport failed to connect",
+            [
+                "package=etcdserver/api/v3rpc",
+            ],
+        ),
+        (
+            Some(
+                Capnslog,
+            ),
+            Some(
+                "2021-06-01 12:00:02",
+            ),
+            Some(
+                Info,
+            ),
+            "received terminated signal, shutting down...",
+            [
+                "package=pkg/osutil",
+            ],
+        ),
+        (
+            Some(
+                Rfc3339,
+            ),
+            Some(
+                "2021-06-01 12:00:03",
+            ),
+            None,
+            "X | etcdserver: unknown level",
+            [],
+        ),
+    ]
+    "###
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_parse_zap_log_entry() {
+    let entry = LogEntry::parse(
+        br#"{"level":"warn","ts":"2021-06-01T12:00:00.123Z","caller":"etcdserver/server.go:2065","msg":"failed to publish local member to cluster through raft","local-member-id":"8e9e05c52164694d"}"#,
+    );
+    assert_debug_snapshot!(
+        (
+            entry.format(),
+            entry.level(),
+            entry.message().to_string(),
+            entry.field("package"),
+        ),
+        @r###"
+    (
+        Some(
+            Capnslog,
+        ),
+        Some(
+            Warning,
+        ),
+        "failed to publish local member to cluster through raft",
+        Some(
+            "etcdserver",
+        ),
+    )
+    "###
+    );
+}
@@ -0,0 +1,111 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(r#"^<([A-Za-z0-9_.-]+)>"#).unwrap();
+    static ref COMPONENT_RE: Regex = Regex::new(r#"^([^:"]{1,80}):[\t ]"#).unwrap();
+    static ref HTTP_REQUEST_RE: Regex = Regex::new(
+        r#""(?:GET|HEAD|POST|PUT|PATCH|DELETE|OPTIONS|CONNECT|TRACE) [^ "]+ HTTP/[0-9.]+""#
+    )
+    .unwrap();
+}
+
+/// Maps well known programs and tags onto breadcrumb categories.
+static CATEGORIES: &[(&str, &str)] = &[
+    ("kernel", "device"),
+    ("kern", "device"),
+    ("udev", "device"),
+    ("systemd-udevd", "device"),
+    ("bluetoothd", "device"),
+    ("sshd", "auth"),
+    ("sudo", "auth"),
+    ("su", "auth"),
+    ("login", "auth"),
+    ("auth", "auth"),
+    ("authpriv", "auth"),
+    ("polkitd", "auth"),
+    ("systemd-logind", "auth"),
+    ("nginx", "http"),
+    ("httpd", "http"),
+    ("apache", "http"),
+    ("apache2", "http"),
+    ("haproxy", "http"),
+    ("envoy", "http"),
+    ("postgres", "query"),
+    ("postgresql", "query"),
+    ("mysqld", "query"),
+    ("mongod", "query"),
+    ("redis", "query"),
+    ("redis-server", "query"),
+    ("dhclient", "network"),
+    ("dhcpd", "network"),
+    ("networkmanager", "network"),
+    ("wpa_supplicant", "network"),
+    ("cron", "cron"),
+    ("crond", "cron"),
+    ("anacron", "cron"),
+];
+
+fn lookup(tag: &str) -> Option<&'static str> {
+    // strip a pid (`sshd[123]`) and reverse domain prefixes
+    // (`com.apple.xpc.launchd`)
+    let tag = tag.split('[').next().unwrap_or(tag);
+    let tag = tag.rsplit('.').next().unwrap_or(tag).to_ascii_lowercase();
+    CATEGORIES
+        .iter()
+        .find(|(name, _)| *name == tag)
+        .map(|(_, category)| *category)
+}
+
+/// Infers a breadcrumb category from the message of an entry.
+///
+/// This looks at a leading `<tag>`, the words of a leading component
+/// (`host sshd[123]: ...`) and for HTTP request lines.
+pub(crate) fn infer_category(message: &str) -> Option<&'static str> {
+    if let Some(caps) = TAG_RE.captures(message) {
+        if let Some(category) = lookup(&caps[1]) {
+            return Some(category);
+        }
+    }
+    if let Some(caps) = COMPONENT_RE.captures(message) {
+        if let Some(category) = caps[1].split_whitespace().find_map(lookup) {
+            return Some(category);
+        }
+    }
+    if HTTP_REQUEST_RE.is_match(message) {
+        return Some("http");
+    }
+    None
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_infer_category() {
+    assert_debug_snapshot!(
+        [
+            "<kernel> en0: Received EAPOL packet (length = 161)",
+            "herzog sshd[1234]: Accepted publickey for root",
+            r#"127.0.0.1 - - "GET /index.html HTTP/1.1" 200 512"#,
+            "LogInit: Selected Device Profile: [WindowsNoEditor]",
+        ]
+        .iter()
+        .map(|x| infer_category(x))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            "device",
+        ),
+        Some(
+            "auth",
+        ),
+        Some(
+            "http",
+        ),
+        None,
+    ]
+    "###
+    );
+}
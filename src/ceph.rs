@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CEPH_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9a-f]{8,16})
+            \x20+(-?[0-9]{1,2})
+            (?:
+                \x20log_channel\(([a-z_]+)\)
+                \x20log\x20\[(DBG|INF|SEC|WRN|ERR)\]\x20:
+            )?
+            (?:\x20|$)
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Ceph daemon log.
+///
+/// The thread and debug level number are exposed as the `thread` and
+/// `debug_level` fields.  Cluster log messages also carry a channel, which
+/// is exposed as the `channel` field, and a severity which sets the level.
+/// `SEC` (security) messages rank between info and warnings and are
+/// mapped to `notice`.
+///
+/// Example: `2021-06-01 12:00:00.123456 7f1234567700  0 log_channel(cluster) log [INF] : osd.0 boot`
+pub fn parse_ceph_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = CEPH_RE.captures(&bytes[message_span.clone()])?;
+
+    // everything except the message only matches ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let mut entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_field("thread", text(1))
+        .with_field("debug_level", text(2));
+    if caps.get(3).is_some() {
+        let level = match text(4) {
+            "DBG" => "debug",
+            "INF" => "info",
+            "SEC" => "notice",
+            "WRN" => "warning",
+            _ => "error",
+        };
+        entry = entry
+            .with_level(Cow::Borrowed(level))
+            .with_field("channel", text(3));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_ceph_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01 12:00:00.123456 7f1234567700  0 log_channel(cluster) log [INF] : osd.0 [v2:10.0.0.1:6800/1234,v1:10.0.0.1:6801/1234] boot"[..]),
+            parse(b"2021-06-01T12:00:01.000+0000 7f1234567700  0 log_channel(cluster) log [WRN] : Health check failed: 1 osds down (OSD_DOWN)"),
+            parse(b"2021-06-01T12:00:02.000+0000 7f1234567700 -1 osd.0 123 heartbeat_check: no reply from 10.0.0.2:6804"),
+            parse(b"2021-06-01T12:00:03.000+0000 7f1234567700 20 bluestore(/var/lib/ceph/osd/ceph-0) _txc_calc_cost"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Ceph,
+            ),
+            Some(
+                Info,
+            ),
+            "osd.0 [v2:10.0.0.1:6800/1234,v1:10.0.0.1:6801/1234] boot",
+            [
+                "thread=7f1234567700",
+                "debug_level=0",
+                "channel=cluster",
+            ],
+        ),
+        (
+            Some(
+                Ceph,
+            ),
+            Some(
+                Warning,
+            ),
+            "Health check failed: 1 osds down (OSD_DOWN)",
+            [
+                "thread=7f1234567700",
+                "debug_level=0",
+                "channel=cluster",
+            ],
+        ),
+        (
+            Some(
+                Ceph,
+            ),
+            None,
+            "osd.0 123 heartbeat_check: no reply from 10.0.0.2:6804",
+            [
+                "thread=7f1234567700",
+                "debug_level=-1",
+            ],
+        ),
+        (
+            Some(
+                Ceph,
+            ),
+            None,
+            "bluestore(/var/lib/ceph/osd/ceph-0) _txc_calc_cost",
+            [
+                "thread=7f1234567700",
+                "debug_level=20",
+            ],
+        ),
+    ]
+    "###
+    );
+}
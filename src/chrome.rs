@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CHROME_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[
+            (?:([0-9]+):)?
+            (?:([0-9]+):)?
+            (
+                (0[1-9]|1[0-2])(0[1-9]|[12][0-9]|3[01])
+                /([0-9]{2})([0-9]{2})([0-9]{2})(?:\.([0-9]{1,9}))?
+            )
+            :(VERBOSE[0-9]*|INFO|WARNING|ERROR|FATAL)
+            :([^():\]]+)\(([0-9]+)\)\]
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line written by Chrome, Chromium or an application embedding
+/// them (such as Electron or CEF).
+///
+/// The severity sets the level, with the verbose levels treated as trace.
+/// The process and thread ids and the source location are exposed as the
+/// `procid`, `tid`, `file` and `line` fields, the latter two are also
+/// returned by [`LogEntry::source_location`].  The year is inferred like
+/// for the short format.
+///
+/// Example: `[1234:5678:0601/120000.123456:ERROR:gpu_init.cc(441)] message`
+pub fn parse_chrome_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CHROME_RE.captures(bytes)?;
+    // all groups except the message only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let (month, day) = (num(4), num(5));
+    let (h, m, s) = (num(6), num(7), num(8));
+    let nanos = caps
+        .get(9)
+        .map_or(0, |x| num(9) * 10u32.pow(9 - x.as_bytes().len() as u32));
+    let year = ctx.infer_year(month, day, h, m, s);
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(nanos.into());
+
+    let level = match text(10) {
+        level if level.starts_with("VERBOSE") => "trace",
+        level => level,
+    };
+    let message = caps.get(13).unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(3).unwrap().range(), message)
+        .with_level(Cow::Borrowed(level));
+    // with a single id it is the one of the process
+    if let Some(pid) = caps.get(1) {
+        entry = entry.with_field("procid", str::from_utf8(pid.as_bytes()).unwrap());
+    }
+    if let Some(tid) = caps.get(2) {
+        entry = entry.with_field("tid", str::from_utf8(tid.as_bytes()).unwrap());
+    }
+    Some(
+        entry
+            .with_field(
+                "file",
+                String::from_utf8_lossy(&bytes[caps.get(11).unwrap().range()]),
+            )
+            .with_field("line", text(12)),
+    )
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use crate::types::NaiveTimezone;
+#[cfg(test)]
+use chrono::prelude::*;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_chrome_log_entry() {
+    let ctx = Context::new(
+        NaiveTimezone::Utc,
+        &FixedClock::new(Utc.with_ymd_and_hms(2021, 7, 1, 0, 0, 0).unwrap()),
+    );
+    let parse = |line| {
+        let entry = parse_chrome_log_entry(line, &ctx)?;
+        Some((
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"[1234:5678:0601/120000.123456:ERROR:gpu_init.cc(441)] Passthrough is not supported"),
+            parse(b"[1234:0601/120001.5:VERBOSE1:network_delegate.cc(32)] NetworkDelegate::NotifyBeforeURLRequest"),
+            parse(b"[0601/120002.000000:WARNING:dns_config_service_posix.cc(343)] Failed to read DnsConfig."),
+            parse(b"[1234:5678:0601/120000.123456:ERROR gpu_init.cc] not chrome"),
+        ],
+        @r###"
+    [
+        Some(
+            (
+                Some(
+                    Utc(
+                        2021-06-01T12:00:00.123456Z,
+                    ),
+                ),
+                Some(
+                    Error,
+                ),
+                "Passthrough is not supported",
+                [
+                    "procid=1234",
+                    "tid=5678",
+                    "file=gpu_init.cc",
+                    "line=441",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2021-06-01T12:00:01.500Z,
+                    ),
+                ),
+                Some(
+                    Trace,
+                ),
+                "NetworkDelegate::NotifyBeforeURLRequest",
+                [
+                    "procid=1234",
+                    "file=network_delegate.cc",
+                    "line=32",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2021-06-01T12:00:02Z,
+                    ),
+                ),
+                Some(
+                    Warning,
+                ),
+                "Failed to read DnsConfig.",
+                [
+                    "file=dns_config_service_posix.cc",
+                    "line=343",
+                ],
+            ),
+        ),
+        None,
+    ]
+    "###
+    );
+    let entry = LogEntry::parse(b"[1234:5678:0601/120000.123456:INFO:CONSOLE(12)] \"ready\"");
+    assert_eq!(entry.format(), Some(FormatKind::Chrome));
+    assert_eq!(entry.source_location().map(|x| x.line()), Some(12));
+}
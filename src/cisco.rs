@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::syslog;
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CISCO_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?:([0-9]+):\x20)?
+            [*.]?
+            (
+                (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+                \x20+([0-9]{1,2})
+                (?:\x20([0-9]{4}))?
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.([0-9]{3}))?
+            )
+            (?:\x20[A-Z]{3,4})?
+            :\x20
+            (
+                %([A-Z0-9_]+(?:-[A-Z0-9_]+)*)-([0-7])-([A-Z0-9_]+):
+                (?:\x20|$)
+            )
+            (.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a Cisco IOS syslog message as shown by `show logging`.
+///
+/// The optional sequence number and the `%FACILITY-SEVERITY-MNEMONIC` tag
+/// are exposed as the `sequence`, `facility`, `severity` and `mnemonic`
+/// fields.  The severity sets the level like a syslog severity.  A leading
+/// `*` or `.` (clock not synchronized) is ignored.
+///
+/// Example: `*Jun  1 12:00:00.123: %SYS-5-CONFIG_I: Configured from console by admin`
+pub fn parse_cisco_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CISCO_RE.captures(bytes)?;
+    // everything except the message only matches ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let month = get_month(&caps[3])?;
+    let (day, h, m, s) = (num(4), num(6), num(7), num(8));
+    let year = match caps.get(5) {
+        Some(_) => num(5) as i32,
+        None => ctx.infer_year(month, day, h, m, s),
+    };
+    let mut naive = naive_datetime(year, month, day, h, m, s)?;
+    if caps.get(9).is_some() {
+        naive += Duration::milliseconds(num(9).into());
+    }
+
+    let severity = num(12);
+    let message = caps.get(14).unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(2).unwrap().range(), message)
+        .with_level(Cow::Borrowed(syslog::severity_name(severity)));
+    if caps.get(1).is_some() {
+        entry = entry.with_field("sequence", text(1));
+    }
+    Some(
+        entry
+            .with_field("facility", text(11))
+            .with_field("severity", text(12))
+            .with_field("mnemonic", text(13)),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_cisco_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry
+                .local_timestamp()
+                .map(|x| x.format("%m-%d %H:%M:%S%.f").to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"*Jun  1 12:00:00.123: %SYS-5-CONFIG_I: Configured from console by admin on vty0 (10.0.0.1)"),
+            parse(&b"000123: Jun  1 2021 12:00:01.456 UTC: %LINEPROTO-5-UPDOWN: Line protocol on Interface GigabitEthernet0/1, changed state to down"[..]),
+            parse(b"000124: Jun  1 12:00:02: %SEC_LOGIN-4-LOGIN_FAILED: Login failed [user: admin]"),
+            parse(b"Jun  1 12:00:03: %ETHPORT-3-IF_DOWN_LINK_FAILURE: Interface Ethernet1/1 is down"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Cisco,
+            ),
+            Some(
+                "06-01 12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "Configured from console by admin on vty0 (10.0.0.1)",
+            [
+                "facility=SYS",
+                "severity=5",
+                "mnemonic=CONFIG_I",
+            ],
+        ),
+        (
+            Some(
+                Cisco,
+            ),
+            Some(
+                "06-01 12:00:01.456",
+            ),
+            Some(
+                Info,
+            ),
+            "Line protocol on Interface GigabitEthernet0/1, changed state to down",
+            [
+                "sequence=000123",
+                "facility=LINEPROTO",
+                "severity=5",
+                "mnemonic=UPDOWN",
+            ],
+        ),
+        (
+            Some(
+                Cisco,
+            ),
+            Some(
+                "06-01 12:00:02",
+            ),
+            Some(
+                Warning,
+            ),
+            "Login failed [user: admin]",
+            [
+                "sequence=000124",
+                "facility=SEC_LOGIN",
+                "severity=4",
+                "mnemonic=LOGIN_FAILED",
+            ],
+        ),
+        (
+            Some(
+                Cisco,
+            ),
+            Some(
+                "06-01 12:00:03",
+            ),
+            Some(
+                Error,
+            ),
+            "Interface Ethernet1/1 is down",
+            [
+                "facility=ETHPORT",
+                "severity=3",
+                "mnemonic=IF_DOWN_LINK_FAILURE",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::prelude::*;
+
+/// A source for the current time.
+///
+/// Some log formats lack the year or the date entirely.  For those the
+/// parser fills in the missing components from the clock.  Injecting a
+/// custom clock makes this inference deterministic.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> DateTime<Utc> + Send + Sync,
+{
+    fn now(&self) -> DateTime<Utc> {
+        self()
+    }
+}
+
+/// A clock that reads the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that always returns the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(DateTime<Utc>);
+
+impl FixedClock {
+    /// Creates a clock fixed at the given time.
+    pub fn new<Tz: TimeZone>(now: DateTime<Tz>) -> FixedClock {
+        FixedClock(now.with_timezone(&Utc))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// A clock that caches the time of another clock.
+///
+/// Parsers consult the clock for every line that lacks date components.
+/// For bulk parsing the time only needs to be roughly current, so this
+/// reads the wrapped clock at most once per interval.
+pub struct CachedClock {
+    inner: Arc<dyn Clock>,
+    interval: Duration,
+    cached: Mutex<Option<(Instant, DateTime<Utc>)>>,
+}
+
+impl CachedClock {
+    /// Wraps a clock so that it is read at most once per `interval`.
+    pub fn new<C: Clock + 'static>(clock: C, interval: Duration) -> CachedClock {
+        CachedClock::from_arc(Arc::new(clock), interval)
+    }
+
+    pub(crate) fn from_arc(inner: Arc<dyn Clock>, interval: Duration) -> CachedClock {
+        CachedClock {
+            inner,
+            interval,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl fmt::Debug for CachedClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedClock")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Clock for CachedClock {
+    fn now(&self) -> DateTime<Utc> {
+        let mut cached = self.cached.lock().unwrap_or_else(|err| err.into_inner());
+        match *cached {
+            Some((fetched, now)) if fetched.elapsed() < self.interval => now,
+            _ => {
+                let now = self.inner.now();
+                *cached = Some((Instant::now(), now));
+                now
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[test]
+fn test_cached_clock() {
+    let calls = Arc::new(AtomicI64::new(0));
+    let counter = calls.clone();
+    let clock = move || {
+        let secs = counter.fetch_add(1, Ordering::SeqCst);
+        Utc.timestamp_opt(secs, 0).unwrap()
+    };
+
+    let cached = CachedClock::new(clock.clone(), Duration::from_secs(3600));
+    assert_eq!(cached.now(), cached.now());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let uncached = CachedClock::new(clock, Duration::from_secs(0));
+    assert_ne!(uncached.now(), uncached.now());
+}
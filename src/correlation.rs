@@ -0,0 +1,72 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref CORRELATION_RE: Regex = Regex::new(
+        r#"(?x)
+        (?:^|[^A-Za-z0-9_-])
+        "?
+        (?i-u:
+            (?:x-)?
+            (?:
+                request[_-]?id | req[_-]?id | trace[_-]?id | correlation[_-]?id
+                | session[_-]?id | amzn[_-]trace[_-]id | cf[_-]ray
+            )
+        )
+        "?
+        [\t\x20]*[=:][\t\x20]*
+        ["']?
+        ([A-Za-z0-9][A-Za-z0-9._:/+=-]{3,})
+    "#
+    )
+    .unwrap();
+}
+
+/// Finds a request, trace or session identifier in a message.
+///
+/// Identifiers are recognized after well known keys in `key=value`,
+/// `key: value` and JSON style notation, such as `request_id=...`,
+/// `"traceId": "..."` or `X-Request-ID: ...`.
+pub(crate) fn find_correlation_id(message: &str) -> Option<&str> {
+    CORRELATION_RE
+        .captures(message)
+        .map(|caps| caps.get(1).unwrap().as_str())
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_find_correlation_id() {
+    assert_debug_snapshot!(
+        [
+            "GET /api/users status=200 request_id=5f2b9c1e-8d4a-4f3b-9a7e-1c2d3e4f5a6b",
+            r#"{"level":"info","traceId":"4bf92f3577b34da6a3ce929d0e0e4736","msg":"done"}"#,
+            "X-Request-ID: abc123def",
+            "session_id='s-0042' logged in",
+            "my_request_id_count=5 is not an id",
+            "no identifiers here",
+        ]
+        .iter()
+        .map(|x| find_correlation_id(x))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            "5f2b9c1e-8d4a-4f3b-9a7e-1c2d3e4f5a6b",
+        ),
+        Some(
+            "4bf92f3577b34da6a3ce929d0e0e4736",
+        ),
+        Some(
+            "abc123def",
+        ),
+        Some(
+            "s-0042",
+        ),
+        None,
+        None,
+    ]
+    "###
+    );
+}
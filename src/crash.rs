@@ -0,0 +1,98 @@
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref DATE_TIME_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (Date/Time:)[\t\x20]+
+            (
+                ([0-9]{4})-([0-9]{2})-([0-9]{2})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})(?:\.([0-9]{1,9}))?
+                \x20([+-])([0-9]{2})([0-9]{2})
+            )
+            [\t\x20]*
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses the `Date/Time:` header of an Apple crash report.
+///
+/// The line becomes an entry with the time of the crash, so that the rest
+/// of the report can be attached to it as continuation lines or inherit
+/// its timestamp.  The message is the `Date/Time:` label.
+///
+/// Example: `Date/Time:           2021-06-01 12:00:00.123 +0200`
+pub fn parse_crash_report_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = DATE_TIME_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let nanos = caps
+        .get(9)
+        .map_or(0, |x| num(9) * 10u32.pow(9 - x.as_bytes().len() as u32));
+    let naive = naive_datetime(num(3) as i32, num(4), num(5), num(6), num(7), num(8))?
+        + Duration::nanoseconds(nanos.into());
+    let offset = ((num(11) * 60 + num(12)) * 60) as i32;
+    let offset = FixedOffset::east_opt(if &caps[10] == b"-" { -offset } else { offset })?;
+    let ts = offset.from_local_datetime(&naive).single()?;
+
+    let message = caps.get(1).unwrap().range();
+    Some(
+        LogEntry::from_fixed_time(ts, &bytes[message.clone()])
+            .with_spans(caps.get(2).unwrap().range(), message),
+    )
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_crash_report_log_entry() {
+    let input = &b"Process:               Example [1234]\nPath:                  /Applications/Example.app/Contents/MacOS/Example\nDate/Time:             2021-06-01 12:00:00.123 +0200\nOS Version:            macOS 11.4 (20F71)\n\nException Type:        EXC_CRASH (SIGABRT)\n"[..];
+    let parser = Parser::builder().merge_continuation_lines(true).build();
+    assert_debug_snapshot!(
+        parser
+            .read_entries(input)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (
+                    entry.format(),
+                    entry.timestamp().cloned(),
+                    entry.message().to_string(),
+                )
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            None,
+            None,
+            "Process:               Example [1234]\nPath:                  /Applications/Example.app/Contents/MacOS/Example",
+        ),
+        (
+            Some(
+                CrashReport,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123+02:00,
+                ),
+            ),
+            "Date/Time:\nOS Version:            macOS 11.4 (20F71)\n\nException Type:        EXC_CRASH (SIGABRT)",
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,144 @@
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::format::FormatKind;
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CRI_RE: Regex = Regex::new(r#"^(stdout|stderr)\x20([PF])(?:\x20|$)"#).unwrap();
+}
+
+/// Parses a line written by a Kubernetes container runtime (CRI).
+///
+/// The stream and the tag (`P` for partial lines, `F` for full ones) are
+/// exposed as the `stream` and `logtag` fields.  When reading a stream the
+/// partial lines are joined with the lines that complete them.
+///
+/// Example: `2021-07-01T12:00:00.123456789Z stdout F message`
+pub fn parse_cri_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = CRI_RE.captures(&bytes[message_span.clone()])?;
+
+    // both groups only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        entry
+            .with_message(&bytes[message.clone()])
+            .with_spans(ts_span, message)
+            .with_field("stream", text(1))
+            .with_field("logtag", text(2)),
+    )
+}
+
+/// Checks if an entry is a partial CRI line.
+pub(crate) fn is_partial(entry: &LogEntry) -> bool {
+    entry.format() == Some(FormatKind::Cri) && entry.field("logtag") == Some("P")
+}
+
+/// Joins the continuation of a partial CRI line onto it.
+///
+/// Returns `false` if `next` does not continue the entry, which is the
+/// case for lines from the other stream.
+pub(crate) fn join_partial(entry: &mut LogEntry, next: &LogEntry) -> bool {
+    if next.format() != Some(FormatKind::Cri) || next.field("stream") != entry.field("stream") {
+        return false;
+    }
+    entry.append_text(next.message());
+    entry.set_field("logtag", next.field("logtag").unwrap_or("F"));
+    true
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use chrono::prelude::*;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_cri_log_entry() {
+    let entry = LogEntry::parse(b"2021-07-01T12:00:00.123456789Z stderr F actual message");
+    assert_debug_snapshot!(
+        (&entry, entry.format(), entry.fields().collect::<Vec<_>>()),
+        @r###"
+    (
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2021-07-01T12:00:00.123456789Z,
+                ),
+            ),
+            message: "actual message",
+        },
+        Some(
+            Cri,
+        ),
+        [
+            (
+                "stream",
+                "stderr",
+            ),
+            (
+                "logtag",
+                "F",
+            ),
+        ],
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_join_partial_lines() {
+    let input = &b"2021-07-01T12:00:00.1Z stdout P first \n2021-07-01T12:00:00.2Z stderr F interleaved\n2021-07-01T12:00:00.3Z stdout P second \n2021-07-01T12:00:00.4Z stdout F third\n2021-07-01T12:00:00.5Z stdout F done\n"[..];
+    let parser = Parser::builder()
+        .clock(FixedClock::new(
+            Utc.with_ymd_and_hms(2021, 7, 1, 0, 0, 0).unwrap(),
+        ))
+        .build();
+    assert_debug_snapshot!(
+        parser
+            .read_entries(input)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.message().to_string(), entry.field("logtag").map(|x| x.to_string()))
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            "first ",
+            Some(
+                "P",
+            ),
+        ),
+        (
+            "interleaved",
+            Some(
+                "F",
+            ),
+        ),
+        (
+            "second third",
+            Some(
+                "F",
+            ),
+        ),
+        (
+            "done",
+            Some(
+                "F",
+            ),
+        ),
+    ]
+    "###
+    );
+}
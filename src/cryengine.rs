@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CRYENGINE_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            <(
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})(?:\.([0-9]{3}))?
+            )>
+            \x20(.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref COLOR_RE: regex::Regex = regex::Regex::new(r#"\$[0-9]"#).unwrap();
+    static ref LEVEL_RE: Regex =
+        Regex::new(r#"^\[(Warning|Error|Fatal Error)\](?:\x20|$)"#).unwrap();
+}
+
+/// Parses a line of a CryEngine or Lumberyard log.
+///
+/// The time of day in angle brackets is completed with an inferred date.
+/// Color codes (`$1` to `$9`) are removed from the message and a leading
+/// `[Warning]` or `[Error]` sets the level.
+///
+/// Example: `<12:00:00> [Warning] $6Texture not found`
+pub fn parse_cryengine_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CRYENGINE_RE.captures(bytes)?;
+    // the time only matches ASCII
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let (h, m, s) = (num(2), num(3), num(4));
+    let ms = caps.get(5).map_or(0, |_| num(5));
+    let date = ctx.infer_date(h, m, s);
+    let naive = naive_datetime(date.year(), date.month(), date.day(), h, m, s)?
+        + Duration::milliseconds(ms.into());
+
+    let mut message = caps.get(6).unwrap().range();
+    let mut level = None;
+    if let Some(marker) = LEVEL_RE.captures(&bytes[message.clone()]) {
+        let name = str::from_utf8(marker.get(1).unwrap().as_bytes()).unwrap();
+        level = Some(if name == "Fatal Error" { "fatal" } else { name });
+        message.start += marker.get(0).unwrap().end();
+    }
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(1).unwrap().range(), message.clone());
+    if let Cow::Owned(message) = COLOR_RE.replace_all(entry.message(), "") {
+        entry = entry.with_owned_message(message);
+    }
+    Some(match level {
+        Some(level) => entry.with_level(Cow::Borrowed(level)),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_cryengine_log_entry() {
+    let input = &b"<23:59:58> $3Loading level $6Village\n<23:59:59> [Warning] $6Texture not found: textures/rock.dds\n<00:00:01.250> [Error] Shader compilation failed\n"[..];
+    assert_debug_snapshot!(
+        Parser::new()
+            .read_entries(input)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (
+                    entry.format(),
+                    entry.local_timestamp().map(|x| x.time().to_string()),
+                    entry.level(),
+                    entry.message().to_string(),
+                )
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            Some(
+                Cryengine,
+            ),
+            Some(
+                "23:59:58",
+            ),
+            None,
+            "Loading level Village",
+        ),
+        (
+            Some(
+                Cryengine,
+            ),
+            Some(
+                "23:59:59",
+            ),
+            Some(
+                Warning,
+            ),
+            "Texture not found: textures/rock.dds",
+        ),
+        (
+            Some(
+                Cryengine,
+            ),
+            Some(
+                "00:00:01.250",
+            ),
+            Some(
+                Error,
+            ),
+            "Shader compilation failed",
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,161 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CUPS_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([EWINDdACX])
+            \x20\[(
+                ([0-9]{2})/(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/([0-9]{4})
+                :([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.([0-9]{1,9}))?
+                \x20([+-])([0-9]{2})([0-9]{2})
+            )\]
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of the CUPS `error_log`.
+///
+/// The single letter prefix sets the level.  `d` is the extra verbose
+/// debug level and is mapped to `trace`.  The fraction written with
+/// `LogTimeFormat usecs` is kept.
+///
+/// Example: `E [01/Jun/2021:12:00:00 +0200] [Job 12] Unable to open printer`
+pub fn parse_cups_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CUPS_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> i32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+    let naive = naive_datetime(
+        num(5),
+        get_month(&caps[4])?,
+        num(3) as u32,
+        num(6) as u32,
+        num(7) as u32,
+        num(8) as u32,
+    )?;
+    let naive = match caps.get(9) {
+        Some(fraction) => {
+            let digits = fraction.as_bytes();
+            let value: i64 = str::from_utf8(digits).unwrap().parse().unwrap();
+            naive + Duration::nanoseconds(value * 10i64.pow(9 - digits.len() as u32))
+        }
+        None => naive,
+    };
+    let offset = (num(11) * 60 + num(12)) * 60;
+    let offset = FixedOffset::east_opt(if &caps[10] == b"-" { -offset } else { offset })?;
+    let ts = offset.from_local_datetime(&naive).single()?;
+
+    let level = match caps[1][0] {
+        b'X' => "emergency",
+        b'A' => "alert",
+        b'C' => "critical",
+        b'E' => "error",
+        b'W' => "warning",
+        b'N' => "notice",
+        b'I' => "info",
+        b'D' => "debug",
+        _ => "trace",
+    };
+    let message = caps.get(13).unwrap();
+    Some(
+        LogEntry::from_fixed_time(ts, message.as_bytes())
+            .with_spans(caps.get(2).unwrap().range(), message.range())
+            .with_level(Cow::Borrowed(level)),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_cups_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.utc_timestamp().map(|x| x.to_rfc3339()),
+            entry.level().map(|x| x.as_str()),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"E [01/Jun/2021:12:00:00 +0200] [Job 12] Unable to open printer"),
+            parse(b"W [01/Jun/2021:12:00:01 +0200] CreateProfile failed"),
+            parse(b"I [01/Jun/2021:12:00:02 +0200] Listening on localhost:631"),
+            parse(b"d [01/Jun/2021:12:00:03.123456 +0200] cupsdReadClient: 5 POST / HTTP/1.1"),
+            parse(b"Q [01/Jun/2021:12:00:04 +0200] unknown level"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Cups,
+            ),
+            Some(
+                "2021-06-01T10:00:00+00:00",
+            ),
+            Some(
+                "error",
+            ),
+            "[Job 12] Unable to open printer",
+        ),
+        (
+            Some(
+                Cups,
+            ),
+            Some(
+                "2021-06-01T10:00:01+00:00",
+            ),
+            Some(
+                "warning",
+            ),
+            "CreateProfile failed",
+        ),
+        (
+            Some(
+                Cups,
+            ),
+            Some(
+                "2021-06-01T10:00:02+00:00",
+            ),
+            Some(
+                "info",
+            ),
+            "Listening on localhost:631",
+        ),
+        (
+            Some(
+                Cups,
+            ),
+            Some(
+                "2021-06-01T10:00:03.123456+00:00",
+            ),
+            Some(
+                "debug",
+            ),
+            "cupsdReadClient: 5 POST / HTTP/1.1",
+        ),
+        (
+            None,
+            None,
+            None,
+            "Q [01/Jun/2021:12:00:04 +0200] unknown level",
+        ),
+    ]
+    "###
+    );
+}
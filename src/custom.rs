@@ -0,0 +1,823 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::ops::Range;
+use std::str;
+use std::sync::OnceLock;
+
+use chrono::format::{parse_and_remainder, Parsed, StrftimeItems};
+use chrono::prelude::*;
+use regex::bytes::{Captures, Regex};
+
+use crate::format::{FormatKind, LogFormat};
+use crate::grok;
+use crate::parser::{get_month, log_entry_from_local_time, naive_datetime, Context};
+use crate::types::LogEntry;
+
+/// The fields a custom format can extract.
+const FIELDS: &[&str] = &[
+    "year", "month", "day", "hour", "minute", "second", "offset", "level", "message",
+];
+
+/// Maps the fields of a custom format onto capture group names.
+///
+/// The keys are field names (`year`, `message`, ...), the values the names
+/// of the capture groups that hold them.
+pub type FieldMap = HashMap<String, String>;
+
+/// An error raised when a custom format cannot be compiled.
+#[derive(Debug)]
+pub enum FormatError {
+    /// The regular expression is invalid.
+    Regex { format: String, error: regex::Error },
+    /// A field is mapped to a group that does not exist in the expression.
+    MissingGroup { format: String, group: String },
+    /// A field mapping refers to a field that does not exist.
+    UnknownField { format: String, field: String },
+    /// A grok expression references an unknown pattern.
+    UnknownPattern { format: String, pattern: String },
+    /// The groups present do not make up a usable timestamp or message.
+    Incomplete {
+        format: String,
+        reason: &'static str,
+    },
+    /// The definitions could not be loaded.
+    #[cfg(feature = "config")]
+    Config(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormatError::Regex {
+                ref format,
+                ref error,
+            } => write!(f, "format {}: invalid regex: {}", format, error),
+            FormatError::MissingGroup {
+                ref format,
+                ref group,
+            } => write!(f, "format {}: no capture group named {}", format, group),
+            FormatError::UnknownField {
+                ref format,
+                ref field,
+            } => write!(f, "format {}: unknown field {}", format, field),
+            FormatError::UnknownPattern {
+                ref format,
+                ref pattern,
+            } => write!(f, "format {}: unknown grok pattern {}", format, pattern),
+            FormatError::Incomplete { ref format, reason } => {
+                write!(f, "format {}: {}", format, reason)
+            }
+            #[cfg(feature = "config")]
+            FormatError::Config(ref msg) => write!(f, "invalid format definitions: {}", msg),
+        }
+    }
+}
+
+impl error::Error for FormatError {}
+
+/// The capture group indexes of the fields of a custom format.
+#[derive(Debug, Clone, Default)]
+struct Groups {
+    year: Option<usize>,
+    month: Option<usize>,
+    day: Option<usize>,
+    hour: Option<usize>,
+    minute: Option<usize>,
+    second: Option<usize>,
+    offset: Option<usize>,
+    level: Option<usize>,
+    message: Option<usize>,
+}
+
+/// How a custom format recognizes its lines.
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex {
+        regex: Regex,
+        groups: Groups,
+    },
+    Strftime {
+        format: String,
+        prefix_len: Option<usize>,
+        prefix: String,
+        suffix: String,
+    },
+}
+
+/// A user defined log format.
+///
+/// A custom format is either a strftime layout of the timestamp at the
+/// start of the line or a regular expression whose named capture groups
+/// map onto the parts of a log entry.  The recognized groups are `year`,
+/// `month` (numeric or abbreviated name), `day`, `hour`, `minute`,
+/// `second`, `offset` (`Z`, `+hh:mm` or `+hhmm`), `level` and `message`.
+/// Missing date parts are inferred like for the built-in formats.
+///
+/// Expressions are compiled without Unicode character classes, so ASCII
+/// classes such as `[0-9]` have to be used instead of `\d`.
+#[derive(Debug, Clone)]
+pub struct CustomFormat {
+    name: String,
+    matcher: Matcher,
+}
+
+impl CustomFormat {
+    /// Compiles a custom format from a regular expression.
+    pub fn new(name: &str, regex: &str) -> Result<CustomFormat, FormatError> {
+        CustomFormat::with_fields(name, regex, &HashMap::new())
+    }
+
+    /// Like `new` but maps fields onto differently named groups.
+    ///
+    /// The keys of the mapping are field names, the values the names of
+    /// the capture groups.  Fields that are not mapped use the group of
+    /// the same name, the message also falls back to a group named `msg`.
+    pub fn with_fields(
+        name: &str,
+        regex: &str,
+        fields: &FieldMap,
+    ) -> Result<CustomFormat, FormatError> {
+        let regex = Regex::new(regex).map_err(|error| FormatError::Regex {
+            format: name.to_string(),
+            error,
+        })?;
+
+        if let Some(field) = fields.keys().find(|x| !FIELDS.contains(&x.as_str())) {
+            return Err(FormatError::UnknownField {
+                format: name.to_string(),
+                field: field.clone(),
+            });
+        }
+
+        let lookup = |field: &str| -> Result<Option<usize>, FormatError> {
+            let group = fields.get(field).map(|x| x.as_str()).unwrap_or(field);
+            match regex.capture_names().position(|x| x == Some(group)) {
+                Some(idx) => Ok(Some(idx)),
+                None if fields.contains_key(field) => Err(FormatError::MissingGroup {
+                    format: name.to_string(),
+                    group: group.to_string(),
+                }),
+                None => Ok(None),
+            }
+        };
+
+        let groups = Groups {
+            year: lookup("year")?,
+            month: lookup("month")?,
+            day: lookup("day")?,
+            hour: lookup("hour")?,
+            minute: lookup("minute")?,
+            second: lookup("second")?,
+            offset: lookup("offset")?,
+            level: lookup("level")?,
+            message: match lookup("message")? {
+                Some(idx) => Some(idx),
+                None => regex.capture_names().position(|x| x == Some("msg")),
+            },
+        };
+
+        let incomplete = |reason| {
+            Err(FormatError::Incomplete {
+                format: name.to_string(),
+                reason,
+            })
+        };
+        if groups.message.is_none() {
+            return incomplete("a message group is required");
+        }
+        if groups.hour.is_some() != groups.minute.is_some() {
+            return incomplete("hour and minute groups must be used together");
+        }
+        if groups.month.is_some() != groups.day.is_some() {
+            return incomplete("month and day groups must be used together");
+        }
+        if groups.year.is_some() && groups.month.is_none() {
+            return incomplete("a year group requires month and day groups");
+        }
+        if groups.hour.is_none()
+            && (groups.month.is_some() || groups.second.is_some() || groups.offset.is_some())
+        {
+            return incomplete("date parts require hour and minute groups");
+        }
+
+        Ok(CustomFormat {
+            name: name.to_string(),
+            matcher: Matcher::Regex { regex, groups },
+        })
+    }
+
+    /// Creates a custom format from a strftime layout.
+    ///
+    /// The line has to start with a timestamp in the given layout, for
+    /// instance `%Y-%m-%d %H:%M:%S%.3f`, and the remainder of the line
+    /// becomes the message.  If the length of the timestamp is known it
+    /// can be passed as `prefix_len` which avoids ambiguities where the
+    /// timestamp ends.  Missing date parts are inferred.
+    pub fn from_strftime(format: &str, prefix_len: Option<usize>) -> CustomFormat {
+        CustomFormat {
+            name: format.to_string(),
+            matcher: Matcher::Strftime {
+                format: format.to_string(),
+                prefix_len,
+                prefix: String::new(),
+                suffix: String::new(),
+            },
+        }
+    }
+
+    /// Like `from_strftime` but the timestamp is enclosed in literals.
+    ///
+    /// The line has to start with `prefix` and the timestamp has to be
+    /// followed by `suffix`, for instance `[` and `]` for
+    /// `[2021-06-01 12:00:00] started`.  Neither is part of the message.
+    pub fn from_strftime_delimited(format: &str, prefix: &str, suffix: &str) -> CustomFormat {
+        CustomFormat {
+            name: format!("{}{}{}", prefix, format, suffix),
+            matcher: Matcher::Strftime {
+                format: format.to_string(),
+                prefix_len: None,
+                prefix: prefix.to_string(),
+                suffix: suffix.to_string(),
+            },
+        }
+    }
+
+    /// Compiles a custom format from a grok expression.
+    ///
+    /// The standard grok pattern library is available.  Matches of
+    /// `TIMESTAMP_ISO8601`, `SYSLOGTIMESTAMP` and `HTTPDATE` become the
+    /// timestamp of the entry and the semantic names `level` and `message`
+    /// map onto the respective fields, for instance
+    /// `%{TIMESTAMP_ISO8601} %{LOGLEVEL:level} %{GREEDYDATA:message}`.
+    pub fn from_grok(name: &str, expr: &str) -> Result<CustomFormat, FormatError> {
+        let regex = grok::expand(expr).map_err(|pattern| FormatError::UnknownPattern {
+            format: name.to_string(),
+            pattern,
+        })?;
+        CustomFormat::new(name, &regex)
+    }
+}
+
+impl LogFormat for CustomFormat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parse<'a>(&self, bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+        let entry = match self.matcher {
+            Matcher::Regex {
+                ref regex,
+                ref groups,
+            } => parse_regex(regex, groups, bytes, ctx),
+            Matcher::Strftime {
+                ref format,
+                prefix_len,
+                ref prefix,
+                ref suffix,
+            } => parse_strftime(format, prefix_len, prefix, suffix, bytes, ctx),
+        };
+        Some(entry?.with_format(FormatKind::Custom))
+    }
+}
+
+impl Groups {
+    /// Returns the range covered by the timestamp groups.
+    fn timestamp_span(&self, caps: &Captures) -> Range<usize> {
+        let matches = [
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.offset,
+        ];
+        let ranges = matches.iter().filter_map(|x| caps.get((*x)?));
+        ranges
+            .fold(None, |acc: Option<Range<usize>>, m| match acc {
+                Some(acc) => Some(acc.start.min(m.start())..acc.end.max(m.end())),
+                None => Some(m.range()),
+            })
+            .unwrap_or(0..0)
+    }
+}
+
+fn parse_regex<'a>(
+    regex: &Regex,
+    groups: &Groups,
+    bytes: &'a [u8],
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    let caps = regex.captures(bytes)?;
+    let message = caps.get(groups.message?)?;
+
+    let mut entry = match groups.hour {
+        Some(hour) => {
+            let h = num(&caps, Some(hour))?;
+            let m = num(&caps, groups.minute)?;
+            let s = num(&caps, groups.second).unwrap_or(0);
+            let (year, month, day) = match groups.month {
+                Some(month) => {
+                    let month = get_month(caps.get(month)?.as_bytes())
+                        .or_else(|| num(&caps, Some(month)))?;
+                    let day = num(&caps, groups.day)?;
+                    let year = match num::<i32>(&caps, groups.year) {
+                        Some(year) if year < 100 => year + 2000,
+                        Some(year) => year,
+                        None => ctx.infer_year(month, day, h, m, s),
+                    };
+                    (year, month, day)
+                }
+                None => {
+                    let date = ctx.infer_date(h, m, s);
+                    (date.year(), date.month(), date.day())
+                }
+            };
+            let entry = match groups.offset.and_then(|x| caps.get(x)) {
+                Some(offset) => LogEntry::from_fixed_time(
+                    parse_offset(offset.as_bytes())?
+                        .from_local_datetime(&naive_datetime(year, month, day, h, m, s)?)
+                        .single()?,
+                    message.as_bytes(),
+                ),
+                None => {
+                    log_entry_from_local_time(ctx, year, month, day, h, m, s, message.as_bytes())?
+                }
+            };
+            entry.with_spans(groups.timestamp_span(&caps), message.range())
+        }
+        None => LogEntry::from_message_only(message.as_bytes()).with_message_span(message.range()),
+    };
+
+    if let Some(level) = groups.level.and_then(|x| caps.get(x)) {
+        entry = entry.with_level(String::from_utf8_lossy(level.as_bytes()));
+    }
+    Some(entry)
+}
+
+fn parse_strftime<'a>(
+    format: &str,
+    prefix_len: Option<usize>,
+    prefix: &str,
+    suffix: &str,
+    bytes: &'a [u8],
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    if !bytes.starts_with(prefix.as_bytes()) {
+        return None;
+    }
+    let timestamp_start = prefix.len();
+    let head = match prefix_len {
+        Some(len) => bytes.get(timestamp_start..timestamp_start + len)?,
+        None => &bytes[timestamp_start..],
+    };
+    let head = match str::from_utf8(head) {
+        Ok(head) => head,
+        Err(err) if prefix_len.is_none() => str::from_utf8(&head[..err.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    let mut parsed = Parsed::new();
+    let rest = parse_and_remainder(&mut parsed, head, StrftimeItems::new(format)).ok()?;
+    if prefix_len.is_some() && !rest.is_empty() {
+        return None;
+    }
+
+    let time = parsed.to_naive_time().ok()?;
+    let (h, m) = (time.hour(), time.minute());
+    let s = time.second() + time.nanosecond() / 1_000_000_000;
+    let date = match parsed.to_naive_date() {
+        Ok(date) => date,
+        Err(_) => match (parsed.year(), parsed.month(), parsed.day()) {
+            (None, Some(month), Some(day)) => {
+                NaiveDate::from_ymd_opt(ctx.infer_year(month, day, h, m, s), month, day)?
+            }
+            (None, None, None) => ctx.infer_date(h, m, s),
+            _ => return None,
+        },
+    };
+
+    let timestamp_end = timestamp_start + head.len() - rest.len();
+    if !bytes[timestamp_end..].starts_with(suffix.as_bytes()) {
+        return None;
+    }
+    let message_start = timestamp_end
+        + suffix.len()
+        + bytes[timestamp_end + suffix.len()..]
+            .iter()
+            .take_while(|x| **x == b' ' || **x == b'\t')
+            .count();
+    let message = &bytes[message_start..];
+    let (year, month, day) = (date.year(), date.month(), date.day());
+    let entry = match parsed.offset() {
+        Some(offset) => LogEntry::from_fixed_time(
+            FixedOffset::east_opt(offset)?
+                .from_local_datetime(&naive_datetime(year, month, day, h, m, s)?)
+                .single()?,
+            message,
+        ),
+        None => log_entry_from_local_time(ctx, year, month, day, h, m, s, message)?,
+    };
+    Some(entry.with_spans(timestamp_start..timestamp_end, message_start..bytes.len()))
+}
+
+fn num<T: str::FromStr>(caps: &Captures, idx: Option<usize>) -> Option<T> {
+    str::from_utf8(caps.get(idx?)?.as_bytes())
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Parses a `Z`, `+hh:mm`, `+hhmm` or `+hh` offset.
+fn parse_offset(bytes: &[u8]) -> Option<FixedOffset> {
+    if bytes == b"Z" || bytes == b"z" {
+        return FixedOffset::east_opt(0);
+    }
+    let sign = match bytes.first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits: Vec<u8> = bytes[1..].iter().cloned().filter(|x| *x != b':').collect();
+    let digits = str::from_utf8(&digits).ok()?;
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// A custom format defined at compile time.
+///
+/// Values of this type are created with the
+/// [`define_log_format!`](crate::define_log_format) macro.
+/// The expression is compiled once on first use and shared by all parsers
+/// the format is registered with.
+pub struct StaticFormat {
+    name: &'static str,
+    regex: &'static str,
+    fields: &'static [(&'static str, &'static str)],
+    compiled: OnceLock<CustomFormat>,
+}
+
+impl StaticFormat {
+    #[doc(hidden)]
+    pub const fn new(
+        name: &'static str,
+        regex: &'static str,
+        fields: &'static [(&'static str, &'static str)],
+    ) -> StaticFormat {
+        StaticFormat {
+            name,
+            regex,
+            fields,
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// Returns the compiled format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the definition is invalid, just like an invalid regex in
+    /// a `lazy_static!` block would.
+    pub fn get(&self) -> &CustomFormat {
+        self.compiled.get_or_init(|| {
+            let fields = self
+                .fields
+                .iter()
+                .map(|(field, group)| (field.to_string(), group.to_string()))
+                .collect();
+            CustomFormat::with_fields(self.name, self.regex, &fields)
+                .unwrap_or_else(|err| panic!("{}", err))
+        })
+    }
+}
+
+impl fmt::Debug for StaticFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticFormat")
+            .field("name", &self.name)
+            .field("regex", &self.regex)
+            .finish()
+    }
+}
+
+impl<'a> From<&'a StaticFormat> for CustomFormat {
+    fn from(format: &'a StaticFormat) -> CustomFormat {
+        format.get().clone()
+    }
+}
+
+/// Defines a custom format at compile time.
+///
+/// The format is declared as a static with a name, a regular expression and
+/// an optional mapping of fields onto capture groups, see [`CustomFormat`]
+/// for the recognized fields.  It can then be registered with
+/// [`ParserBuilder::custom_format`](crate::ParserBuilder::custom_format):
+///
+/// ```
+/// anylog::define_log_format! {
+///     /// The log format of our service.
+///     pub static SERVICE_FORMAT: "service" =
+///         r"^\[(?P<h>[0-9]{2}):(?P<m>[0-9]{2})\] (?P<message>.*)$"
+///         { hour => "h", minute => "m" };
+/// }
+///
+/// let parser = anylog::Parser::builder()
+///     .custom_format(&SERVICE_FORMAT)
+///     .build();
+/// ```
+#[macro_export]
+macro_rules! define_log_format {
+    ($(#[$attr:meta])* $vis:vis static $ident:ident: $name:literal = $regex:literal
+     $({ $($field:ident => $group:literal),* $(,)? })?;) => {
+        $(#[$attr])*
+        $vis static $ident: $crate::StaticFormat = $crate::StaticFormat::new(
+            $name,
+            $regex,
+            &[$($((stringify!($field), $group)),*)?],
+        );
+    };
+}
+
+#[cfg(feature = "config")]
+mod config {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::{CustomFormat, FormatError};
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Definitions {
+        #[serde(default)]
+        formats: Vec<Definition>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Definition {
+        name: String,
+        regex: Option<String>,
+        grok: Option<String>,
+        #[serde(default)]
+        fields: HashMap<String, String>,
+    }
+
+    impl Definition {
+        fn compile(&self) -> Result<CustomFormat, FormatError> {
+            match (&self.regex, &self.grok) {
+                (Some(regex), None) => CustomFormat::with_fields(&self.name, regex, &self.fields),
+                (None, Some(expr)) if self.fields.is_empty() => {
+                    CustomFormat::from_grok(&self.name, expr)
+                }
+                (None, Some(_)) => Err(FormatError::Config(format!(
+                    "format {}: fields cannot be mapped for grok expressions",
+                    self.name
+                ))),
+                _ => Err(FormatError::Config(format!(
+                    "format {}: exactly one of regex or grok is required",
+                    self.name
+                ))),
+            }
+        }
+    }
+
+    fn compile(definitions: Definitions) -> Result<Vec<CustomFormat>, FormatError> {
+        definitions
+            .formats
+            .iter()
+            .map(Definition::compile)
+            .collect()
+    }
+
+    impl CustomFormat {
+        /// Loads format definitions from TOML.
+        ///
+        /// The document holds a `formats` array of tables with a `name`, a
+        /// `regex` and an optional `fields` table mapping fields onto
+        /// capture groups.  Instead of a `regex` a `grok` expression can be
+        /// given:
+        ///
+        /// ```toml
+        /// [[formats]]
+        /// name = "myservice"
+        /// regex = '^(?P<hour>[0-9]{2}):(?P<minute>[0-9]{2}) (?P<message>.*)$'
+        ///
+        /// [[formats]]
+        /// name = "myapp"
+        /// grok = '^%{SYSLOGTIMESTAMP} %{LOGLEVEL:level}: %{GREEDYDATA:message}$'
+        /// ```
+        pub fn from_toml(source: &str) -> Result<Vec<CustomFormat>, FormatError> {
+            compile(toml::from_str(source).map_err(|err| FormatError::Config(err.to_string()))?)
+        }
+
+        /// Loads format definitions from JSON.
+        ///
+        /// The structure is the same as for `from_toml`.
+        pub fn from_json(source: &str) -> Result<Vec<CustomFormat>, FormatError> {
+            compile(
+                serde_json::from_str(source).map_err(|err| FormatError::Config(err.to_string()))?,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::parser::test_ctx;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_custom_format() {
+    let format = CustomFormat::new(
+        "service",
+        r"^(?P<level>[A-Z]+) (?P<day>[0-9][0-9])/(?P<month>[A-Za-z]{3}) (?P<hour>[0-9][0-9]):(?P<minute>[0-9][0-9]):(?P<second>[0-9][0-9]) (?P<message>.*)$",
+    )
+    .unwrap();
+    let entry = format
+        .parse(b"WARN 21/Nov 00:30:05 disk almost full", &test_ctx())
+        .unwrap();
+    assert_debug_snapshot!((entry.level_name(), entry.timestamp_span(), &entry), @r###"
+    (
+        Some(
+            "WARN",
+        ),
+        Some(
+            5..20,
+        ),
+        LogEntry {
+            timestamp: Some(
+                Local(
+                    2017-11-21T00:30:05+01:00,
+                ),
+            ),
+            message: "disk almost full",
+        },
+    )
+    "###);
+}
+
+#[test]
+fn test_custom_format_errors() {
+    assert_debug_snapshot!(CustomFormat::new("broken", r"^(?P<message>.*").unwrap_err().to_string(), @r#""format broken: invalid regex: regex parse error:\n    ^(?P<message>.*\n     ^\nerror: unclosed group""#);
+    assert_debug_snapshot!(CustomFormat::new("no-message", r"^(?P<hour>[0-9][0-9]):(?P<minute>[0-9][0-9])").unwrap_err().to_string(), @r#""format no-message: a message group is required""#);
+    assert_debug_snapshot!(CustomFormat::new("no-time", r"^(?P<month>[0-9][0-9])-(?P<day>[0-9][0-9]) (?P<message>.*)").unwrap_err().to_string(), @r#""format no-time: date parts require hour and minute groups""#);
+}
+
+#[test]
+fn test_strftime_format() {
+    let parse = |format, prefix_len, bytes| {
+        let entry = CustomFormat::from_strftime(format, prefix_len).parse(bytes, &test_ctx());
+        entry.map(|x| (x.timestamp_span(), x.into_owned()))
+    };
+    assert_debug_snapshot!(parse("%Y-%m-%d %H:%M:%S%.3f", None, b"2021-06-01 12:00:00.123 started"), @r###"
+    Some(
+        (
+            Some(
+                0..23,
+            ),
+            LogEntry {
+                timestamp: Some(
+                    Local(
+                        2021-06-01T12:00:00+02:00,
+                    ),
+                ),
+                message: "started",
+            },
+        ),
+    )
+    "###);
+    assert_debug_snapshot!(parse("%d.%m. %H:%M:%S", None, b"21.11. 00:30:05 no year"), @r###"
+    Some(
+        (
+            Some(
+                0..15,
+            ),
+            LogEntry {
+                timestamp: Some(
+                    Local(
+                        2017-11-21T00:30:05+01:00,
+                    ),
+                ),
+                message: "no year",
+            },
+        ),
+    )
+    "###);
+    assert_debug_snapshot!(parse("%Y%m%d%H%M%S", Some(14), b"20210601120000 tight"), @r###"
+    Some(
+        (
+            Some(
+                0..14,
+            ),
+            LogEntry {
+                timestamp: Some(
+                    Local(
+                        2021-06-01T12:00:00+02:00,
+                    ),
+                ),
+                message: "tight",
+            },
+        ),
+    )
+    "###);
+    assert_debug_snapshot!(parse("%Y-%m-%d", None, b"2021-06-01 no time"), @"None");
+}
+
+#[test]
+fn test_strftime_delimited_format() {
+    let format = CustomFormat::from_strftime_delimited("%Y-%m-%d %H:%M:%S", "[", "]");
+    let entry = format.parse(b"[2021-06-01 12:00:00] started", &test_ctx());
+    assert_debug_snapshot!(entry.map(|x| (x.timestamp_span(), x.into_owned())), @r###"
+    Some(
+        (
+            Some(
+                1..20,
+            ),
+            LogEntry {
+                timestamp: Some(
+                    Local(
+                        2021-06-01T12:00:00+02:00,
+                    ),
+                ),
+                message: "started",
+            },
+        ),
+    )
+    "###);
+    assert!(format
+        .parse(b"2021-06-01 12:00:00 started", &test_ctx())
+        .is_none());
+    assert!(format
+        .parse(b"[2021-06-01 12:00:00 started", &test_ctx())
+        .is_none());
+}
+
+#[test]
+fn test_define_log_format() {
+    define_log_format! {
+        static TEST_FORMAT: "test" =
+            r"^(?P<l>[A-Z]+) (?P<hour>[0-9]{2}):(?P<minute>[0-9]{2}) (?P<message>.*)$"
+            { level => "l" };
+    }
+    let entry = TEST_FORMAT
+        .get()
+        .parse(b"INFO 12:30 ready", &test_ctx())
+        .unwrap();
+    assert_debug_snapshot!((TEST_FORMAT.get().name(), entry.level_name(), &entry), @r###"
+    (
+        "test",
+        Some(
+            "INFO",
+        ),
+        LogEntry {
+            timestamp: Some(
+                Local(
+                    2017-01-01T12:30:00+01:00,
+                ),
+            ),
+            message: "ready",
+        },
+    )
+    "###);
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_load_formats() {
+    let formats = CustomFormat::from_toml(
+        r#"
+        [[formats]]
+        name = "service"
+        regex = '^(?P<y>[0-9]{4})-(?P<m>[0-9][0-9])-(?P<d>[0-9][0-9]) (?P<hour>[0-9][0-9]):(?P<minute>[0-9][0-9]) (?P<msg>.*)$'
+        fields = { year = "y", month = "m", day = "d", message = "msg" }
+        "#,
+    )
+    .unwrap();
+    let parser = crate::parser::Parser::builder()
+        .custom_formats(formats)
+        .build();
+    assert_debug_snapshot!(parser.parse(b"2015-05-13 17:39 Hello World"), @r###"
+    LogEntry {
+        timestamp: Some(
+            Local(
+                2015-05-13T17:39:00+02:00,
+            ),
+        ),
+        message: "Hello World",
+    }
+    "###);
+    assert_debug_snapshot!(
+        CustomFormat::from_json(r#"{"formats": [{"name": "bad", "regex": "(?P<message>.*)", "fields": {"msg": "message"}}]}"#)
+            .unwrap_err()
+            .to_string(),
+        @r#""format bad: unknown field msg""#
+    );
+}
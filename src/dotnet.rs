@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::{Captures, Regex};
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref SERILOG_CONSOLE_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[
+            (?P<ts>
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})(?:\.(?P<frac>[0-9]{1,9}))?
+            )
+            \x20(?P<level>VRB|DBG|INF|WRN|ERR|FTL)\]
+            (?:\x20|$)(?P<msg>.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref SERILOG_FILE_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?P<ts>
+                (?P<year>[0-9]{4})-(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+                \x20
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})(?:\.(?P<frac>[0-9]{1,9}))?
+                \x20(?P<sign>[+-])(?P<oh>[0-9]{2}):(?P<om>[0-9]{2})
+            )
+            \x20\[(?P<level>VRB|DBG|INF|WRN|ERR|FTL)\]
+            (?:\x20|$)(?P<msg>.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref NLOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?P<ts>
+                (?P<year>[0-9]{4})-(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+                \x20
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})(?:\.(?P<frac>[0-9]{1,9}))?
+            )
+            \|(?P<level>TRACE|DEBUG|INFO|WARN|ERROR|FATAL)
+            \|(?P<logger>[^|]*)
+            \|(?P<msg>.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+fn text<'a>(caps: &Captures<'a>, name: &str) -> Option<&'a str> {
+    caps.name(name)
+        .map(|x| str::from_utf8(x.as_bytes()).unwrap())
+}
+
+fn num<T: str::FromStr>(caps: &Captures, name: &str) -> Option<T> {
+    text(caps, name)?.parse().ok()
+}
+
+fn log_entry_from_caps<'a>(
+    bytes: &'a [u8],
+    caps: Captures<'a>,
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    let (h, m, s) = (num(&caps, "h")?, num(&caps, "m")?, num(&caps, "s")?);
+    let (year, month, day) = match num(&caps, "year") {
+        Some(year) => (year, num(&caps, "month")?, num(&caps, "day")?),
+        None => {
+            let date = ctx.infer_date(h, m, s);
+            (date.year(), date.month(), date.day())
+        }
+    };
+    let nanos = text(&caps, "frac").map_or(Some(0), |frac| {
+        Some(frac.parse::<u32>().ok()? * 10u32.pow(9 - frac.len() as u32))
+    })?;
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(nanos.into());
+
+    let message = caps.name("msg").unwrap().range();
+    let entry = match text(&caps, "sign") {
+        Some(sign) => {
+            let offset = (num::<i32>(&caps, "oh")? * 60 + num::<i32>(&caps, "om")?) * 60;
+            let offset = FixedOffset::east_opt(if sign == "-" { -offset } else { offset })?;
+            let ts = offset.from_local_datetime(&naive).single()?;
+            LogEntry::from_fixed_time(ts, &bytes[message.clone()])
+        }
+        None => ctx.local_entry(naive, &bytes[message.clone()])?,
+    };
+    let entry = entry
+        .with_spans(caps.name("ts").unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(&caps, "level")?));
+    Some(match caps.name("logger") {
+        Some(logger) => entry.with_field("logger", String::from_utf8_lossy(logger.as_bytes())),
+        None => entry,
+    })
+}
+
+/// Parses a line written with the default Serilog or NLog output templates.
+///
+/// Both the Serilog console template (time of day only) and its file
+/// template (with date and offset) are understood, as is the default NLog
+/// layout, whose logger name is exposed as the `logger` field.  The level
+/// codes (`INF`, `WRN`, ...) set the level.
+///
+/// Example: `2021-06-01 12:00:00.123 +02:00 [ERR] message`
+pub fn parse_serilog_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SERILOG_CONSOLE_RE
+        .captures(bytes)
+        .or_else(|| SERILOG_FILE_RE.captures(bytes))
+        .or_else(|| NLOG_RE.captures(bytes))?;
+    log_entry_from_caps(bytes, caps, ctx)
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_serilog_log_entry() {
+    let parser = Parser::builder()
+        .clock(FixedClock::new(
+            Utc.with_ymd_and_hms(2021, 6, 1, 18, 0, 0).unwrap(),
+        ))
+        .build();
+    let parse = |line| {
+        let entry = parser.parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"[12:00:00 INF] message"[..]),
+            parse(b"2021-06-01 12:00:00.123 +02:00 [ERR] message"),
+            parse(b"2021-06-01 12:00:00.123 -05:00 [VRB] details"),
+            parse(b"2021-06-01 12:00:00.1234|WARN|MyApp.Program|careful"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Serilog,
+            ),
+            Some(
+                Local(
+                    2021-06-01T12:00:00+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "message",
+            [],
+        ),
+        (
+            Some(
+                Serilog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123+02:00,
+                ),
+            ),
+            Some(
+                Error,
+            ),
+            "message",
+            [],
+        ),
+        (
+            Some(
+                Serilog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123-05:00,
+                ),
+            ),
+            Some(
+                Trace,
+            ),
+            "details",
+            [],
+        ),
+        (
+            Some(
+                Serilog,
+            ),
+            Some(
+                Local(
+                    2021-06-01T12:00:00.123400+02:00,
+                ),
+            ),
+            Some(
+                Warning,
+            ),
+            "careful",
+            [
+                "logger=MyApp.Program",
+            ],
+        ),
+    ]
+    "###
+    );
+}
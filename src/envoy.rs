@@ -0,0 +1,251 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref ACCESS_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[([0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(?:\.[0-9]{1,9})?Z)\]
+            \x20(
+                "([A-Z]+|-)\x20([^\x20"]+)\x20([^\x20"]+)"
+                \x20([0-9]{1,3})
+                \x20([A-Z,]+|-)
+                \x20([0-9]+)
+                \x20([0-9]+)
+                \x20([0-9]+)
+                \x20([0-9]+|-)
+                \x20"([^"]*)"
+                \x20"([^"]*)"
+                \x20"([^"]*)"
+                \x20"([^"]*)"
+                \x20"([^"]*)"
+                .*
+            )
+        $
+    "#
+    )
+    .unwrap();
+    static ref APP_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[(
+                ([0-9]{4})-([0-9]{2})-([0-9]{2})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{3})
+            )\]
+            \[([0-9]+)\]
+            \[(trace|debug|info|warning|error|critical)\]
+            \[([a-z0-9_]+)\]
+            (?:\x20\[([^\]:]+):([0-9]+)\])?
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of an Envoy (or Istio sidecar) access log in the default
+/// format.
+///
+/// The message is the part after the timestamp, starting with the request
+/// line.  The request method, path and protocol, status, response flags,
+/// byte counts, durations, forwarded for header, user agent, request id,
+/// authority and upstream host are exposed as the `method`, `path`,
+/// `protocol`, `status`, `response_flags`, `bytes_received`, `bytes_sent`,
+/// `duration`, `upstream_service_time`, `forwarded_for`, `user_agent`,
+/// `request_id`, `authority` and `upstream_host` fields.  Missing values
+/// (`-`) are left out.
+///
+/// Example: `[2021-06-01T12:00:00.123Z] "GET / HTTP/1.1" 200 - 0 612 3 2 "-" "curl/7.68.0" "..." "example.com" "10.0.0.5:80"`
+pub fn parse_envoy_access_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = ACCESS_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let ts = DateTime::parse_from_rfc3339(str::from_utf8(&caps[1]).unwrap()).ok()?;
+
+    let message = caps.get(2).unwrap();
+    let mut entry = LogEntry::from_utc_time(ts.with_timezone(&Utc), message.as_bytes())
+        .with_spans(caps.get(1).unwrap().range(), message.range());
+    let fields = [
+        ("method", caps.get(3)),
+        ("path", caps.get(4)),
+        ("protocol", caps.get(5)),
+        ("status", caps.get(6)),
+        ("response_flags", caps.get(7)),
+        ("bytes_received", caps.get(8)),
+        ("bytes_sent", caps.get(9)),
+        ("duration", caps.get(10)),
+        ("upstream_service_time", caps.get(11)),
+        ("forwarded_for", caps.get(12)),
+        ("user_agent", caps.get(13)),
+        ("request_id", caps.get(14)),
+        ("authority", caps.get(15)),
+        ("upstream_host", caps.get(16)),
+    ];
+    for (key, value) in fields.iter() {
+        let value = match value {
+            Some(value) if value.as_bytes() != b"-" => value,
+            _ => continue,
+        };
+        entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+    }
+    Some(entry)
+}
+
+/// Parses a line of Envoy's own log in the default format.
+///
+/// The level sets the level and the thread id and logger are exposed as
+/// the `tid` and `component` fields.  The source location Envoy prints
+/// since 1.16 is exposed as the `file` and `line` fields, which are also
+/// returned by [`LogEntry::source_location`].
+///
+/// Example: `[2021-06-01 12:00:00.123][12][info][main] [source/server/server.cc:123] starting main dispatch loop`
+pub fn parse_envoy_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = APP_RE.captures(bytes)?;
+    // all groups except the message only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let naive = naive_datetime(num(2) as i32, num(3), num(4), num(5), num(6), num(7))?
+        + Duration::milliseconds(num(8).into());
+    let message = caps.get(14).unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(1).unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(10)))
+        .with_field("tid", text(9))
+        .with_field("component", text(11));
+    if caps.get(12).is_some() {
+        entry = entry
+            .with_field("file", text(12))
+            .with_field("line", text(13));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_envoy_log_entries() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&br#"[2021-06-01T12:00:00.123Z] "GET /status HTTP/1.1" 200 - 0 612 3 2 "-" "curl/7.68.0" "4d3b3a1e-8f4c-4b0a-9d2e-1c2b3a4d5e6f" "example.com" "10.0.0.5:80""#[..]),
+            parse(br#"[2021-06-01T12:00:01.456Z] "POST /api HTTP/2" 503 UF,URX 120 91 30 - "10.0.0.1" "Go-http-client/2.0" "-" "api.internal" "-""#),
+            parse(b"[2021-06-01 12:00:02.789][12][info][main] [source/server/server.cc:123] starting main dispatch loop"),
+            parse(b"[2021-06-01 12:00:03.000][34][warning][config] gRPC config stream closed: 14, upstream connect error"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                EnvoyAccess,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.123Z,
+                ),
+            ),
+            None,
+            "\"GET /status HTTP/1.1\" 200 - 0 612 3 2 \"-\" \"curl/7.68.0\" \"4d3b3a1e-8f4c-4b0a-9d2e-1c2b3a4d5e6f\" \"example.com\" \"10.0.0.5:80\"",
+            [
+                "method=GET",
+                "path=/status",
+                "protocol=HTTP/1.1",
+                "status=200",
+                "bytes_received=0",
+                "bytes_sent=612",
+                "duration=3",
+                "upstream_service_time=2",
+                "user_agent=curl/7.68.0",
+                "request_id=4d3b3a1e-8f4c-4b0a-9d2e-1c2b3a4d5e6f",
+                "authority=example.com",
+                "upstream_host=10.0.0.5:80",
+            ],
+        ),
+        (
+            Some(
+                EnvoyAccess,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:01.456Z,
+                ),
+            ),
+            None,
+            "\"POST /api HTTP/2\" 503 UF,URX 120 91 30 - \"10.0.0.1\" \"Go-http-client/2.0\" \"-\" \"api.internal\" \"-\"",
+            [
+                "method=POST",
+                "path=/api",
+                "protocol=HTTP/2",
+                "status=503",
+                "response_flags=UF,URX",
+                "bytes_received=120",
+                "bytes_sent=91",
+                "duration=30",
+                "forwarded_for=10.0.0.1",
+                "user_agent=Go-http-client/2.0",
+                "authority=api.internal",
+            ],
+        ),
+        (
+            Some(
+                Envoy,
+            ),
+            Some(
+                Local(
+                    2021-06-01T12:00:02.789+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "starting main dispatch loop",
+            [
+                "tid=12",
+                "component=main",
+                "file=source/server/server.cc",
+                "line=123",
+            ],
+        ),
+        (
+            Some(
+                Envoy,
+            ),
+            Some(
+                Local(
+                    2021-06-01T12:00:03+02:00,
+                ),
+            ),
+            Some(
+                Warning,
+            ),
+            "gRPC config stream closed: 14, upstream connect error",
+            [
+                "tid=34",
+                "component=config",
+            ],
+        ),
+    ]
+    "###
+    );
+}
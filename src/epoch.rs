@@ -0,0 +1,353 @@
+use std::ops::RangeInclusive;
+use std::str;
+
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::format::FormatKind;
+use crate::parser::Context;
+use crate::types::{LogEntry, Precision};
+
+lazy_static! {
+    static ref EPOCH_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[?
+            ([0-9]{1,19})
+            (?:\.([0-9]{1,9}))?
+            \]?
+            (?:[\t\x20]+|:[\t\x20]*|$)
+            (.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// The unit of an epoch timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EpochUnit {
+    /// Seconds since the epoch.
+    Seconds,
+    /// Milliseconds since the epoch.
+    Millis,
+    /// Microseconds since the epoch.
+    Micros,
+    /// Nanoseconds since the epoch.
+    Nanos,
+}
+
+impl EpochUnit {
+    fn nanos(self) -> i64 {
+        match self {
+            EpochUnit::Seconds => 1_000_000_000,
+            EpochUnit::Millis => 1_000_000,
+            EpochUnit::Micros => 1_000,
+            EpochUnit::Nanos => 1,
+        }
+    }
+
+    fn fraction_digits(self) -> usize {
+        match self {
+            EpochUnit::Seconds => 0,
+            EpochUnit::Millis => 3,
+            EpochUnit::Micros => 6,
+            EpochUnit::Nanos => 9,
+        }
+    }
+}
+
+/// Which numbers without a fractional part are read as timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BareIntegers {
+    Never,
+    FullWidth,
+    Always,
+}
+
+/// Controls how numbers at the start of a line are read as epoch
+/// timestamps.
+///
+/// Lines that merely start with a large number (a request id, a byte
+/// count) can look like epoch timestamps.  The unit is picked by the
+/// number of integer digits and the resulting time must fall into a
+/// plausible year range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochHeuristics {
+    years: RangeInclusive<i32>,
+    bare_integers: BareIntegers,
+    units: Vec<(RangeInclusive<usize>, EpochUnit)>,
+}
+
+impl Default for EpochHeuristics {
+    fn default() -> EpochHeuristics {
+        EpochHeuristics {
+            years: 2000..=2100,
+            bare_integers: BareIntegers::FullWidth,
+            units: vec![
+                (9..=10, EpochUnit::Seconds),
+                (12..=13, EpochUnit::Millis),
+                (15..=16, EpochUnit::Micros),
+                (18..=19, EpochUnit::Nanos),
+            ],
+        }
+    }
+}
+
+impl EpochHeuristics {
+    /// Only accepts timestamps within the given years.
+    ///
+    /// Defaults to 2000 through 2100.
+    pub fn year_range(mut self, years: RangeInclusive<i32>) -> EpochHeuristics {
+        self.years = years;
+        self
+    }
+
+    /// Accepts numbers without a fractional part of any mapped width.
+    ///
+    /// By default such numbers are only accepted with the full width of a
+    /// sub-second unit (13, 16 or 19 digits), as shorter ones are easily a
+    /// count like `1000000000 bytes transferred`.  When disabled only
+    /// numbers like `1612345678.123` are considered, which are far less
+    /// likely to be something other than a timestamp.
+    pub fn bare_integers(mut self, yes: bool) -> EpochHeuristics {
+        self.bare_integers = if yes {
+            BareIntegers::Always
+        } else {
+            BareIntegers::Never
+        };
+        self
+    }
+
+    /// Maps numbers with the given count of integer digits to a unit.
+    ///
+    /// This replaces earlier mappings for these lengths.  Numbers with a
+    /// digit count that maps to no unit are not treated as timestamps.
+    pub fn unit_for_digits(
+        mut self,
+        digits: RangeInclusive<usize>,
+        unit: EpochUnit,
+    ) -> EpochHeuristics {
+        self.units
+            .retain(|(range, _)| range.end() < digits.start() || range.start() > digits.end());
+        self.units.push((digits, unit));
+        self
+    }
+
+    fn unit(&self, digits: usize, bare: bool) -> Option<EpochUnit> {
+        let (range, unit) = self
+            .units
+            .iter()
+            .find(|(range, _)| range.contains(&digits))?;
+        let accepted = match self.bare_integers {
+            _ if !bare => true,
+            BareIntegers::Never => false,
+            BareIntegers::FullWidth => *unit != EpochUnit::Seconds && digits == *range.end(),
+            BareIntegers::Always => true,
+        };
+        if accepted {
+            Some(*unit)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a line starting with an epoch timestamp.
+    pub(crate) fn parse<'a>(&self, bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+        let caps = EPOCH_RE.captures(bytes)?;
+        let int = caps.get(1)?;
+        let ts_span = int.start()..caps.get(2).unwrap_or(int).end();
+        let fraction = caps.get(2).map(|x| x.as_bytes());
+        let unit = self.unit(int.as_bytes().len(), fraction.is_none())?;
+
+        let int: i64 = str::from_utf8(int.as_bytes()).ok()?.parse().ok()?;
+        let fraction_nanos = match fraction {
+            Some(fraction) => {
+                let value: i64 = str::from_utf8(fraction).ok()?.parse().ok()?;
+                value * unit.nanos() / 10i64.pow(fraction.len() as u32)
+            }
+            None => 0,
+        };
+        let nanos = int.checked_mul(unit.nanos())?.checked_add(fraction_nanos)?;
+        let ts = Utc.timestamp_nanos(nanos);
+        if !self.years.contains(&ts.year()) {
+            return None;
+        }
+
+        let digits = unit.fraction_digits() + fraction.map_or(0, |x| x.len());
+        let message = caps.get(3).unwrap();
+        Some(
+            LogEntry::from_utc_time(ts, message.as_bytes())
+                .with_spans(ts_span, message.range())
+                .with_precision(Precision::from_fraction_digits(digits))
+                .with_format(FormatKind::Epoch),
+        )
+    }
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_epoch_heuristics() {
+    let parse = |heuristics: EpochHeuristics, line: &[u8]| {
+        let entry = Parser::builder()
+            .epoch_heuristics(Some(heuristics))
+            .build()
+            .parse(line)
+            .into_owned();
+        (entry.utc_timestamp(), entry.timestamp_precision())
+    };
+    assert_debug_snapshot!(
+        [
+            &b"1612345678 GET /"[..],
+            &b"1612345678123 GET /"[..],
+            &b"1612345678.123456 GET /"[..],
+            &b"[1612345678123456789] GET /"[..],
+            &b"12345 bytes sent"[..],
+            &b"9999999999 out of range"[..],
+        ]
+        .iter()
+        .map(|line| parse(EpochHeuristics::default().bare_integers(true), line))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            Some(
+                2021-02-03T09:47:58Z,
+            ),
+            Some(
+                Second,
+            ),
+        ),
+        (
+            Some(
+                2021-02-03T09:47:58.123Z,
+            ),
+            Some(
+                Milli,
+            ),
+        ),
+        (
+            Some(
+                2021-02-03T09:47:58.123456Z,
+            ),
+            Some(
+                Micro,
+            ),
+        ),
+        (
+            Some(
+                2021-02-03T09:47:58.123456789Z,
+            ),
+            Some(
+                Nano,
+            ),
+        ),
+        (
+            None,
+            None,
+        ),
+        (
+            None,
+            None,
+        ),
+    ]
+    "###
+    );
+    assert_debug_snapshot!(
+        [
+            parse(EpochHeuristics::default().bare_integers(false), b"1612345678 request id"),
+            parse(EpochHeuristics::default(), b"1612345678 request id"),
+            parse(EpochHeuristics::default(), b"161234567812 request id"),
+            parse(
+                EpochHeuristics::default()
+                    .bare_integers(true)
+                    .year_range(1970..=2100),
+                b"0123456789 old",
+            ),
+            parse(
+                EpochHeuristics::default().unit_for_digits(12..=13, EpochUnit::Micros),
+                b"1612345678123 micros",
+            ),
+        ],
+        @r###"
+    [
+        (
+            None,
+            None,
+        ),
+        (
+            None,
+            None,
+        ),
+        (
+            None,
+            None,
+        ),
+        (
+            Some(
+                1973-11-29T21:33:09Z,
+            ),
+            Some(
+                Second,
+            ),
+        ),
+        (
+            None,
+            None,
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_epoch_by_default() {
+    assert_debug_snapshot!(
+        [
+            &b"1612345678.123    102 192.0.2.1 TCP_MISS/200 1024 GET http://example.com/"[..],
+            &b"1612345678123 sensor ready"[..],
+        ]
+        .iter()
+        .map(|line| LogEntry::parse(line).into_owned())
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2021-02-03T09:47:58.123Z,
+                ),
+            ),
+            message: "102 192.0.2.1 TCP_MISS/200 1024 GET http://example.com/",
+        },
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2021-02-03T09:47:58.123Z,
+                ),
+            ),
+            message: "sensor ready",
+        },
+    ]
+    "###
+    );
+    let entry = Parser::builder()
+        .epoch_heuristics(None)
+        .build()
+        .parse(b"1612345678 request id");
+    assert!(entry.timestamp().is_none());
+}
+
+#[test]
+fn test_epoch_counts() {
+    let entry = LogEntry::parse(b"1000000000 bytes transferred");
+    assert!(entry.timestamp().is_none());
+    assert_eq!(entry.message(), "1000000000 bytes transferred");
+    let entry = Parser::new().parse(b"1000000000.5 bytes transferred");
+    assert!(entry.timestamp().is_some());
+}
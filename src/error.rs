@@ -0,0 +1,25 @@
+use std::io;
+use std::str::Utf8Error;
+
+use crate::custom::FormatError;
+
+/// An error from one of the fallible APIs of this crate.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A timestamp component is out of range or the time does not exist.
+    #[error("invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+    /// The line carried no timestamp any format recognized.
+    #[error("no timestamp found")]
+    NoTimestamp,
+    /// The line is not valid UTF-8.
+    #[error("line is not valid UTF-8")]
+    Encoding(#[from] Utf8Error),
+    /// Reading from a stream or file failed.
+    #[error("failed to read log")]
+    Io(#[from] io::Error),
+    /// A custom format definition is invalid.
+    #[error(transparent)]
+    Format(#[from] FormatError),
+}
@@ -0,0 +1,488 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref PY_START_RE: Regex = Regex::new(r#"^Traceback \(most recent call last\):$"#).unwrap();
+    static ref PY_CHAIN_RE: Regex = Regex::new(
+        r#"^(?:During handling of the above exception, another exception occurred|The above exception was the direct cause of the following exception):$"#
+    )
+    .unwrap();
+    static ref PY_FRAME_RE: Regex =
+        Regex::new(r#"^[\t ]+File "([^"]+)", line ([0-9]+)(?:, in (.+))?$"#).unwrap();
+    static ref PY_EXCEPTION_RE: Regex =
+        Regex::new(r#"^[A-Za-z_][A-Za-z0-9_.]*(?:: .*)?$"#).unwrap();
+    static ref JAVA_START_RE: Regex = Regex::new(
+        r#"^(?:Exception in thread "[^"]*" |Caused by: |[\t ]*Suppressed: )?([A-Za-z_$][A-Za-z0-9_$]*(?:\.[A-Za-z_$][A-Za-z0-9_$]*)+(?:Exception|Error|Throwable)(?:: .*)?)$"#
+    )
+    .unwrap();
+    static ref JAVA_FRAME_RE: Regex =
+        Regex::new(r#"^[\t ]+at ([A-Za-z0-9_$.<>/]+)\(([^:)]*)(?::([0-9]+))?\)$"#).unwrap();
+    static ref JAVA_MORE_RE: Regex =
+        Regex::new(r#"^[\t ]+\.\.\. [0-9]+ (?:more|common frames omitted)$"#).unwrap();
+    static ref RUST_START_RE: Regex = Regex::new(r#"^thread '[^']*' panicked at (.*)$"#).unwrap();
+    static ref RUST_FRAME_RE: Regex = Regex::new(r#"^[\t ]+[0-9]+: (.+)$"#).unwrap();
+    static ref RUST_AT_RE: Regex =
+        Regex::new(r#"^[\t ]+at (.+?):([0-9]+)(?::[0-9]+)?$"#).unwrap();
+    static ref RUST_NOTE_RE: Regex = Regex::new(r#"^(?:stack backtrace:|note: .*)$"#).unwrap();
+    static ref UNITY_FRAME_RE: Regex = Regex::new(
+        r#"^([A-Za-z_<][A-Za-z0-9_.:<>`+$|\[\],/]*[.:][A-Za-z0-9_.<>`+$|]+) ?\(([^()]*)\)(?: \(at (.+):([0-9]+)\))?$"#
+    )
+    .unwrap();
+    static ref UNITY_AT_RE: Regex = Regex::new(
+        r#"^[\t ]+at ([^\t (]+) ?\([^()]*\)(?: \[0x[0-9a-f]+\] in (.+):([0-9]+))?[\t ]*$"#
+    )
+    .unwrap();
+    static ref UNITY_FILENAME_RE: Regex =
+        Regex::new(r#"^\(Filename: (.*) Line: (-?[0-9]+)\)$"#).unwrap();
+}
+
+/// The runtime that produced a stack trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TraceKind {
+    /// A Python traceback.
+    Python,
+    /// A JVM exception with `at` frames.
+    Java,
+    /// A Rust panic, optionally with a backtrace.
+    Rust,
+    /// A Unity or Mono stack trace following a log message.
+    Unity,
+}
+
+/// A single frame of a stack trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Frame<'a> {
+    function: Option<&'a str>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+}
+
+impl<'a> Frame<'a> {
+    /// Returns the function or method of the frame.
+    pub fn function(&self) -> Option<&'a str> {
+        self.function
+    }
+
+    /// Returns the source file of the frame.
+    pub fn file(&self) -> Option<&'a str> {
+        self.file
+    }
+
+    /// Returns the line within the source file.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+}
+
+/// A stack trace found in the message of an entry.
+///
+/// Frames are in the order they appear in the log, which for Python is
+/// the most recent call last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StackTrace<'a> {
+    kind: TraceKind,
+    exception: Option<&'a str>,
+    frames: Vec<Frame<'a>>,
+}
+
+impl<'a> StackTrace<'a> {
+    /// Returns the runtime that produced the trace.
+    pub fn kind(&self) -> TraceKind {
+        self.kind
+    }
+
+    /// Returns the exception type and value, or the panic message.
+    pub fn exception(&self) -> Option<&'a str> {
+        self.exception
+    }
+
+    /// Returns the frames of the trace.
+    pub fn frames(&self) -> &[Frame<'a>] {
+        &self.frames
+    }
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+/// The shape of the trace an entry is currently collecting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Python,
+    PythonEnded,
+    Java,
+    RustMessage,
+    Rust,
+    Unity,
+    UnityEnded,
+}
+
+fn trace_start(line: &str) -> Option<State> {
+    if PY_START_RE.is_match(line) {
+        Some(State::Python)
+    } else if JAVA_START_RE.is_match(line) {
+        Some(State::Java)
+    } else if RUST_START_RE.is_match(line) {
+        // since Rust 1.73 the panic message follows on its own line
+        Some(if line.ends_with(':') {
+            State::RustMessage
+        } else {
+            State::Rust
+        })
+    } else if is_unity_start(line) {
+        Some(State::Unity)
+    } else {
+        None
+    }
+}
+
+/// Checks if a line is the first frame of a Unity trace.
+///
+/// Unity prints the frames right after the message without a header, so
+/// only frames that are clearly from Unity start a trace: those with a
+/// source location, those of `UnityEngine` and Mono's `at` frames.
+fn is_unity_start(line: &str) -> bool {
+    match UNITY_FRAME_RE.captures(line) {
+        Some(caps) => caps.get(3).is_some() || line.starts_with("UnityEngine."),
+        None => UNITY_AT_RE.is_match(line),
+    }
+}
+
+/// Decides which lines belong to a stack trace when reading streams.
+#[derive(Debug, Default)]
+pub(crate) struct TraceGrouper {
+    state: Option<State>,
+}
+
+impl TraceGrouper {
+    /// Starts tracking a new entry with the given first line.
+    pub fn start(&mut self, line: &str) {
+        self.state = trace_start(line);
+    }
+
+    /// Returns `true` if a line without a timestamp belongs to the trace of
+    /// the current entry or starts one.
+    pub fn continues(&mut self, line: &str) -> bool {
+        let next = match self.state {
+            Some(State::Python)
+                if is_indented(line)
+                    || line.is_empty()
+                    || PY_START_RE.is_match(line)
+                    || PY_CHAIN_RE.is_match(line) =>
+            {
+                Some(State::Python)
+            }
+            Some(State::Python) if PY_EXCEPTION_RE.is_match(line) => Some(State::PythonEnded),
+            Some(State::PythonEnded) if line.is_empty() || PY_CHAIN_RE.is_match(line) => {
+                Some(State::Python)
+            }
+            Some(State::Java)
+                if JAVA_FRAME_RE.is_match(line)
+                    || JAVA_MORE_RE.is_match(line)
+                    || JAVA_START_RE.is_match(line) =>
+            {
+                Some(State::Java)
+            }
+            Some(State::RustMessage) if !line.is_empty() => Some(State::Rust),
+            Some(State::Rust)
+                if RUST_FRAME_RE.is_match(line)
+                    || RUST_AT_RE.is_match(line)
+                    || RUST_NOTE_RE.is_match(line) =>
+            {
+                Some(State::Rust)
+            }
+            Some(State::Unity)
+                if line.is_empty()
+                    || UNITY_FRAME_RE.is_match(line)
+                    || UNITY_AT_RE.is_match(line) =>
+            {
+                Some(State::Unity)
+            }
+            Some(State::Unity) if UNITY_FILENAME_RE.is_match(line) => Some(State::UnityEnded),
+            _ => trace_start(line),
+        };
+        self.state = next.or(self.state);
+        next.is_some()
+    }
+}
+
+fn python_trace<'a>(lines: &[&'a str]) -> StackTrace<'a> {
+    let mut frames = Vec::new();
+    let mut exception = None;
+    for line in lines {
+        if let Some(caps) = PY_FRAME_RE.captures(line) {
+            frames.push(Frame {
+                function: caps.get(3).map(|x| x.as_str()),
+                file: Some(caps.get(1).unwrap().as_str()),
+                line: caps[2].parse().ok(),
+            });
+        } else if !is_indented(line)
+            && !PY_START_RE.is_match(line)
+            && PY_EXCEPTION_RE.is_match(line)
+        {
+            exception = Some(*line);
+        }
+    }
+    StackTrace {
+        kind: TraceKind::Python,
+        exception,
+        frames,
+    }
+}
+
+fn java_trace<'a>(lines: &[&'a str]) -> StackTrace<'a> {
+    let exception = JAVA_START_RE
+        .captures(lines[0])
+        .map(|caps| caps.get(1).unwrap().as_str());
+    let frames = lines
+        .iter()
+        .filter_map(|line| JAVA_FRAME_RE.captures(line))
+        .map(|caps| Frame {
+            function: Some(caps.get(1).unwrap().as_str()),
+            // `(Native Method)` and `(Unknown Source)` carry no file
+            file: caps
+                .get(2)
+                .map(|x| x.as_str())
+                .filter(|x| !x.is_empty() && !x.contains(' ')),
+            line: caps.get(3).and_then(|x| x.as_str().parse().ok()),
+        })
+        .collect();
+    StackTrace {
+        kind: TraceKind::Java,
+        exception,
+        frames,
+    }
+}
+
+fn rust_trace<'a>(lines: &[&'a str]) -> StackTrace<'a> {
+    let location = RUST_START_RE
+        .captures(lines[0])
+        .map(|caps| caps.get(1).unwrap().as_str());
+    let exception = match location {
+        Some(location) if location.ends_with(':') => lines.get(1).cloned(),
+        location => location,
+    };
+    let mut frames: Vec<Frame> = Vec::new();
+    for line in lines {
+        if let Some(caps) = RUST_FRAME_RE.captures(line) {
+            frames.push(Frame {
+                function: Some(caps.get(1).unwrap().as_str()),
+                file: None,
+                line: None,
+            });
+        } else if let Some(caps) = RUST_AT_RE.captures(line) {
+            if let Some(frame) = frames.last_mut() {
+                frame.file = Some(caps.get(1).unwrap().as_str());
+                frame.line = caps[2].parse().ok();
+            }
+        }
+    }
+    StackTrace {
+        kind: TraceKind::Rust,
+        exception,
+        frames,
+    }
+}
+
+fn unity_trace<'a>(exception: Option<&'a str>, lines: &[&'a str]) -> StackTrace<'a> {
+    let mut frames = Vec::new();
+    for line in lines {
+        let caps = match UNITY_FRAME_RE.captures(line) {
+            Some(caps) => (caps.get(1), caps.get(3), caps.get(4)),
+            None => match UNITY_AT_RE.captures(line) {
+                Some(caps) => (caps.get(1), caps.get(2), caps.get(3)),
+                None => continue,
+            },
+        };
+        // builds without symbols print a module id (`<3a1b...>`) instead
+        let file = caps.1.map(|x| x.as_str()).filter(|x| !x.starts_with('<'));
+        frames.push(Frame {
+            function: caps.0.map(|x| x.as_str()),
+            file,
+            line: file.and(caps.2).and_then(|x| x.as_str().parse().ok()),
+        });
+    }
+    StackTrace {
+        kind: TraceKind::Unity,
+        exception,
+        frames,
+    }
+}
+
+/// Finds a stack trace in a (usually multi-line) message.
+pub(crate) fn find_stack_trace(message: &str) -> Option<StackTrace<'_>> {
+    let lines: Vec<&str> = message.lines().collect();
+    let (start, state) = lines
+        .iter()
+        .enumerate()
+        .find_map(|(idx, line)| Some((idx, trace_start(line)?)))?;
+    // the message before a Unity trace is what was logged or thrown
+    let before = start.checked_sub(1).map(|idx| lines[idx]);
+    let lines = &lines[start..];
+    let trace = match state {
+        State::Python | State::PythonEnded => python_trace(lines),
+        State::Java => java_trace(lines),
+        State::Rust | State::RustMessage => return Some(rust_trace(lines)),
+        State::Unity | State::UnityEnded => unity_trace(before, lines),
+    };
+    if trace.frames.is_empty() {
+        None
+    } else {
+        Some(trace)
+    }
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_find_stack_trace() {
+    assert_debug_snapshot!(find_stack_trace(
+        "request failed\nTraceback (most recent call last):\n  File \"app.py\", line 12, in handler\n    return compute()\n  File \"lib/compute.py\", line 3, in compute\n    1 / 0\nZeroDivisionError: division by zero"
+    ), @r###"
+    Some(
+        StackTrace {
+            kind: Python,
+            exception: Some(
+                "ZeroDivisionError: division by zero",
+            ),
+            frames: [
+                Frame {
+                    function: Some(
+                        "handler",
+                    ),
+                    file: Some(
+                        "app.py",
+                    ),
+                    line: Some(
+                        12,
+                    ),
+                },
+                Frame {
+                    function: Some(
+                        "compute",
+                    ),
+                    file: Some(
+                        "lib/compute.py",
+                    ),
+                    line: Some(
+                        3,
+                    ),
+                },
+            ],
+        },
+    )
+    "###);
+    assert_debug_snapshot!(find_stack_trace(
+        "Exception in thread \"main\" java.lang.IllegalStateException: boom\n\tat com.example.Main.run(Main.java:12)\n\tat java.base/java.lang.Thread.run(Native Method)\n\t... 3 more"
+    ), @r###"
+    Some(
+        StackTrace {
+            kind: Java,
+            exception: Some(
+                "java.lang.IllegalStateException: boom",
+            ),
+            frames: [
+                Frame {
+                    function: Some(
+                        "com.example.Main.run",
+                    ),
+                    file: Some(
+                        "Main.java",
+                    ),
+                    line: Some(
+                        12,
+                    ),
+                },
+                Frame {
+                    function: Some(
+                        "java.base/java.lang.Thread.run",
+                    ),
+                    file: None,
+                    line: None,
+                },
+            ],
+        },
+    )
+    "###);
+    assert_debug_snapshot!(find_stack_trace(
+        "thread 'main' panicked at src/main.rs:2:5:\nexplicit panic\nstack backtrace:\n   0: std::panicking::begin_panic\n             at /rustc/library/std/src/panicking.rs:593:12\n   1: app::main"
+    ), @r###"
+    Some(
+        StackTrace {
+            kind: Rust,
+            exception: Some(
+                "explicit panic",
+            ),
+            frames: [
+                Frame {
+                    function: Some(
+                        "std::panicking::begin_panic",
+                    ),
+                    file: Some(
+                        "/rustc/library/std/src/panicking.rs",
+                    ),
+                    line: Some(
+                        593,
+                    ),
+                },
+                Frame {
+                    function: Some(
+                        "app::main",
+                    ),
+                    file: None,
+                    line: None,
+                },
+            ],
+        },
+    )
+    "###);
+    assert_debug_snapshot!(find_stack_trace(
+        "NullReferenceException: Object reference not set to an instance of an object\nPlayer.Update () (at Assets/Scripts/Player.cs:42)\nUnityEngine.Debug:LogException(Exception)\n  at Enemy.Attack () [0x00000] in <3a1b2c4d5e6f>:0 \n(Filename: Assets/Scripts/Player.cs Line: 42)"
+    ), @r###"
+    Some(
+        StackTrace {
+            kind: Unity,
+            exception: Some(
+                "NullReferenceException: Object reference not set to an instance of an object",
+            ),
+            frames: [
+                Frame {
+                    function: Some(
+                        "Player.Update",
+                    ),
+                    file: Some(
+                        "Assets/Scripts/Player.cs",
+                    ),
+                    line: Some(
+                        42,
+                    ),
+                },
+                Frame {
+                    function: Some(
+                        "UnityEngine.Debug:LogException",
+                    ),
+                    file: None,
+                    line: None,
+                },
+                Frame {
+                    function: Some(
+                        "Enemy.Attack",
+                    ),
+                    file: None,
+                    line: None,
+                },
+            ],
+        },
+    )
+    "###);
+    assert_debug_snapshot!(find_stack_trace("java.lang.Error is not a trace without frames"), @"None");
+}
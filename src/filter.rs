@@ -0,0 +1,418 @@
+use std::collections::VecDeque;
+use std::io;
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use crate::level::Level;
+use crate::types::LogEntry;
+
+/// What to do with entries that carry no timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UntimedPolicy {
+    /// Keeps or drops the entry together with the preceding timestamped
+    /// entry.  Entries before the first timestamp are dropped.
+    #[default]
+    FollowPrevious,
+    /// Always keeps the entry.
+    Keep,
+    /// Always drops the entry.
+    Drop,
+}
+
+/// Adapters for streams of log entries.
+///
+/// This is implemented for all iterators over parsed entries, such as
+/// [`Entries`](crate::Entries).
+pub trait EntriesExt: Iterator<Item = io::Result<LogEntry<'static>>> + Sized {
+    /// Drops entries older than `max_age` before the reference time.
+    ///
+    /// Errors are passed through.
+    fn max_age(
+        self,
+        max_age: Duration,
+        reference: DateTime<Utc>,
+        untimed: UntimedPolicy,
+    ) -> MaxAge<Self> {
+        MaxAge {
+            inner: self,
+            cutoff: reference - max_age,
+            untimed,
+            keep_untimed: false,
+        }
+    }
+
+    /// Keeps the first and then every `n`th entry.
+    ///
+    /// Entries at or above `keep_level` are always kept and do not count
+    /// towards `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    fn sample_every(self, n: usize, keep_level: Option<Level>) -> Sample<Self> {
+        assert!(n > 0, "sample interval must not be zero");
+        Sample {
+            inner: self,
+            mode: SampleMode::Every { n, seen: 0 },
+            keep_level,
+        }
+    }
+
+    /// Keeps each entry with the given probability.
+    ///
+    /// The selection is pseudo random but reproducible for the same
+    /// `seed`.  Entries at or above `keep_level` are always kept.
+    fn sample_random(self, probability: f64, seed: u64, keep_level: Option<Level>) -> Sample<Self> {
+        Sample {
+            inner: self,
+            mode: SampleMode::Random {
+                threshold: (probability.clamp(0.0, 1.0) * u64::MAX as f64) as u64,
+                // xorshift gets stuck on zero
+                state: seed | 1,
+            },
+            keep_level,
+        }
+    }
+
+    /// Collapses bursts of similar entries into a single entry.
+    ///
+    /// A burst is a run of more than `max` consecutive entries with the
+    /// same message template (the message with numbers ignored) within
+    /// `window` of the first entry of the run.  Only the first entry of a
+    /// burst is kept and its message notes how many entries it stands for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    fn collapse_bursts(self, max: usize, window: Duration) -> CollapseBursts<Self> {
+        assert!(max > 0, "burst size must not be zero");
+        CollapseBursts {
+            inner: self,
+            max,
+            window,
+            template: String::new(),
+            run: Vec::new(),
+            run_len: 0,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = io::Result<LogEntry<'static>>>> EntriesExt for I {}
+
+/// An adapter that drops old entries.
+///
+/// This is created by [`EntriesExt::max_age`].
+#[derive(Debug, Clone)]
+pub struct MaxAge<I> {
+    inner: I,
+    cutoff: DateTime<Utc>,
+    untimed: UntimedPolicy,
+    keep_untimed: bool,
+}
+
+impl<I: Iterator<Item = io::Result<LogEntry<'static>>>> Iterator for MaxAge<I> {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.inner.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            let keep = match entry.utc_timestamp() {
+                Some(ts) => {
+                    self.keep_untimed = ts >= self.cutoff;
+                    self.keep_untimed
+                }
+                None => match self.untimed {
+                    UntimedPolicy::FollowPrevious => self.keep_untimed,
+                    UntimedPolicy::Keep => true,
+                    UntimedPolicy::Drop => false,
+                },
+            };
+            if keep {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/// An adapter that keeps a subset of entries.
+///
+/// This is created by [`EntriesExt::sample_every`] and
+/// [`EntriesExt::sample_random`].
+#[derive(Debug, Clone)]
+pub struct Sample<I> {
+    inner: I,
+    mode: SampleMode,
+    keep_level: Option<Level>,
+}
+
+#[derive(Debug, Clone)]
+enum SampleMode {
+    Every { n: usize, seen: usize },
+    Random { threshold: u64, state: u64 },
+}
+
+impl SampleMode {
+    fn keep(&mut self) -> bool {
+        match self {
+            SampleMode::Every { n, seen } => {
+                let keep = *seen % *n == 0;
+                *seen += 1;
+                keep
+            }
+            SampleMode::Random { threshold, state } => {
+                // xorshift64*, good enough for sampling and reproducible
+                *state ^= *state >> 12;
+                *state ^= *state << 25;
+                *state ^= *state >> 27;
+                state.wrapping_mul(0x2545_f491_4f6c_dd1d) < *threshold
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = io::Result<LogEntry<'static>>>> Iterator for Sample<I> {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.inner.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            let important = match (self.keep_level, entry.level()) {
+                (Some(keep_level), Some(level)) => level >= keep_level,
+                _ => false,
+            };
+            if important || self.mode.keep() {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/// Reduces a message to a template by collapsing numbers.
+fn message_template(message: &str) -> String {
+    let mut rv = String::with_capacity(message.len());
+    for c in message.chars() {
+        if c.is_ascii_digit() {
+            if !rv.ends_with('#') {
+                rv.push('#');
+            }
+        } else {
+            rv.push(c);
+        }
+    }
+    rv
+}
+
+/// An adapter that collapses bursts of similar entries.
+///
+/// This is created by [`EntriesExt::collapse_bursts`].
+#[derive(Debug)]
+pub struct CollapseBursts<I> {
+    inner: I,
+    max: usize,
+    window: Duration,
+    template: String,
+    run: Vec<LogEntry<'static>>,
+    run_len: usize,
+    ready: VecDeque<io::Result<LogEntry<'static>>>,
+    done: bool,
+}
+
+impl<I> CollapseBursts<I> {
+    fn flush(&mut self) {
+        if self.run_len > self.max {
+            let first = self.run.swap_remove(0);
+            let message = format!(
+                "{} [{} similar entries collapsed]",
+                first.message(),
+                self.run_len
+            );
+            self.ready.push_back(Ok(first.with_owned_message(message)));
+            self.run.clear();
+        } else {
+            self.ready.extend(self.run.drain(..).map(Ok));
+        }
+        self.run_len = 0;
+    }
+
+    fn push(&mut self, entry: LogEntry<'static>) {
+        let ts = match entry.utc_timestamp() {
+            Some(ts) => ts,
+            None => {
+                self.flush();
+                self.ready.push_back(Ok(entry));
+                return;
+            }
+        };
+        let template = message_template(entry.message());
+        let continues_run = match self.run.first().and_then(|x| x.utc_timestamp()) {
+            Some(start) => template == self.template && ts - start <= self.window,
+            None => false,
+        };
+        if !continues_run {
+            self.flush();
+            self.template = template;
+        }
+        // once a run is a burst only the first entry is needed
+        if self.run_len <= self.max {
+            self.run.push(entry);
+        }
+        self.run_len += 1;
+    }
+}
+
+impl<I: Iterator<Item = io::Result<LogEntry<'static>>>> Iterator for CollapseBursts<I> {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            match self.inner.next() {
+                Some(Ok(entry)) => self.push(entry),
+                Some(Err(err)) => {
+                    self.flush();
+                    self.ready.push_back(Err(err));
+                }
+                None => {
+                    self.done = true;
+                    self.flush();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::custom::CustomFormat;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_max_age() {
+    let input = &b"2017-01-01 11:50:00 +0000: too old\nold continuation\n2017-01-01 11:58:00 +0000: recent\nrecent continuation\n2017-01-01 11:59:30 +0000: crash"[..];
+    let filter = |untimed| {
+        Parser::new()
+            .read_entries(input)
+            .max_age(
+                Duration::minutes(5),
+                Utc.with_ymd_and_hms(2017, 1, 1, 12, 0, 0).unwrap(),
+                untimed,
+            )
+            .map(|entry| entry.unwrap().message().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_debug_snapshot!(filter(UntimedPolicy::FollowPrevious), @r###"
+    [
+        "recent",
+        "recent continuation",
+        "crash",
+    ]
+    "###);
+    assert_debug_snapshot!(filter(UntimedPolicy::Drop), @r###"
+    [
+        "recent",
+        "crash",
+    ]
+    "###);
+}
+
+#[test]
+fn test_sample() {
+    let input = (0..10)
+        .map(|idx| {
+            let level = if idx == 5 { "ERROR" } else { "INFO" };
+            format!("2017-01-01 12:00:0{} +0000: {} line {}\n", idx, level, idx)
+        })
+        .collect::<String>();
+    let parser = Parser::builder()
+        .custom_format(
+            CustomFormat::new(
+                "leveled",
+                r"^(?P<year>[0-9]{4})-(?P<month>[0-9]{2})-(?P<day>[0-9]{2}) (?P<hour>[0-9]{2}):(?P<minute>[0-9]{2}):(?P<second>[0-9]{2}) (?P<offset>[+-][0-9]{4}): (?P<level>[A-Z]+) (?P<message>.*)$",
+            )
+            .unwrap(),
+        )
+        .build();
+    let messages = |sampled: Sample<_>| {
+        sampled
+            .map(|entry| entry.unwrap().message().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_debug_snapshot!(
+        messages(parser.read_entries(input.as_bytes()).sample_every(4, Some(Level::Error))),
+        @r###"
+    [
+        "line 0",
+        "line 4",
+        "line 5",
+        "line 9",
+    ]
+    "###
+    );
+    let random = |seed| {
+        messages(
+            parser
+                .read_entries(input.as_bytes())
+                .sample_random(0.3, seed, None),
+        )
+    };
+    assert_eq!(random(42), random(42));
+    assert_debug_snapshot!(random(42), @r###"
+    [
+        "line 2",
+        "line 6",
+        "line 8",
+    ]
+    "###);
+}
+
+#[test]
+fn test_collapse_bursts() {
+    let mut input = String::from("2017-01-01 12:00:00 +0000: starting\n");
+    for idx in 0..50 {
+        input.push_str(&format!(
+            "2017-01-01 12:00:01 +0000: connection {} refused\n",
+            idx
+        ));
+    }
+    input.push_str("2017-01-01 12:00:02 +0000: retry 1 failed\n");
+    input.push_str("2017-01-01 12:00:03 +0000: retry 2 failed\n");
+    input.push_str("2017-01-01 12:00:04 +0000: giving up");
+    let messages: Vec<_> = Parser::new()
+        .read_entries(input.as_bytes())
+        .collapse_bursts(3, Duration::milliseconds(500))
+        .map(|entry| entry.unwrap().message().to_string())
+        .collect();
+    assert_debug_snapshot!(messages, @r###"
+    [
+        "starting",
+        "connection 0 refused [50 similar entries collapsed]",
+        "retry 1 failed",
+        "retry 2 failed",
+        "giving up",
+    ]
+    "###);
+}
+
+#[test]
+#[should_panic(expected = "burst size must not be zero")]
+fn test_collapse_bursts_zero() {
+    let _ = Parser::new()
+        .read_entries(&b"starting"[..])
+        .collapse_bursts(0, Duration::milliseconds(500));
+}
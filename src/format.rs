@@ -0,0 +1,905 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::access;
+use crate::actions;
+use crate::apple;
+use crate::audit;
+use crate::build;
+use crate::capnslog;
+use crate::ceph;
+use crate::chrome;
+use crate::cisco;
+use crate::crash;
+use crate::cri;
+use crate::cryengine;
+use crate::cups;
+use crate::dotnet;
+use crate::envoy;
+#[cfg(feature = "json")]
+use crate::gelf;
+use crate::haproxy;
+use crate::hclog;
+use crate::heroku;
+use crate::java;
+#[cfg(feature = "json")]
+use crate::json;
+use crate::kafka;
+use crate::klog;
+use crate::lambda;
+use crate::logcat;
+use crate::minecraft;
+use crate::mongodb;
+use crate::mysql;
+use crate::npm;
+use crate::parser::{self, Context};
+use crate::redis;
+use crate::ruby;
+use crate::source;
+use crate::squid;
+use crate::syslog;
+use crate::types::LogEntry;
+use crate::unity;
+use crate::w3c;
+use crate::windows;
+use crate::xorg;
+
+/// A log format the parser can recognize.
+///
+/// All built-in formats implement this trait and custom formats can be
+/// added to a parser by implementing it.
+pub trait LogFormat: Send + Sync {
+    /// Returns the name of the format.
+    fn name(&self) -> &str;
+
+    /// Cheaply checks if a line could be in this format.
+    ///
+    /// This is called before `parse` so that the more expensive matching
+    /// can be skipped for lines that clearly are in another format.
+    fn quick_check(&self, bytes: &[u8]) -> bool {
+        let _ = bytes;
+        true
+    }
+
+    /// Parses a line in this format.
+    fn parse<'a>(&self, bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>>;
+}
+
+/// Identifies the format a line was recognized as.
+///
+/// The `LogFormat` trait describes formats themselves; this is the kind of
+/// format reported by [`LogEntry::format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum FormatKind {
+    /// A C `ctime` timestamp (`Tue Nov 21 00:30:05 2017`).
+    C,
+    /// A syslog style timestamp without a year (`Nov 20 21:56:01`).
+    Short,
+    /// A line in the default Serilog or NLog templates (`[12:00:00 INF] ...`).
+    Serilog,
+    /// A line of a Minecraft server log (`[12:00:00] [Server thread/INFO]: ...`).
+    Minecraft,
+    /// A time of day only (`22:07:10`).
+    Simple,
+    /// A date, time and offset (`2015-05-13 17:39:16 +0200`).
+    Common,
+    /// A `ctime` like timestamp with a padded day or fractional seconds.
+    CommonAlt,
+    /// A `Mon DD, YYYY` date (`Jan 03, 2016 22:29:55`).
+    CommonAlt2,
+    /// An Unreal Engine 4 log line.
+    Ue4,
+    /// A line of a W3C extended log, mapped with its `#Fields:` directive.
+    W3c,
+    /// A line of an HAProxy HTTP log (`haproxy[1234]: 1.2.3.4:5678 [01/Jun/2021:12:00:00.123] ...`).
+    Haproxy,
+    /// A record of the Linux audit log (`type=AVC msg=audit(1612345678.123:456): ...`).
+    Audit,
+    /// A line of the CUPS `error_log` (`E [01/Jun/2021:12:00:00 +0200] message`).
+    Cups,
+    /// A line printed by `journalctl -o short-precise` or `-o short-iso`.
+    Journal,
+    /// A line written by Chrome or Chromium (`[1234:5678:0601/120000.123456:ERROR:...]`).
+    Chrome,
+    /// A Kubernetes CRI container log line (`2021-07-01T12:00:00Z stdout F ...`).
+    Cri,
+    /// A line in the default log4j or logback pattern (`... [main] INFO Foo - ...`).
+    Log4j,
+    /// A line of a GitHub Actions or Azure Pipelines job log (`2021-06-01T12:00:00.1234567Z ##[error]message`).
+    Actions,
+    /// A line of an npm debug log (`2021-06-01T12:00:00.123Z verbose message`).
+    Npm,
+    /// A line written by HashiCorp's hclog (`2021-06-01T12:00:00.123+0200 [INFO]  agent: message`).
+    Hclog,
+    /// A line of an etcd server log (`2021-06-01 12:00:00.123456 I | etcdserver: message`).
+    Capnslog,
+    /// A line of an Envoy access log in the default format (`[2021-06-01T12:00:00.123Z] "GET / HTTP/1.1" 200 ...`).
+    EnvoyAccess,
+    /// A line of a Ceph daemon log (`2021-06-01 12:00:00.123456 7f1234567700  0 message`).
+    Ceph,
+    /// A line of a Kafka or ZooKeeper server log (`[2021-06-01 12:00:00,123] INFO message (kafka.server.KafkaServer)`).
+    Kafka,
+    /// A line drained from Heroku's logplex (`... heroku[router]: ...`).
+    Heroku,
+    /// A line of an AWS Lambda function (`... START RequestId: ...`).
+    Lambda,
+    /// A line of a MySQL or MariaDB error log (`... 0 [Warning] [MY-010101] ...`).
+    Mysql,
+    /// A line of a MongoDB server log (`... I NETWORK  [conn1] ...`).
+    Mongodb,
+    /// A line of the Windows servicing log (`2021-06-01 12:00:00, Info CBS ...`).
+    Cbs,
+    /// A line of the Apple unified log as printed by `log show`.
+    Unified,
+    /// An RFC 3339 or ISO 8601 timestamp (`2018-10-29T16:56:37.123Z`).
+    Rfc3339,
+    /// A `MM/DD/YYYY` or `DD/MM/YYYY` date (`03/17/2021 4:05:06 PM`).
+    SlashDate,
+    /// A `DD.MM.YYYY` date (`17.03.2021 16:05:06`).
+    DotDate,
+    /// A compact all-digit date and time (`20181029 165637`).
+    Compact,
+    /// A line of the X.Org server log (`[    45.123] (II) modeset(0): message`).
+    Xorg,
+    /// The seconds since boot as written by the kernel.
+    Uptime,
+    /// The time since the previous line (`+0.5s`).
+    Delta,
+    /// An RFC 5424 syslog line (`<165>1 2003-10-11T22:14:15.003Z ...`).
+    Rfc5424,
+    /// An RFC 3164 syslog line with hostname and tag.
+    Rfc3164,
+    /// An Android `logcat` line (`03-17 16:13:38.811  1702  8671 D Tag: ...`).
+    Logcat,
+    /// A klog or glog line (`I0102 15:04:05.123456 1 main.go:12] ...`).
+    Klog,
+    /// An Apache or nginx access log line in the Common or Combined Log Format.
+    Clf,
+    /// A line written by Ruby's default `Logger` formatter.
+    Ruby,
+    /// A line in the bracketed log4j2 pattern of Elasticsearch (`[...][INFO ][o.e.n.Node] ...`).
+    Bracketed,
+    /// A line of a Redis server log (`1234:M 01 Jun 2021 12:00:00.123 * ...`).
+    Redis,
+    /// A section boundary of the Windows device setup log (`>>>  Section start ...`).
+    Setupapi,
+    /// The `Date/Time:` header of an Apple crash report.
+    CrashReport,
+    /// A line of a Unity log written with `-timestamps` (`2021-06-01T12:00:00.123Z|0x1a2b|...`).
+    Unity,
+    /// A line of a CryEngine or Lumberyard log (`<12:00:00> [Warning] ...`).
+    Cryengine,
+    /// A line of a Source engine server log (`L 06/01/2021 - 12:00:00: ...`).
+    Source,
+    /// A line of a Squid `access.log` in the native format (`1612345678.123    456 1.2.3.4 TCP_MISS/200 ...`).
+    Squid,
+    /// A timestamp line of a PowerShell transcript (`Start time: 20210601120000`).
+    Powershell,
+    /// A line of Maven build output (`[INFO] message`).
+    Maven,
+    /// A line of Gradle output with `--info` or `--debug` (`12:00:00.123 [LIFECYCLE] [category] message`).
+    Gradle,
+    /// A step line of yarn output (`[2/4] Fetching packages...`).
+    Yarn,
+    /// A line of Envoy's own log (`[2021-06-01 12:00:00.123][12][info][main] message`).
+    Envoy,
+    /// A Cisco IOS syslog message (`*Jun  1 12:00:00.123: %SYS-5-CONFIG_I: message`).
+    Cisco,
+    /// A GELF message (`{"version":"1.1","host":...}`).
+    Gelf,
+    /// A JSON object.
+    Json,
+    /// A `java.util.logging` record spanning two lines.
+    Jul,
+    /// A number of seconds (or smaller units) since the epoch.
+    Epoch,
+    /// A timestamp found elsewhere in the line by scanning.
+    Scanned,
+    /// A custom format or one implemented outside of this crate.
+    Custom,
+}
+
+impl FormatKind {
+    /// Returns the name of the format as used in a [`Registry`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FormatKind::C => "c",
+            FormatKind::Short => "short",
+            FormatKind::Serilog => "serilog",
+            FormatKind::Minecraft => "minecraft",
+            FormatKind::Simple => "simple",
+            FormatKind::Common => "common",
+            FormatKind::CommonAlt => "common_alt",
+            FormatKind::CommonAlt2 => "common_alt2",
+            FormatKind::Ue4 => "ue4",
+            FormatKind::W3c => "w3c",
+            FormatKind::Haproxy => "haproxy",
+            FormatKind::Audit => "audit",
+            FormatKind::Cups => "cups",
+            FormatKind::Journal => "journal",
+            FormatKind::Chrome => "chrome",
+            FormatKind::Cri => "cri",
+            FormatKind::Log4j => "log4j",
+            FormatKind::Actions => "actions",
+            FormatKind::Npm => "npm",
+            FormatKind::Hclog => "hclog",
+            FormatKind::Capnslog => "capnslog",
+            FormatKind::EnvoyAccess => "envoy_access",
+            FormatKind::Ceph => "ceph",
+            FormatKind::Kafka => "kafka",
+            FormatKind::Heroku => "heroku",
+            FormatKind::Lambda => "lambda",
+            FormatKind::Mysql => "mysql",
+            FormatKind::Mongodb => "mongodb",
+            FormatKind::Cbs => "cbs",
+            FormatKind::Unified => "unified",
+            FormatKind::Rfc3339 => "rfc3339",
+            FormatKind::SlashDate => "slash_date",
+            FormatKind::DotDate => "dot_date",
+            FormatKind::Compact => "compact",
+            FormatKind::Xorg => "xorg",
+            FormatKind::Uptime => "uptime",
+            FormatKind::Delta => "delta",
+            FormatKind::Rfc5424 => "rfc5424",
+            FormatKind::Rfc3164 => "rfc3164",
+            FormatKind::Logcat => "logcat",
+            FormatKind::Klog => "klog",
+            FormatKind::Clf => "clf",
+            FormatKind::Ruby => "ruby",
+            FormatKind::Bracketed => "bracketed",
+            FormatKind::Redis => "redis",
+            FormatKind::Setupapi => "setupapi",
+            FormatKind::CrashReport => "crash_report",
+            FormatKind::Unity => "unity",
+            FormatKind::Cryengine => "cryengine",
+            FormatKind::Source => "source",
+            FormatKind::Squid => "squid",
+            FormatKind::Powershell => "powershell",
+            FormatKind::Maven => "maven",
+            FormatKind::Gradle => "gradle",
+            FormatKind::Yarn => "yarn",
+            FormatKind::Envoy => "envoy",
+            FormatKind::Cisco => "cisco",
+            FormatKind::Gelf => "gelf",
+            FormatKind::Json => "json",
+            FormatKind::Jul => "jul",
+            FormatKind::Epoch => "epoch",
+            FormatKind::Scanned => "scanned",
+            FormatKind::Custom => "custom",
+        }
+    }
+}
+
+/// A built-in format backed by one of the parse functions.
+#[derive(Clone, Copy)]
+struct BuiltinFormat {
+    kind: FormatKind,
+    quick_check: fn(&[u8]) -> bool,
+    parse: for<'a> fn(&'a [u8], &Context) -> Option<LogEntry<'a>>,
+}
+
+impl LogFormat for BuiltinFormat {
+    fn name(&self) -> &str {
+        self.kind.as_str()
+    }
+
+    fn quick_check(&self, bytes: &[u8]) -> bool {
+        (self.quick_check)(bytes)
+    }
+
+    fn parse<'a>(&self, bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+        Some((self.parse)(bytes, ctx)?.with_format(self.kind))
+    }
+}
+
+/// Returns the first byte, skipping an opening bracket.
+fn first_byte(bytes: &[u8]) -> Option<u8> {
+    match bytes.first()? {
+        b'[' => bytes.get(1).cloned(),
+        &c => Some(c),
+    }
+}
+
+fn starts_with_letter(bytes: &[u8]) -> bool {
+    matches!(first_byte(bytes), Some(b'A'..=b'Z'))
+}
+
+fn starts_with_digit(bytes: &[u8]) -> bool {
+    matches!(first_byte(bytes), Some(b'0'..=b'9'))
+}
+
+/// Checks for `Mon D` or `YYYY-MM-DD` at the start of a line.
+fn starts_with_journal_date(bytes: &[u8]) -> bool {
+    let month_day = bytes.get(..3).and_then(parser::get_month).is_some()
+        && bytes.get(3) == Some(&b' ')
+        && bytes.get(4).is_some_and(|x| x.is_ascii_digit());
+    let iso_date = bytes.get(..10).is_some_and(|date| {
+        date.iter().enumerate().all(|(idx, x)| {
+            if idx == 4 || idx == 7 {
+                *x == b'-'
+            } else {
+                x.is_ascii_digit()
+            }
+        })
+    });
+    month_day || iso_date
+}
+
+/// The built-in formats in the order they are tried.
+///
+/// The original formats (`c`, `short`, `simple`, `common`, `common_alt`,
+/// `common_alt2` and `ue4`) come first and in their original order, so lines
+/// they parse keep parsing the same, except that `short` leaves journal lines
+/// to the `journal` format.  Newer formats go after them.  A newer
+/// format may only go ahead of an original one if `test_builtin_order` shows
+/// that it leaves the lines of that format alone.
+static BUILTIN_FORMATS: &[BuiltinFormat] = &[
+    BuiltinFormat {
+        kind: FormatKind::C,
+        quick_check: starts_with_letter,
+        parse: parser::parse_c_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Short,
+        quick_check: starts_with_letter,
+        parse: syslog::parse_short_unless_journal,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Serilog,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'0'..=b'9') | Some(b'[')),
+        parse: dotnet::parse_serilog_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Minecraft,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: minecraft::parse_minecraft_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Simple,
+        quick_check: starts_with_digit,
+        parse: parser::parse_simple_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Common,
+        quick_check: starts_with_digit,
+        parse: parser::parse_common_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::CommonAlt,
+        quick_check: starts_with_letter,
+        parse: parser::parse_common_alt_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::CommonAlt2,
+        quick_check: starts_with_letter,
+        parse: parser::parse_common_alt2_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Ue4,
+        quick_check: |bytes| bytes.starts_with(b"[") && starts_with_digit(bytes),
+        parse: parser::parse_ue4_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::W3c,
+        // only parses once the stream declared its columns
+        quick_check: |bytes| !bytes.starts_with(b"#") && memchr::memchr(b' ', bytes).is_some(),
+        parse: w3c::parse_w3c_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Haproxy,
+        quick_check: |bytes| memchr::memmem::find(bytes, b"haproxy[").is_some(),
+        parse: haproxy::parse_haproxy_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Audit,
+        quick_check: |bytes| bytes.starts_with(b"type=") || bytes.starts_with(b"node="),
+        parse: audit::parse_audit_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Cups,
+        quick_check: |bytes| bytes.get(1..3) == Some(b" ["),
+        parse: cups::parse_cups_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Journal,
+        quick_check: starts_with_journal_date,
+        parse: syslog::parse_journal_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Chrome,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: chrome::parse_chrome_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Cri,
+        quick_check: starts_with_digit,
+        parse: cri::parse_cri_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Log4j,
+        quick_check: starts_with_digit,
+        parse: java::parse_log4j_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Actions,
+        quick_check: |bytes| bytes.get(27) == Some(&b'Z'),
+        parse: actions::parse_actions_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Npm,
+        quick_check: |bytes| bytes.first().is_some_and(|x| x.is_ascii_digit()),
+        parse: npm::parse_npm_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Hclog,
+        quick_check: |bytes| bytes.first().is_some_and(|x| x.is_ascii_digit()),
+        parse: hclog::parse_hclog_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Capnslog,
+        quick_check: |bytes| {
+            bytes
+                .first()
+                .is_some_and(|x| x.is_ascii_digit() || *x == b'{')
+        },
+        parse: capnslog::parse_capnslog_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::EnvoyAccess,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: envoy::parse_envoy_access_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Ceph,
+        quick_check: |bytes| bytes.first().is_some_and(|x| x.is_ascii_digit()),
+        parse: ceph::parse_ceph_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Kafka,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: kafka::parse_kafka_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Heroku,
+        quick_check: starts_with_digit,
+        parse: heroku::parse_heroku_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Lambda,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'0'..=b'9') | Some(b'[')),
+        parse: lambda::parse_lambda_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Mysql,
+        quick_check: starts_with_digit,
+        parse: mysql::parse_mysql_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Mongodb,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'0'..=b'9') | Some(b'{')),
+        parse: mongodb::parse_mongodb_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Cbs,
+        quick_check: starts_with_digit,
+        parse: windows::parse_cbs_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Unified,
+        quick_check: starts_with_digit,
+        parse: apple::parse_unified_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Rfc3339,
+        quick_check: starts_with_digit,
+        parse: syslog::parse_rfc3339_unless_journal,
+    },
+    BuiltinFormat {
+        kind: FormatKind::SlashDate,
+        quick_check: starts_with_digit,
+        parse: parser::parse_slash_date_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::DotDate,
+        quick_check: starts_with_digit,
+        parse: parser::parse_dot_date_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Compact,
+        quick_check: starts_with_digit,
+        parse: parser::parse_compact_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Xorg,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: xorg::parse_xorg_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Uptime,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'[') | Some(b'<') | Some(b'a'..=b'z')),
+        parse: parser::parse_uptime_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Delta,
+        quick_check: |bytes| bytes.starts_with(b"+"),
+        parse: parser::parse_delta_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Rfc5424,
+        quick_check: |bytes| bytes.starts_with(b"<"),
+        parse: syslog::parse_rfc5424_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Rfc3164,
+        quick_check: |bytes| bytes.starts_with(b"<"),
+        parse: syslog::parse_rfc3164_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Logcat,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'0'..=b'9') | Some(b'[')),
+        parse: logcat::parse_logcat_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Klog,
+        quick_check: |bytes| {
+            matches!(
+                bytes.first(),
+                Some(b'I') | Some(b'W') | Some(b'E') | Some(b'F')
+            )
+        },
+        parse: klog::parse_klog_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Clf,
+        quick_check: |bytes| memchr::memchr(b'[', bytes).is_some(),
+        parse: access::parse_clf_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Ruby,
+        quick_check: |bytes| bytes.get(1..4) == Some(&b", ["[..]),
+        parse: ruby::parse_ruby_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Bracketed,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: java::parse_bracketed_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Redis,
+        quick_check: starts_with_digit,
+        parse: redis::parse_redis_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Setupapi,
+        quick_check: |bytes| matches!(bytes.first(), Some(b'>') | Some(b'<')),
+        parse: windows::parse_setupapi_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::CrashReport,
+        quick_check: |bytes| bytes.starts_with(b"Date/Time:"),
+        parse: crash::parse_crash_report_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Unity,
+        quick_check: starts_with_digit,
+        parse: unity::parse_unity_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Cryengine,
+        quick_check: |bytes| bytes.starts_with(b"<"),
+        parse: cryengine::parse_cryengine_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Source,
+        quick_check: |bytes| bytes.starts_with(b"L "),
+        parse: source::parse_source_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Squid,
+        quick_check: |bytes| bytes.first().is_some_and(|x| x.is_ascii_digit()),
+        parse: squid::parse_squid_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Powershell,
+        quick_check: |bytes| memchr::memmem::find(bytes, b"time: ").is_some(),
+        parse: windows::parse_powershell_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Maven,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: build::parse_maven_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Gradle,
+        quick_check: |bytes| bytes.get(12..14) == Some(b" ["),
+        parse: build::parse_gradle_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Yarn,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: npm::parse_yarn_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Envoy,
+        quick_check: |bytes| bytes.starts_with(b"["),
+        parse: envoy::parse_envoy_log_entry,
+    },
+    BuiltinFormat {
+        kind: FormatKind::Cisco,
+        quick_check: |bytes| memchr::memmem::find(bytes, b": %").is_some(),
+        parse: cisco::parse_cisco_log_entry,
+    },
+    #[cfg(feature = "json")]
+    BuiltinFormat {
+        kind: FormatKind::Gelf,
+        quick_check: |bytes| bytes.starts_with(b"{"),
+        parse: gelf::parse_gelf_log_entry,
+    },
+    #[cfg(feature = "json")]
+    BuiltinFormat {
+        kind: FormatKind::Json,
+        quick_check: |bytes| bytes.starts_with(b"{"),
+        parse: json::parse_json_log_entry,
+    },
+];
+
+/// Returns the built-in format with the given name.
+pub(crate) fn builtin_format(name: &str) -> Option<impl LogFormat> {
+    BUILTIN_FORMATS
+        .iter()
+        .find(|format| format.kind.as_str() == name)
+        .cloned()
+}
+
+/// Parses a line with the first matching built-in format.
+pub(crate) fn parse_builtin<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    BUILTIN_FORMATS
+        .iter()
+        .filter(|format| format.quick_check(bytes))
+        .find_map(|format| LogFormat::parse(format, bytes, ctx))
+}
+
+/// An ordered list of log formats.
+///
+/// Formats are tried in order and the first one that parses a line wins.
+#[derive(Clone)]
+pub struct Registry {
+    formats: Vec<Arc<dyn LogFormat>>,
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::builtin()
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Registry {
+        Registry {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Creates a registry with all built-in formats.
+    pub fn builtin() -> Registry {
+        Registry {
+            formats: BUILTIN_FORMATS
+                .iter()
+                .map(|format| Arc::new(*format) as Arc<dyn LogFormat>)
+                .collect(),
+        }
+    }
+
+    /// Creates a registry with the given built-in formats in the given
+    /// order.
+    ///
+    /// Kinds that are not backed by a registry format (such as
+    /// [`FormatKind::Epoch`]) are skipped.
+    pub fn with_builtin<I: IntoIterator<Item = FormatKind>>(kinds: I) -> Registry {
+        Registry {
+            formats: kinds
+                .into_iter()
+                .filter_map(|kind| BUILTIN_FORMATS.iter().find(|format| format.kind == kind))
+                .map(|format| Arc::new(*format) as Arc<dyn LogFormat>)
+                .collect(),
+        }
+    }
+
+    /// Removes all formats with the given name.
+    ///
+    /// Returns `true` if a format was removed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.formats.len();
+        self.formats.retain(|format| format.name() != name);
+        self.formats.len() != len
+    }
+
+    /// Adds a format after all registered formats.
+    pub fn register<F: LogFormat + 'static>(&mut self, format: F) {
+        self.formats.push(Arc::new(format));
+    }
+
+    /// Returns the names of the formats in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|format| format.name())
+    }
+
+    /// Parses a line with the first matching format.
+    pub fn parse<'a>(&self, bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+        self.formats
+            .iter()
+            .filter(|format| format.quick_check(bytes))
+            .find_map(|format| format.parse(bytes, ctx))
+    }
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_registry() {
+    struct Marker;
+
+    impl LogFormat for Marker {
+        fn name(&self) -> &str {
+            "marker"
+        }
+
+        fn quick_check(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(b"--")
+        }
+
+        fn parse<'a>(&self, bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+            Some(LogEntry::from_message_only(&bytes[2..]))
+        }
+    }
+
+    let mut registry = Registry::new();
+    registry.register(Marker);
+    assert_debug_snapshot!(registry, @r###"
+    [
+        "marker",
+    ]
+    "###);
+
+    let parser = Parser::builder().registry(registry).build();
+    assert_debug_snapshot!(parser.parse(b"-- MARK --"), @r###"
+    LogEntry {
+        timestamp: None,
+        message: " MARK --",
+    }
+    "###);
+    assert_debug_snapshot!(parser.parse(b"Tue Nov 21 00:30:05 2017 not registered"), @r###"
+    LogEntry {
+        timestamp: None,
+        message: "Tue Nov 21 00:30:05 2017 not registered",
+    }
+    "###);
+    #[cfg(not(feature = "json"))]
+    assert_debug_snapshot!(Registry::builtin(), @r###"
+    [
+        "c",
+        "short",
+        "serilog",
+        "minecraft",
+        "simple",
+        "common",
+        "common_alt",
+        "common_alt2",
+        "ue4",
+        "w3c",
+        "haproxy",
+        "audit",
+        "cups",
+        "journal",
+        "chrome",
+        "cri",
+        "log4j",
+        "actions",
+        "npm",
+        "hclog",
+        "capnslog",
+        "envoy_access",
+        "ceph",
+        "kafka",
+        "heroku",
+        "lambda",
+        "mysql",
+        "mongodb",
+        "cbs",
+        "unified",
+        "rfc3339",
+        "slash_date",
+        "dot_date",
+        "compact",
+        "xorg",
+        "uptime",
+        "delta",
+        "rfc5424",
+        "rfc3164",
+        "logcat",
+        "klog",
+        "clf",
+        "ruby",
+        "bracketed",
+        "redis",
+        "setupapi",
+        "crash_report",
+        "unity",
+        "cryengine",
+        "source",
+        "squid",
+        "powershell",
+        "maven",
+        "gradle",
+        "yarn",
+        "envoy",
+        "cisco",
+    ]
+    "###);
+}
+
+#[test]
+fn test_registry_with_builtin() {
+    let mut registry =
+        Registry::with_builtin(vec![FormatKind::Ue4, FormatKind::Common, FormatKind::Epoch]);
+    assert_debug_snapshot!(registry, @r###"
+    [
+        "ue4",
+        "common",
+    ]
+    "###);
+    assert!(registry.remove("ue4"));
+    assert!(!registry.remove("ue4"));
+    assert_debug_snapshot!(registry, @r###"
+    [
+        "common",
+    ]
+    "###);
+}
+
+#[test]
+fn test_builtin_order() {
+    // serilog and minecraft lines are also matched by `simple`, which would
+    // keep their level and thread in the message
+    let ctx = Parser::new().context();
+    for line in &[
+        &b"22:07:10 server  | detected binary path: /usr/bin/uwsgi"[..],
+        &b"[22:07:10] server started"[..],
+        &b"[10:07:10 PM] INF worker ready"[..],
+        &b"[22:07:10] [worker] ready"[..],
+    ] {
+        assert_eq!(Parser::new().parse(line).format(), Some(FormatKind::Simple));
+        assert!(dotnet::parse_serilog_log_entry(line, &ctx).is_none());
+        assert!(minecraft::parse_minecraft_log_entry(line, &ctx).is_none());
+    }
+}
+
+#[test]
+fn test_journal_quick_check() {
+    assert!(starts_with_journal_date(
+        b"Jun 01 12:00:00.123456 myhost sshd[123]: hi"
+    ));
+    assert!(starts_with_journal_date(
+        b"2021-06-01T12:00:00+0000 myhost kernel: hi"
+    ));
+    assert!(!starts_with_journal_date(b"Jun  1 12:00:00 message"));
+    assert!(!starts_with_journal_date(b"June 01 12:00:00 message"));
+    assert!(!starts_with_journal_date(b"worker 7 started"));
+    assert!(!starts_with_journal_date(b"2021/06/01 12:00:00 message"));
+}
@@ -0,0 +1,110 @@
+//! Direct access to the built-in formats.
+//!
+//! [`Parser`](crate::Parser) tries all formats in turn.  When the format of
+//! a file is already known, the functions in this module parse lines in
+//! exactly that format and skip the fallback chain.
+//!
+//! Every function has the same signature: it takes the line and a
+//! [`Context`](crate::Context) used to fill in missing date components,
+//! and returns `None` if the line is not in that format.
+//!
+//! ```
+//! use anylog::{formats, Context, NaiveTimezone, SystemClock};
+//!
+//! let ctx = Context::new(NaiveTimezone::Utc, &SystemClock);
+//! let entry = formats::parse_common_log_entry(b"2015-05-13 17:39:16 +0200: hello", &ctx);
+//! assert_eq!(entry.unwrap().message(), "hello");
+//! ```
+
+use crate::format::{self, LogFormat};
+
+pub use crate::access::parse_clf_log_entry;
+pub use crate::actions::parse_actions_log_entry;
+pub use crate::apple::parse_unified_log_entry;
+pub use crate::audit::parse_audit_log_entry;
+pub use crate::build::{parse_gradle_log_entry, parse_maven_log_entry};
+pub use crate::capnslog::parse_capnslog_log_entry;
+pub use crate::ceph::parse_ceph_log_entry;
+pub use crate::chrome::parse_chrome_log_entry;
+pub use crate::cisco::parse_cisco_log_entry;
+pub use crate::crash::parse_crash_report_log_entry;
+pub use crate::cri::parse_cri_log_entry;
+pub use crate::cryengine::parse_cryengine_log_entry;
+pub use crate::cups::parse_cups_log_entry;
+pub use crate::dotnet::parse_serilog_log_entry;
+pub use crate::envoy::{parse_envoy_access_log_entry, parse_envoy_log_entry};
+#[cfg(feature = "json")]
+pub use crate::gelf::parse_gelf_log_entry;
+pub use crate::haproxy::parse_haproxy_log_entry;
+pub use crate::hclog::parse_hclog_log_entry;
+pub use crate::heroku::parse_heroku_log_entry;
+pub use crate::java::{parse_bracketed_log_entry, parse_log4j_log_entry};
+#[cfg(feature = "json")]
+pub use crate::json::parse_json_log_entry;
+pub use crate::kafka::parse_kafka_log_entry;
+pub use crate::klog::parse_klog_log_entry;
+pub use crate::lambda::parse_lambda_log_entry;
+pub use crate::logcat::parse_logcat_log_entry;
+pub use crate::minecraft::parse_minecraft_log_entry;
+pub use crate::mongodb::parse_mongodb_log_entry;
+pub use crate::mysql::parse_mysql_log_entry;
+pub use crate::npm::{parse_npm_log_entry, parse_yarn_log_entry};
+pub use crate::parser::{
+    parse_c_log_entry, parse_common_alt2_log_entry, parse_common_alt_log_entry,
+    parse_common_log_entry, parse_compact_log_entry, parse_delta_log_entry,
+    parse_dot_date_log_entry, parse_rfc3339_log_entry, parse_short_log_entry,
+    parse_simple_log_entry, parse_slash_date_log_entry, parse_ue4_log_entry,
+    parse_uptime_log_entry,
+};
+pub use crate::redis::parse_redis_log_entry;
+pub use crate::ruby::parse_ruby_log_entry;
+pub use crate::source::parse_source_log_entry;
+pub use crate::squid::parse_squid_log_entry;
+pub use crate::syslog::{
+    parse_journal_log_entry, parse_rfc3164_log_entry, parse_rfc5424_log_entry,
+};
+pub use crate::unity::parse_unity_log_entry;
+pub use crate::windows::{
+    parse_cbs_log_entry, parse_powershell_log_entry, parse_setupapi_log_entry,
+};
+pub use crate::xorg::parse_xorg_log_entry;
+
+/// Returns a built-in format by name.
+///
+/// The names are the ones listed by [`Registry::names`](crate::Registry::names)
+/// for [`Registry::builtin`](crate::Registry::builtin).  The returned format
+/// can be registered with a [`Registry`](crate::Registry) to build a parser
+/// that only knows a subset of the formats.
+pub fn by_name(name: &str) -> Option<impl LogFormat> {
+    format::builtin_format(name)
+}
+
+#[cfg(test)]
+use crate::format::Registry;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_by_name() {
+    let mut registry = Registry::new();
+    registry.register(by_name("ue4").unwrap());
+    let parser = Parser::builder().registry(registry).build();
+    assert_debug_snapshot!(
+        (
+            parser.parse(b"[2018.10.29-16.56.37:542][  0]LogInit: hi").utc_timestamp(),
+            parser.parse(b"2015-05-13 17:39:16 +0200: not ue4").utc_timestamp(),
+            by_name("nope").is_none(),
+        ),
+        @r###"
+    (
+        Some(
+            2018-10-29T16:56:37.542Z,
+        ),
+        None,
+        true,
+    )
+    "###
+    );
+}
@@ -0,0 +1,103 @@
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+use crate::json;
+use crate::parser::Context;
+use crate::syslog;
+use crate::types::LogEntry;
+
+/// Parses a GELF (Graylog Extended Log Format) message.
+///
+/// The `short_message` is the message and the epoch `timestamp` and
+/// syslog `level` set the time and level.  The `host` is exposed as the
+/// `hostname` field and the additional fields as fields without their
+/// leading underscore.  Other keys, such as `full_message`, become fields
+/// as they are.
+///
+/// Example: `{"version":"1.1","host":"example.org","short_message":"A short message","timestamp":1622548800.123,"level":3}`
+pub fn parse_gelf_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let mut object = match serde_json::from_slice(bytes).ok()? {
+        Value::Object(object) => object,
+        _ => return None,
+    };
+    if !object.get("version")?.is_string() || !object.get("host")?.is_string() {
+        return None;
+    }
+    let message = match object.remove("short_message")? {
+        Value::String(message) => message,
+        _ => return None,
+    };
+    object.remove("version");
+
+    let mut entry = match object
+        .remove("timestamp")
+        .and_then(|x| json::parse_timestamp(&x, ctx))
+    {
+        Some(ts) => LogEntry::from_timestamp(ts, b""),
+        None => LogEntry::from_message_only(b""),
+    }
+    .with_owned_message(message);
+    if let Some(level) = object.remove("level").and_then(|x| x.as_u64()) {
+        entry = entry.with_level(Cow::Borrowed(syslog::severity_name(level.min(7) as u32)));
+    }
+    for (key, value) in object {
+        let key = match key.strip_prefix('_') {
+            Some(key) => key.to_string(),
+            None if key == "host" => "hostname".to_string(),
+            None => key,
+        };
+        entry = entry.with_field(key, json::value_to_string(value));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_gelf_log_entry() {
+    let entry = LogEntry::parse(
+        br#"{"version":"1.1","host":"example.org","short_message":"A short message","full_message":"Backtrace here\n\nmore stuff","timestamp":1622548800.123,"level":3,"_user_id":9001,"_facility":"payments"}"#,
+    );
+    assert_debug_snapshot!(
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ),
+        @r###"
+    (
+        Some(
+            Gelf,
+        ),
+        Some(
+            Utc(
+                2021-06-01T12:00:00.123Z,
+            ),
+        ),
+        Some(
+            Error,
+        ),
+        "A short message",
+        [
+            "facility=payments",
+            "user_id=9001",
+            "full_message=Backtrace here\n\nmore stuff",
+            "hostname=example.org",
+        ],
+    )
+    "###
+    );
+    assert_eq!(
+        LogEntry::parse(br#"{"host":"example.org","short_message":"no version"}"#).format(),
+        Some(FormatKind::Json)
+    );
+}
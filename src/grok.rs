@@ -0,0 +1,232 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref REFERENCE_RE: Regex =
+        Regex::new(r#"%\{([A-Za-z0-9_]+)(?::([^:}]+))?(?::[A-Za-z]+)?\}"#).unwrap();
+}
+
+/// The standard grok pattern library.
+///
+/// The timestamp patterns capture their components into the groups
+/// understood by custom formats instead of referencing the generic date
+/// patterns, so that matching them yields the timestamp of the entry.
+static PATTERNS: &[(&str, &str)] = &[
+    ("USERNAME", r"[a-zA-Z0-9._-]+"),
+    ("USER", r"%{USERNAME}"),
+    (
+        "EMAILLOCALPART",
+        r"[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+)*",
+    ),
+    ("EMAILADDRESS", r"%{EMAILLOCALPART}@%{HOSTNAME}"),
+    ("INT", r"[+-]?\d+"),
+    ("BASE10NUM", r"[+-]?(?:\d+(?:\.\d+)?|\.\d+)"),
+    ("NUMBER", r"%{BASE10NUM}"),
+    ("BASE16NUM", r"[+-]?(?:0x)?[0-9A-Fa-f]+"),
+    ("POSINT", r"[1-9]\d*"),
+    ("NONNEGINT", r"\d+"),
+    ("WORD", r"\b\w+\b"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    (
+        "QUOTEDSTRING",
+        r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|`(?:[^`\\]|\\.)*`"#,
+    ),
+    (
+        "UUID",
+        r"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}",
+    ),
+    ("MAC", r"(?:[A-Fa-f0-9]{2}[:-]){5}[A-Fa-f0-9]{2}"),
+    (
+        "IPV6",
+        r"[0-9A-Fa-f]{0,4}(?::[0-9A-Fa-f]{0,4}){2,7}(?:%[0-9A-Za-z]+)?",
+    ),
+    (
+        "IPV4",
+        r"(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)",
+    ),
+    ("IP", r"(?:%{IPV6}|%{IPV4})"),
+    (
+        "HOSTNAME",
+        r"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?\b",
+    ),
+    ("IPORHOST", r"(?:%{IP}|%{HOSTNAME})"),
+    ("HOSTPORT", r"%{IPORHOST}:%{POSINT}"),
+    ("PATH", r"(?:%{UNIXPATH}|%{WINPATH})"),
+    ("UNIXPATH", r"(?:/[\w_%!$@:.,+~-]*)+"),
+    ("WINPATH", r"(?:[A-Za-z]+:|\\)(?:\\[^\\?*]*)+"),
+    ("URIPROTO", r"[A-Za-z][A-Za-z0-9+\-.]*"),
+    ("URIPATH", r"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_\-]*)+"),
+    ("URIPARAM", r"\?[A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?\-\[\]<>]*"),
+    ("URIPATHPARAM", r"%{URIPATH}(?:%{URIPARAM})?"),
+    (
+        "MONTH",
+        r"\b(?:[Jj]an(?:uary)?|[Ff]eb(?:ruary)?|[Mm]ar(?:ch)?|[Aa]pr(?:il)?|[Mm]ay|[Jj]un(?:e)?|[Jj]ul(?:y)?|[Aa]ug(?:ust)?|[Ss]ep(?:tember)?|[Oo]ct(?:ober)?|[Nn]ov(?:ember)?|[Dd]ec(?:ember)?)\b",
+    ),
+    ("MONTHNUM", r"(?:0?[1-9]|1[0-2])"),
+    ("MONTHDAY", r"(?:(?:0[1-9])|(?:[12][0-9])|(?:3[01])|[1-9])"),
+    (
+        "DAY",
+        r"(?:Mon(?:day)?|Tue(?:sday)?|Wed(?:nesday)?|Thu(?:rsday)?|Fri(?:day)?|Sat(?:urday)?|Sun(?:day)?)",
+    ),
+    ("YEAR", r"\d\d(?:\d\d)?"),
+    ("HOUR", r"(?:2[0123]|[01]?[0-9])"),
+    ("MINUTE", r"(?:[0-5][0-9])"),
+    ("SECOND", r"(?:(?:[0-5]?[0-9]|60)(?:[:.,][0-9]+)?)"),
+    ("TIME", r"%{HOUR}:%{MINUTE}(?::%{SECOND})?"),
+    ("DATE_US", r"%{MONTHNUM}[/-]%{MONTHDAY}[/-]%{YEAR}"),
+    ("DATE_EU", r"%{MONTHDAY}[./-]%{MONTHNUM}[./-]%{YEAR}"),
+    ("ISO8601_TIMEZONE", r"(?:Z|[+-]%{HOUR}(?::?%{MINUTE}))"),
+    (
+        "TIMESTAMP_ISO8601",
+        r"(?P<year>\d{4})-(?P<month>\d\d)-(?P<day>\d\d)[T ](?P<hour>\d\d):?(?P<minute>\d\d)(?::?(?P<second>\d\d)(?:[.,]\d+)?)?(?P<offset>Z|[+-]\d\d(?::?\d\d)?)?",
+    ),
+    (
+        "SYSLOGTIMESTAMP",
+        r"(?P<month>Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec) +(?P<day>\d\d?) (?P<hour>\d\d):(?P<minute>\d\d):(?P<second>\d\d)",
+    ),
+    (
+        "HTTPDATE",
+        r"(?P<day>\d\d)/(?P<month>Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/(?P<year>\d{4}):(?P<hour>\d\d):(?P<minute>\d\d):(?P<second>\d\d) (?P<offset>[+-]\d{4})",
+    ),
+    (
+        "LOGLEVEL",
+        r"(?:[Aa]lert|ALERT|[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo?(?:rmation)?|INFO?(?:RMATION)?|[Ww]arn?(?:ing)?|WARN?(?:ING)?|[Ee]rr?(?:or)?|ERR?(?:OR)?|[Cc]rit?(?:ical)?|CRIT?(?:ICAL)?|[Ff]atal|FATAL|[Ss]evere|SEVERE|EMERG(?:ENCY)?|[Ee]merg(?:ency)?)",
+    ),
+    ("PROG", r"[\x21-\x5a\x5c\x5e-\x7e]+"),
+    ("SYSLOGPROG", r"%{PROG}(?:\[%{POSINT}\])?"),
+    ("SYSLOGHOST", r"%{IPORHOST}"),
+];
+
+/// Limits the nesting of pattern references to catch cycles.
+const MAX_DEPTH: usize = 16;
+
+fn lookup(name: &str) -> Option<&'static str> {
+    PATTERNS
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, pattern)| *pattern)
+}
+
+/// Turns a grok semantic name into a valid capture group name.
+fn group_name(semantic: &str) -> String {
+    let mut name: String = semantic
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn expand_into(expr: &str, out: &mut String, depth: usize) -> Result<(), String> {
+    let mut last = 0;
+    for caps in REFERENCE_RE.captures_iter(expr) {
+        let all = caps.get(0).unwrap();
+        out.push_str(&expr[last..all.start()]);
+        last = all.end();
+
+        let name = &caps[1];
+        let pattern = match lookup(name) {
+            Some(pattern) if depth < MAX_DEPTH => pattern,
+            _ => return Err(name.to_string()),
+        };
+        // timestamp patterns carry their own groups, a semantic name on
+        // them would only produce an unused capture.
+        match caps.get(2) {
+            Some(semantic) if !pattern.contains("(?P<") => {
+                out.push_str("(?P<");
+                out.push_str(&group_name(semantic.as_str()));
+                out.push('>');
+            }
+            _ => out.push_str("(?:"),
+        }
+        expand_into(pattern, out, depth + 1)?;
+        out.push(')');
+    }
+    out.push_str(&expr[last..]);
+    Ok(())
+}
+
+/// Expands a grok expression into a regular expression.
+///
+/// On failure the name of the unknown pattern is returned.
+pub(crate) fn expand(expr: &str) -> Result<String, String> {
+    // ASCII mode gives the Perl classes used by the library without
+    // requiring the Unicode tables.
+    let mut out = String::from("(?-u)");
+    expand_into(expr, &mut out, 0)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+use crate::custom::CustomFormat;
+#[cfg(test)]
+use crate::parser::test_builder;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_grok_format() {
+    let parse = |expr, bytes| {
+        let entry = test_builder()
+            .custom_format(CustomFormat::from_grok("test", expr).unwrap())
+            .build()
+            .parse(bytes);
+        (
+            entry.level_name().map(|x| x.to_string()),
+            entry.into_owned(),
+        )
+    };
+    assert_debug_snapshot!(parse(
+        "^%{TIMESTAMP_ISO8601:timestamp} \\[%{LOGLEVEL:level}\\] %{GREEDYDATA:message}$",
+        b"2021-06-01T12:00:00.123+02:00 [WARN] disk almost full",
+    ), @r###"
+    (
+        Some(
+            "WARN",
+        ),
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2021-06-01T12:00:00+02:00,
+                ),
+            ),
+            message: "disk almost full",
+        },
+    )
+    "###);
+    assert_debug_snapshot!(parse(
+        "^%{SYSLOGTIMESTAMP:timestamp} %{SYSLOGHOST:host} %{SYSLOGPROG}: %{GREEDYDATA:message}$",
+        b"Nov 20 21:56:01 herzog sshd[123]: Accepted publickey",
+    ), @r###"
+    (
+        None,
+        LogEntry {
+            timestamp: Some(
+                Local(
+                    2017-11-20T21:56:01+01:00,
+                ),
+            ),
+            message: "Accepted publickey",
+        },
+    )
+    "###);
+}
+
+#[test]
+fn test_grok_unknown_pattern() {
+    assert_debug_snapshot!(
+        CustomFormat::from_grok("test", "%{NOPE:message}")
+            .map_err(|err| err.to_string())
+            .map(|_| ()),
+        @r###"
+    Err(
+        "format test: unknown grok pattern NOPE",
+    )
+    "###
+    );
+}
@@ -0,0 +1,153 @@
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref HAPROXY_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?:.*?\x20)?haproxy\[([0-9]+)\]:
+            \x20([0-9A-Fa-f.:]+):([0-9]+)
+            \x20\[(
+                ([0-9]{2})/(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/([0-9]{4})
+                :([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{3})
+            )\]
+            \x20(
+                ([^\x20]+)
+                \x20([^\x20/]+)/([^\x20]+)
+                \x20(-?[0-9]+(?:/-?[0-9]+){4})
+                \x20(-?[0-9]{1,3})
+                \x20\+?([0-9]+)
+                (?:\x20[^\x20]+\x20[^\x20]+\x20([^\x20]{4}))?
+                .*
+            )
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of an HAProxy HTTP log, with or without a syslog header.
+///
+/// The accept date in brackets is the timestamp and the message is the part
+/// following it.  The process id, client address and port, frontend,
+/// backend, server, timers, status code, response size and termination
+/// state are exposed as the `procid`, `client_ip`, `client_port`,
+/// `frontend`, `backend`, `server`, `timers`, `status`, `bytes` and
+/// `termination_state` fields.
+///
+/// Example: `haproxy[1234]: 1.2.3.4:5678 [01/Jun/2021:12:00:00.123] fe be/srv 0/0/1/2/3 200 512 ...`
+pub fn parse_haproxy_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = HAPROXY_RE.captures(bytes)?;
+    // all groups except the message only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let naive = naive_datetime(
+        num(7) as i32,
+        get_month(&caps[6])?,
+        num(5),
+        num(8),
+        num(9),
+        num(10),
+    )? + Duration::milliseconds(num(11).into());
+
+    let message = caps.get(12).unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(4).unwrap().range(), message);
+    let fields = [
+        ("procid", caps.get(1)),
+        ("client_ip", caps.get(2)),
+        ("client_port", caps.get(3)),
+        ("frontend", caps.get(13)),
+        ("backend", caps.get(14)),
+        ("server", caps.get(15)),
+        ("timers", caps.get(16)),
+        ("status", caps.get(17)),
+        ("bytes", caps.get(18)),
+        ("termination_state", caps.get(19)),
+    ];
+    for (key, value) in fields.iter() {
+        if let Some(value) = value {
+            entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+        }
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_haproxy_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"haproxy[1234]: 1.2.3.4:5678 [01/Jun/2021:12:00:00.123] http-in static/srv1 10/0/30/69/109 200 2750 - - ---- 1/1/0/0/0 0/0 \"GET /index.html HTTP/1.1\""[..]),
+            parse(b"Jun  1 12:00:01 lb1 haproxy[1234]: 10.0.0.1:40000 [01/Jun/2021:12:00:01.500] http-in~ app/<NOSRV> -1/-1/-1/-1/0 503 212 - - SC-- 0/0/0/0/0 0/0 \"GET / HTTP/1.1\""),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Haproxy,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            "http-in static/srv1 10/0/30/69/109 200 2750 - - ---- 1/1/0/0/0 0/0 \"GET /index.html HTTP/1.1\"",
+            [
+                "procid=1234",
+                "client_ip=1.2.3.4",
+                "client_port=5678",
+                "frontend=http-in",
+                "backend=static",
+                "server=srv1",
+                "timers=10/0/30/69/109",
+                "status=200",
+                "bytes=2750",
+                "termination_state=----",
+            ],
+        ),
+        (
+            Some(
+                Haproxy,
+            ),
+            Some(
+                "2021-06-01 12:00:01.500",
+            ),
+            "http-in~ app/<NOSRV> -1/-1/-1/-1/0 503 212 - - SC-- 0/0/0/0/0 0/0 \"GET / HTTP/1.1\"",
+            [
+                "procid=1234",
+                "client_ip=10.0.0.1",
+                "client_port=40000",
+                "frontend=http-in~",
+                "backend=app",
+                "server=<NOSRV>",
+                "timers=-1/-1/-1/-1/0",
+                "status=503",
+                "bytes=212",
+                "termination_state=SC--",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,192 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref HCLOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[(TRACE|DEBUG|INFO|WARN|ERROR)\]\x20+
+            (?:([A-Za-z0-9_@\-]+(?:[./][A-Za-z0-9_@\-]+)*):(?:\x20|$))?
+        "#
+    )
+    .unwrap();
+    static ref GO_LOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (
+                ([0-9]{4})/([0-9]{2})/([0-9]{2})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.([0-9]{6}))?
+            )
+            \x20
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line written by HashiCorp's hclog (Vault, Consul, Nomad and
+/// Terraform with `TF_LOG`).
+///
+/// The bracketed level sets the level and the logger name before the
+/// first colon (`agent.server`, `provider.terraform-provider-aws`) is
+/// exposed as the `component` field.  Besides the RFC 3339 timestamps of
+/// hclog the `2021/06/01 12:00:00` timestamps of older Terraform versions
+/// are understood.
+///
+/// Example: `2021-06-01T12:00:00.123+0200 [INFO]  agent.server: Handled event`
+pub fn parse_hclog_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = match GO_LOG_RE.captures(bytes) {
+        Some(caps) => {
+            // the timestamp only matches ASCII
+            let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+            let mut naive = naive_datetime(num(2) as i32, num(3), num(4), num(5), num(6), num(7))?;
+            if caps.get(8).is_some() {
+                naive += Duration::microseconds(num(8).into());
+            }
+            let message = caps.get(0).unwrap().end()..bytes.len();
+            ctx.local_entry(naive, &bytes[message.clone()])?
+                .with_spans(caps.get(1).unwrap().range(), message)
+        }
+        None => parser::parse_rfc3339_log_entry(bytes, ctx)?,
+    };
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = HCLOG_RE.captures(&bytes[message_span.clone()])?;
+
+    // the level and component only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_level(Cow::Borrowed(text(1)));
+    Some(match caps.get(2) {
+        Some(_) => entry.with_field("component", text(2)),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_hclog_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"2021-06-01T12:00:00.123+0200 [INFO]  agent.server: Handled event for server in area: event=member-join"),
+            parse(b"2021-06-01T12:00:01.456+0200 [WARN]  core: no `api_addr` value specified in config"),
+            parse(b"2021-06-01T12:00:02.789+0200 [DEBUG] provider.terraform-provider-aws_v3.0.0_x5: plugin address: network=unix"),
+            parse(b"2021-06-01T12:00:03.000+0200 [INFO]  Terraform version: 1.0.0"),
+            parse(b"2021/06/01 12:00:04 [DEBUG] backend/local: starting Apply operation"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Hclog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "Handled event for server in area: event=member-join",
+            [
+                "component=agent.server",
+                "event=member-join",
+            ],
+        ),
+        (
+            Some(
+                Hclog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:01.456+02:00,
+                ),
+            ),
+            Some(
+                Warning,
+            ),
+            "no `api_addr` value specified in config",
+            [
+                "component=core",
+            ],
+        ),
+        (
+            Some(
+                Hclog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:02.789+02:00,
+                ),
+            ),
+            Some(
+                Debug,
+            ),
+            "plugin address: network=unix",
+            [
+                "component=provider.terraform-provider-aws_v3.0.0_x5",
+                "network=unix",
+            ],
+        ),
+        (
+            Some(
+                Hclog,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:03+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "Terraform version: 1.0.0",
+            [],
+        ),
+        (
+            Some(
+                Hclog,
+            ),
+            Some(
+                Local(
+                    2021-06-01T12:00:04+02:00,
+                ),
+            ),
+            Some(
+                Debug,
+            ),
+            "starting Apply operation",
+            [
+                "component=backend/local",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,73 @@
+use std::str;
+
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use regex::bytes::{Captures, Regex};
+
+use crate::parser::naive_datetime;
+use crate::types::NaiveTimezone;
+
+lazy_static! {
+    static ref UE4_HEADER_RE: Regex = Regex::new(
+        r#"^Log file open, ([0-9]{2})/([0-9]{2})/([0-9]{2}) ([0-9]{2}):([0-9]{2}):([0-9]{2})"#
+    )
+    .unwrap();
+    static ref W3C_HEADER_RE: Regex = Regex::new(
+        r#"^#Date:[\t ]*([0-9]{4})-([0-9]{2})-([0-9]{2}) ([0-9]{2}):([0-9]{2}):([0-9]{2})"#
+    )
+    .unwrap();
+    static ref FFMPEG_HEADER_RE: Regex = Regex::new(
+        r#"^ffmpeg started on ([0-9]{4})-([0-9]{2})-([0-9]{2}) at ([0-9]{2}):([0-9]{2}):([0-9]{2})"#
+    )
+    .unwrap();
+}
+
+/// Context established by a preamble line at the start of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Header {
+    /// The time the file was opened, in the timezone of the file.
+    pub start: NaiveDateTime,
+    /// The timezone of the timestamps in the file if the header defines it.
+    pub timezone: Option<NaiveTimezone>,
+}
+
+fn num<T: str::FromStr>(caps: &Captures, idx: usize) -> Option<T> {
+    str::from_utf8(caps.get(idx)?.as_bytes()).ok()?.parse().ok()
+}
+
+fn start_from_caps(caps: &Captures, year: i32, month: usize, day: usize) -> Option<NaiveDateTime> {
+    naive_datetime(
+        year,
+        num(caps, month)?,
+        num(caps, day)?,
+        num(caps, 4)?,
+        num(caps, 5)?,
+        num(caps, 6)?,
+    )
+}
+
+/// Recognizes preamble lines that carry the start time of a file.
+///
+/// These are the `Log file open` line of Unreal Engine logs, the `#Date:`
+/// directive of W3C extended logs (which are always in UTC) and the first
+/// line of ffmpeg reports.
+pub(crate) fn parse_header(bytes: &[u8]) -> Option<Header> {
+    if let Some(caps) = UE4_HEADER_RE.captures(bytes) {
+        Some(Header {
+            start: start_from_caps(&caps, 2000 + num::<i32>(&caps, 3)?, 1, 2)?,
+            timezone: None,
+        })
+    } else if let Some(caps) = W3C_HEADER_RE.captures(bytes) {
+        Some(Header {
+            start: start_from_caps(&caps, num(&caps, 1)?, 2, 3)?,
+            timezone: Some(NaiveTimezone::Utc),
+        })
+    } else if let Some(caps) = FFMPEG_HEADER_RE.captures(bytes) {
+        Some(Header {
+            start: start_from_caps(&caps, num(&caps, 1)?, 2, 3)?,
+            timezone: None,
+        })
+    } else {
+        None
+    }
+}
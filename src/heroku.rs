@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::level::Level;
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref LOGPLEX_RE: Regex =
+        Regex::new(r#"^(app|heroku)\[([A-Za-z0-9_.\-]+)\]:(?:\x20|$)"#).unwrap();
+    static ref AT_RE: Regex = Regex::new(r#"(?:^|\x20)at=([a-z]+)(?:\x20|$)"#).unwrap();
+}
+
+/// Parses a line drained from Heroku's logplex.
+///
+/// The source and dyno (`heroku` and `router` in `heroku[router]`) are
+/// exposed as the `source` and `dyno` fields.  The logfmt payload of the
+/// platform lines is available through [`LogEntry::fields`] and its `at`
+/// key sets the level.
+///
+/// Example: `2021-06-01T12:00:00.123456+00:00 heroku[router]: at=info method=GET path="/"`
+pub fn parse_heroku_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = LOGPLEX_RE.captures(&bytes[message_span.clone()])?;
+
+    // the source and dyno only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let mut entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message.clone())
+        .with_field("source", text(1))
+        .with_field("dyno", text(2));
+    if let Some(at) = AT_RE.captures(&bytes[message]) {
+        let at = str::from_utf8(at.get(1).unwrap().as_bytes()).unwrap();
+        if Level::from_name(at).is_some() {
+            entry = entry.with_level(Cow::Borrowed(at));
+        }
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_heroku_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01T12:00:00.123456+00:00 heroku[router]: at=info method=GET path=\"/\" status=200"[..]),
+            parse(b"2021-06-01T12:00:01.000000+00:00 heroku[router]: at=error code=H12 desc=\"Request timeout\""),
+            parse(b"2021-06-01T12:00:02.000000+00:00 app[web.1]: Listening on port 3000"),
+            parse(b"2021-06-01T12:00:03.000000+00:00 sshd[123]: Accepted publickey"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Heroku,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123456+00:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "at=info method=GET path=\"/\" status=200",
+            [
+                "source=heroku",
+                "dyno=router",
+                "at=info",
+                "method=GET",
+                "path=/",
+                "status=200",
+            ],
+        ),
+        (
+            Some(
+                Heroku,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:01+00:00,
+                ),
+            ),
+            Some(
+                Error,
+            ),
+            "at=error code=H12 desc=\"Request timeout\"",
+            [
+                "source=heroku",
+                "dyno=router",
+                "at=error",
+                "code=H12",
+                "desc=Request timeout",
+            ],
+        ),
+        (
+            Some(
+                Heroku,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:02+00:00,
+                ),
+            ),
+            None,
+            "Listening on port 3000",
+            [
+                "source=app",
+                "dyno=web.1",
+            ],
+        ),
+        (
+            Some(
+                Rfc3339,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:03+00:00,
+                ),
+            ),
+            None,
+            "sshd[123]: Accepted publickey",
+            [],
+        ),
+    ]
+    "###
+    );
+}
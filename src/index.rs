@@ -0,0 +1,131 @@
+use std::io::{self, BufRead, Seek, SeekFrom};
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use crate::parser::Parser;
+use crate::stream::LineReader;
+
+/// An index from timestamps to byte offsets within a log file.
+///
+/// Building the index reads the file once and records the offset of a line
+/// whenever the timestamps advanced by at least the configured interval.
+/// Afterwards a reader can seek close to a point in time without reading
+/// everything before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimeIndex {
+    points: Vec<(DateTime<Utc>, u64)>,
+}
+
+impl TimeIndex {
+    /// Builds an index by parsing all lines of a reader.
+    ///
+    /// Lines with timestamps before the latest indexed one are ignored, so
+    /// the index stays sorted even if the log is not.
+    pub fn build<R: BufRead>(
+        parser: &Parser,
+        reader: R,
+        interval: Duration,
+    ) -> io::Result<TimeIndex> {
+        let ctx = parser.context().for_stream();
+        let mut lines = LineReader::new(reader).split_on_nul(parser.skips_binary());
+        let mut points: Vec<(DateTime<Utc>, u64)> = Vec::new();
+        let mut buf = Vec::new();
+        let limit = parser.max_line_length().unwrap_or(usize::MAX);
+        loop {
+            let offset = lines.position();
+            buf.clear();
+            if !lines.read_line(&mut buf, limit)? {
+                break;
+            }
+            let ts = match parser.parse_with_context(&buf, &ctx).utc_timestamp() {
+                Some(ts) => ts,
+                None => continue,
+            };
+            match points.last() {
+                Some(&(last, _)) if ts < last + interval => {}
+                _ => points.push((ts, offset)),
+            }
+        }
+        Ok(TimeIndex { points })
+    }
+
+    /// Returns the indexed timestamps and their byte offsets.
+    pub fn points(&self) -> &[(DateTime<Utc>, u64)] {
+        &self.points
+    }
+
+    /// Returns the offset to start reading from to find entries at or after
+    /// the given time.
+    pub fn offset_for(&self, ts: DateTime<Utc>) -> u64 {
+        match self.points.partition_point(|&(point, _)| point <= ts) {
+            0 => 0,
+            idx => self.points[idx - 1].1,
+        }
+    }
+
+    /// Seeks a reader to the offset for the given time.
+    ///
+    /// Entries before `ts` can still follow; they are within one interval
+    /// of the indexed point.  Returns the new position.
+    pub fn seek_to<R: Seek>(&self, reader: &mut R, ts: DateTime<Utc>) -> io::Result<u64> {
+        reader.seek(SeekFrom::Start(self.offset_for(ts)))
+    }
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+#[cfg(test)]
+use std::io::Cursor;
+
+#[test]
+fn test_time_index() {
+    let input = (0..10)
+        .map(|idx| {
+            format!(
+                "2017-01-01 12:{:02}:00 +0000: minute {}\ncontinuation\n",
+                idx, idx
+            )
+        })
+        .collect::<String>();
+    let index = TimeIndex::build(&Parser::new(), input.as_bytes(), Duration::minutes(3)).unwrap();
+    assert_debug_snapshot!(index.points(), @r###"
+    [
+        (
+            2017-01-01T12:00:00Z,
+            0,
+        ),
+        (
+            2017-01-01T12:03:00Z,
+            147,
+        ),
+        (
+            2017-01-01T12:06:00Z,
+            294,
+        ),
+        (
+            2017-01-01T12:09:00Z,
+            441,
+        ),
+    ]
+    "###);
+
+    let mut reader = Cursor::new(input.as_bytes());
+    index
+        .seek_to(
+            &mut reader,
+            Utc.with_ymd_and_hms(2017, 1, 1, 12, 7, 30).unwrap(),
+        )
+        .unwrap();
+    let first = Parser::new().read_entries(reader).next().unwrap().unwrap();
+    assert_debug_snapshot!(first, @r###"
+    LogEntry {
+        timestamp: Some(
+            Fixed(
+                2017-01-01T12:06:00+00:00,
+            ),
+        ),
+        message: "minute 6",
+    }
+    "###);
+}
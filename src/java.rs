@@ -0,0 +1,365 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::format::FormatKind;
+use crate::parser::{self, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref LOG4J_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[([^\]]*)\]
+            \x20+(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)
+            \x20+([A-Za-z0-9_$.]+)
+            \x20+-\x20?
+        "#
+    )
+    .unwrap();
+    static ref BRACKETED_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[(
+                ([0-9]{4})-([0-9]{2})-([0-9]{2})
+                T([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:[,.]([0-9]{3}))?
+            )\]
+            \[(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\x20*\]
+            \[([^\]\x20]+)\x20*\]
+            \x20(?:\[([^\]]*)\]\x20)?
+            (.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref JUL_SOURCE_RE: Regex =
+        Regex::new(r#"^([A-Za-z_$][A-Za-z0-9_$.]*)(?:\x20([A-Za-z0-9_$<>]+))?$"#).unwrap();
+}
+
+/// The levels of `java.util.logging`.
+const JUL_LEVELS: &[&str] = &[
+    "SEVERE", "WARNING", "INFO", "CONFIG", "FINE", "FINER", "FINEST",
+];
+
+/// Parses a line written with the default log4j or logback pattern.
+///
+/// The timestamp can be in any of the `%d{ISO8601}` variants.  The level
+/// sets the level of the entry and the thread and logger are exposed as the
+/// `thread` and `logger` fields.
+///
+/// Example: `2021-06-01 12:00:00.123 [main] INFO com.example.Foo - message`
+pub fn parse_log4j_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = LOG4J_RE.captures(&bytes[message_span.clone()])?;
+
+    // the level and logger only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        entry
+            .with_message(&bytes[message.clone()])
+            .with_spans(ts_span, message)
+            .with_level(Cow::Borrowed(text(2)))
+            .with_field("thread", String::from_utf8_lossy(&caps[1]).into_owned())
+            .with_field("logger", text(3)),
+    )
+}
+
+/// Parses a line in the bracketed log4j2 pattern used by Elasticsearch.
+///
+/// The level sets the level of the entry and the logger and node name are
+/// exposed as the `logger` and `node` fields.
+///
+/// Example: `[2021-06-01T12:00:00,123][INFO ][o.e.n.Node] [node-1] message`
+pub fn parse_bracketed_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = BRACKETED_RE.captures(bytes)?;
+    // the timestamp, level and logger only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let ms = caps.get(8).map_or(0, |_| num(8));
+    let naive = naive_datetime(num(2) as i32, num(3), num(4), num(5), num(6), num(7))?
+        + Duration::milliseconds(ms.into());
+
+    let message = caps.get(12).unwrap().range();
+    let entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(1).unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(9)))
+        .with_field("logger", text(10));
+    Some(match caps.get(11) {
+        Some(node) => entry.with_field("node", String::from_utf8_lossy(node.as_bytes())),
+        None => entry,
+    })
+}
+
+/// Checks if an entry is the first line of a `java.util.logging` record.
+///
+/// The default `SimpleFormatter` writes the timestamp with the source class
+/// and method and puts the level and message on the next line.
+pub(crate) fn is_jul_header(entry: &LogEntry) -> bool {
+    entry.format() == Some(FormatKind::CommonAlt2)
+        && JUL_SOURCE_RE.is_match(entry.message().as_bytes())
+}
+
+/// Merges the two lines of a `java.util.logging` record.
+///
+/// Returns `None` if `next` is not the `LEVEL: message` line of a record.
+pub(crate) fn join_jul_record(header: &LogEntry, next: &LogEntry) -> Option<LogEntry<'static>> {
+    let level = next.level_name().filter(|x| JUL_LEVELS.contains(x))?;
+    if next.timestamp().is_some() {
+        return None;
+    }
+    // the marker is still there if the parser keeps level markers
+    let message = next.message();
+    let message = message
+        .strip_prefix(level)
+        .and_then(|x| x.strip_prefix(':'))
+        .map_or(message, |x| x.trim_start());
+
+    let caps = JUL_SOURCE_RE.captures(header.message().as_bytes())?;
+    let mut entry = header
+        .clone()
+        .into_owned()
+        .with_owned_message(message.to_string())
+        .with_level(Cow::Owned(level.to_string()))
+        .with_format(FormatKind::Jul)
+        .with_field("logger", String::from_utf8_lossy(&caps[1]).into_owned());
+    if let Some(method) = caps.get(2) {
+        entry = entry.with_field(
+            "method",
+            String::from_utf8_lossy(method.as_bytes()).into_owned(),
+        );
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_log4j_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01 12:00:00.123 [main] INFO com.example.Foo - message"[..]),
+            parse(b"2021-06-01 12:00:00,123 [http-nio-8080-exec-1] WARN  o.s.w.s.PageNotFound - No mapping"),
+            parse(b"2021-06-01T12:00:00.123+02:00 [pool-1-thread-2] ERROR com.example.Job - failed"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Log4j,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "message",
+            [
+                "thread=main",
+                "logger=com.example.Foo",
+            ],
+        ),
+        (
+            Some(
+                Log4j,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Warning,
+            ),
+            "No mapping",
+            [
+                "thread=http-nio-8080-exec-1",
+                "logger=o.s.w.s.PageNotFound",
+            ],
+        ),
+        (
+            Some(
+                Log4j,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Error,
+            ),
+            "failed",
+            [
+                "thread=pool-1-thread-2",
+                "logger=com.example.Job",
+            ],
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_parse_bracketed_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"[2021-06-01T12:00:00,123][INFO ][o.e.n.Node               ] [node-1] started"[..]),
+            parse(b"[2021-06-01T12:00:01,500][WARN ][o.e.c.r.a.DiskThresholdMonitor] [node-1] high disk watermark exceeded"),
+            parse(b"[2021-06-01T12:00:02,000][ERROR][o.e.b.Bootstrap] Exception"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Bracketed,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "started",
+            [
+                "logger=o.e.n.Node",
+                "node=node-1",
+            ],
+        ),
+        (
+            Some(
+                Bracketed,
+            ),
+            Some(
+                "2021-06-01 12:00:01.500",
+            ),
+            Some(
+                Warning,
+            ),
+            "high disk watermark exceeded",
+            [
+                "logger=o.e.c.r.a.DiskThresholdMonitor",
+                "node=node-1",
+            ],
+        ),
+        (
+            Some(
+                Bracketed,
+            ),
+            Some(
+                "2021-06-01 12:00:02",
+            ),
+            Some(
+                Error,
+            ),
+            "Exception",
+            [
+                "logger=o.e.b.Bootstrap",
+            ],
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_join_jul_records() {
+    let input = &b"Jan 03, 2016 10:29:55 PM com.example.Foo bar\nSEVERE: something broke\nJan 03, 2016 10:29:56 PM org.apache.catalina.startup.Catalina start\nINFO: Server startup in 512 ms\nJan 03, 2016 10:29:57 PM not a record\n"[..];
+    assert_debug_snapshot!(
+        crate::parser::Parser::new()
+            .read_entries(input)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (
+                    entry.format(),
+                    entry.local_timestamp().map(|x| x.naive_local().to_string()),
+                    entry.level(),
+                    entry.message().to_string(),
+                    entry
+                        .fields()
+                        .map(|(k, v)| format!("{}={}", k, v))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            Some(
+                Jul,
+            ),
+            Some(
+                "2016-01-03 22:29:55",
+            ),
+            Some(
+                Error,
+            ),
+            "something broke",
+            [
+                "logger=com.example.Foo",
+                "method=bar",
+            ],
+        ),
+        (
+            Some(
+                Jul,
+            ),
+            Some(
+                "2016-01-03 22:29:56",
+            ),
+            Some(
+                Info,
+            ),
+            "Server startup in 512 ms",
+            [
+                "logger=org.apache.catalina.startup.Catalina",
+                "method=start",
+            ],
+        ),
+        (
+            Some(
+                CommonAlt2,
+            ),
+            Some(
+                "2016-01-03 22:29:57",
+            ),
+            None,
+            "not a record",
+            [],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,300 @@
+use chrono::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::epoch::EpochHeuristics;
+use crate::format::{parse_builtin, FormatKind, LogFormat};
+use crate::parser::Context;
+use crate::types::{LogEntry, Timestamp};
+
+const TIMESTAMP_KEYS: &[&str] = &["timestamp", "time", "ts", "@timestamp"];
+const MESSAGE_KEYS: &[&str] = &["message", "msg"];
+const LEVEL_KEYS: &[&str] = &["level", "severity", "lvl"];
+
+/// A format for lines that are JSON objects.
+///
+/// The timestamp and message are taken from the first of the configured
+/// keys present in the object, the level from `level`, `severity` or
+/// `lvl`.  All other keys become fields of the entry, values that are not
+/// strings are kept as JSON.  Timestamps can be strings in any of the
+/// built-in formats or epoch numbers.
+///
+/// A JSON format with the default keys is part of the built-in formats,
+/// add a configured one with [`ParserBuilder::format`] to look at other
+/// keys.
+///
+/// [`ParserBuilder::format`]: crate::ParserBuilder::format
+#[derive(Debug, Clone)]
+pub struct JsonFormat {
+    timestamp_keys: Vec<String>,
+    message_keys: Vec<String>,
+}
+
+impl Default for JsonFormat {
+    fn default() -> JsonFormat {
+        JsonFormat {
+            timestamp_keys: TIMESTAMP_KEYS.iter().map(|x| x.to_string()).collect(),
+            message_keys: MESSAGE_KEYS.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+impl JsonFormat {
+    /// Creates a JSON format with the default keys.
+    pub fn new() -> JsonFormat {
+        JsonFormat::default()
+    }
+
+    /// Sets the keys the timestamp is looked up in, in order.
+    ///
+    /// Defaults to `timestamp`, `time`, `ts` and `@timestamp`.
+    pub fn timestamp_keys<I, S>(mut self, keys: I) -> JsonFormat
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.timestamp_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the keys the message is looked up in, in order.
+    ///
+    /// Defaults to `message` and `msg`.
+    pub fn message_keys<I, S>(mut self, keys: I) -> JsonFormat
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.message_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Removes the first key present in the object along with its value.
+fn take_first<S: AsRef<str>>(
+    object: &mut Map<String, Value>,
+    keys: &[S],
+) -> Option<(String, Value)> {
+    keys.iter()
+        .find_map(|key| object.remove_entry(key.as_ref()))
+}
+
+pub(crate) fn value_to_string(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        other => other.to_string(),
+    }
+}
+
+pub(crate) fn parse_timestamp(value: &Value, ctx: &Context) -> Option<Timestamp> {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    if let Ok(ts) = DateTime::parse_from_rfc3339(&text) {
+        return Some(if text.ends_with('Z') {
+            Timestamp::Utc(ts.with_timezone(&Utc))
+        } else {
+            Timestamp::Fixed(ts)
+        });
+    }
+    if value.is_number() {
+        return EpochHeuristics::default()
+            .bare_integers(true)
+            .parse(text.as_bytes(), ctx)?
+            .timestamp()
+            .cloned();
+    }
+    // the built-in formats expect a message after the timestamp
+    let line = format!("{} -", text);
+    parse_builtin(line.as_bytes(), ctx)?.timestamp().cloned()
+}
+
+fn parse_json<'a, S: AsRef<str>>(
+    bytes: &'a [u8],
+    ctx: &Context,
+    timestamp_keys: &[S],
+    message_keys: &[S],
+) -> Option<LogEntry<'a>> {
+    let mut object = match serde_json::from_slice(bytes).ok()? {
+        Value::Object(object) => object,
+        _ => return None,
+    };
+    let timestamp = take_first(&mut object, timestamp_keys);
+    let message = take_first(&mut object, message_keys)
+        .map_or_else(String::new, |(_, value)| value_to_string(value));
+    let level = take_first(&mut object, LEVEL_KEYS).map(|(_, value)| value_to_string(value));
+
+    let mut entry = match timestamp
+        .as_ref()
+        .and_then(|(_, x)| parse_timestamp(x, ctx))
+    {
+        Some(ts) => LogEntry::from_timestamp(ts, b""),
+        None => LogEntry::from_message_only(b""),
+    }
+    .with_owned_message(message);
+    if let Some(level) = level {
+        entry = entry.with_level(level.into());
+    }
+    // a timestamp that could not be parsed is kept around as a field
+    if let (Some((key, value)), None) = (timestamp, entry.timestamp()) {
+        entry = entry.with_field(key, value_to_string(value));
+    }
+    for (key, value) in object {
+        entry = entry.with_field(key, value_to_string(value));
+    }
+    Some(entry)
+}
+
+impl LogFormat for JsonFormat {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn quick_check(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"{")
+    }
+
+    fn parse<'a>(&self, bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+        Some(
+            parse_json(bytes, ctx, &self.timestamp_keys, &self.message_keys)?
+                .with_format(FormatKind::Json),
+        )
+    }
+}
+
+/// Parses a line that is a JSON object with the default keys.
+///
+/// See [`JsonFormat`] for how the object is mapped onto the entry.
+///
+/// Example: `{"ts": "2021-06-01T12:00:00Z", "level": "info", "msg": "ready"}`
+pub fn parse_json_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    parse_json(bytes, ctx, TIMESTAMP_KEYS, MESSAGE_KEYS)
+}
+
+#[cfg(test)]
+use crate::parser::test_builder;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[cfg(test)]
+fn describe(entry: LogEntry) -> (LogEntry<'static>, Option<String>, Vec<String>) {
+    (
+        entry.clone().into_owned(),
+        entry.level_name().map(|x| x.to_string()),
+        entry
+            .fields()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_parse_json_log_entry() {
+    let parser = test_builder().build();
+    assert_debug_snapshot!(
+        [
+            &br#"{"ts":"2021-06-01T12:00:00.5Z","level":"info","msg":"ready","port":8080,"tags":["a"]}"#[..],
+            &br#"{"@timestamp":"2015-05-13 17:39:16 +0200","message":"indexed","service":"api"}"#[..],
+            &br#"{"time":1612345678.123,"msg":"epoch"}"#[..],
+            &br#"{"time":"yesterday","msg":"unparsable"}"#[..],
+        ]
+        .iter()
+        .map(|line| describe(parser.parse(line)))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            LogEntry {
+                timestamp: Some(
+                    Utc(
+                        2021-06-01T12:00:00.500Z,
+                    ),
+                ),
+                message: "ready",
+            },
+            Some(
+                "info",
+            ),
+            [
+                "port=8080",
+                "tags=[\"a\"]",
+            ],
+        ),
+        (
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2015-05-13T17:39:16+02:00,
+                    ),
+                ),
+                message: "indexed",
+            },
+            None,
+            [
+                "service=api",
+            ],
+        ),
+        (
+            LogEntry {
+                timestamp: Some(
+                    Utc(
+                        2021-02-03T09:47:58.123Z,
+                    ),
+                ),
+                message: "epoch",
+            },
+            None,
+            [],
+        ),
+        (
+            LogEntry {
+                timestamp: None,
+                message: "unparsable",
+            },
+            None,
+            [
+                "time=yesterday",
+            ],
+        ),
+    ]
+    "###
+    );
+    assert_eq!(
+        parser.parse(br#"{"msg":"x"}"#).format(),
+        Some(FormatKind::Json)
+    );
+    assert!(parser.parse(b"{not json").format().is_none());
+}
+
+#[test]
+fn test_json_format_keys() {
+    let parser = Parser::builder()
+        .format(
+            JsonFormat::new()
+                .timestamp_keys(vec!["t"])
+                .message_keys(vec!["text"]),
+        )
+        .build();
+    assert_debug_snapshot!(
+        describe(parser.parse(br#"{"t":"2021-06-01T12:00:00Z","text":"custom keys","msg":"kept"}"#)),
+        @r###"
+    (
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2021-06-01T12:00:00Z,
+                ),
+            ),
+            message: "custom keys",
+        },
+        None,
+        [
+            "msg=kept",
+        ],
+    )
+    "###
+    );
+}
@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref KAFKA_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\x20
+            (.*?)
+            \x20?\(([A-Za-z_$][A-Za-z0-9_$\-]*(?:\.[A-Za-z_$][A-Za-z0-9_$\-]*)+)\)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Kafka, ZooKeeper or other log4j based server log
+/// that puts the logger in parentheses at the end of the line.
+///
+/// The level sets the level and the logger is removed from the message
+/// and exposed as the `component` field.
+///
+/// Example: `[2021-06-01 12:00:00,123] INFO [KafkaServer id=0] started (kafka.server.KafkaServer)`
+pub fn parse_kafka_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    if !bytes.starts_with(b"[") {
+        return None;
+    }
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = KAFKA_RE.captures(&bytes[message_span.clone()])?;
+
+    // the level and logger only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = caps.get(2).unwrap();
+    let message = message_span.start + message.start()..message_span.start + message.end();
+    Some(
+        entry
+            .with_message(&bytes[message.clone()])
+            .with_spans(ts_span, message)
+            .with_level(Cow::Borrowed(text(1)))
+            .with_field("component", text(3)),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_kafka_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"[2021-06-01 12:00:00,123] INFO [KafkaServer id=0] started (kafka.server.KafkaServer)"),
+            parse(&b"[2021-06-01 12:00:01,456] WARN [Controller id=0, targetBrokerId=1] Connection to node 1 could not be established. (org.apache.kafka.clients.NetworkClient)"[..]),
+            parse(b"[2021-06-01 12:00:02,789] INFO Session establishment complete on server localhost/127.0.0.1:2181 (org.apache.zookeeper.ClientCnxn)"),
+            parse(b"[2021-06-01 12:00:03,000] TRACE [Broker id=0] Cached leader info (state.change.logger)"),
+            parse(b"[2021-06-01 12:00:04,000] INFO no logger at the end"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Kafka,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "[KafkaServer id=0] started",
+            [
+                "component=kafka.server.KafkaServer",
+                "id=0]",
+            ],
+        ),
+        (
+            Some(
+                Kafka,
+            ),
+            Some(
+                "2021-06-01 12:00:01.456",
+            ),
+            Some(
+                Warning,
+            ),
+            "[Controller id=0, targetBrokerId=1] Connection to node 1 could not be established.",
+            [
+                "component=org.apache.kafka.clients.NetworkClient",
+                "id=0",
+                "targetBrokerId=1]",
+            ],
+        ),
+        (
+            Some(
+                Kafka,
+            ),
+            Some(
+                "2021-06-01 12:00:02.789",
+            ),
+            Some(
+                Info,
+            ),
+            "Session establishment complete on server localhost/127.0.0.1:2181",
+            [
+                "component=org.apache.zookeeper.ClientCnxn",
+            ],
+        ),
+        (
+            Some(
+                Kafka,
+            ),
+            Some(
+                "2021-06-01 12:00:03",
+            ),
+            Some(
+                Trace,
+            ),
+            "[Broker id=0] Cached leader info",
+            [
+                "component=state.change.logger",
+                "id=0]",
+            ],
+        ),
+        (
+            Some(
+                Rfc3339,
+            ),
+            Some(
+                "2021-06-01 12:00:04",
+            ),
+            Some(
+                Info,
+            ),
+            "INFO no logger at the end",
+            [],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref KLOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([IWEF])
+            (
+                (0[1-9]|1[0-2])(0[1-9]|[12][0-9]|3[01])
+                \x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{6})
+            )
+            \x20+([0-9]+)
+            \x20([^\x20:\]]+):([0-9]+)\]
+            (?:\x20(.*))?
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line written by klog or glog, as used by Kubernetes and many
+/// Go programs.
+///
+/// The severity letter sets the level.  The thread id and the source
+/// location are exposed as the `tid`, `file` and `line` fields, the latter
+/// two are also returned by [`LogEntry::source_location`].  The year is
+/// inferred like for the short format.
+///
+/// Example: `I0102 15:04:05.123456   12345 controller.go:123] syncing pod`
+pub fn parse_klog_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = KLOG_RE.captures(bytes)?;
+    // the severity and the numbers only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let (month, day) = (num(3), num(4));
+    let (h, m, s) = (num(5), num(6), num(7));
+    let year = ctx.infer_year(month, day, h, m, s);
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::microseconds(num(8).into());
+
+    let message = caps.get(12).map_or(bytes.len()..bytes.len(), |x| x.range());
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(2).unwrap().range(), message)
+            .with_level(Cow::Borrowed(text(1)))
+            .with_field("tid", text(9))
+            .with_field(
+                "file",
+                String::from_utf8_lossy(&bytes[caps.get(10).unwrap().range()]),
+            )
+            .with_field("line", text(11)),
+    )
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use crate::types::NaiveTimezone;
+#[cfg(test)]
+use chrono::prelude::*;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_klog_log_entry() {
+    let ctx = Context::new(
+        NaiveTimezone::Utc,
+        &FixedClock::new(Utc.with_ymd_and_hms(2017, 6, 1, 0, 0, 0).unwrap()),
+    );
+    let entry = parse_klog_log_entry(
+        b"E0102 15:04:05.123456   12345 pkg/controller/node.go:123] failed to sync node",
+        &ctx,
+    )
+    .unwrap();
+    assert_debug_snapshot!(
+        (
+            &entry,
+            entry.level(),
+            entry.source_location(),
+            entry.fields().collect::<Vec<_>>(),
+        ),
+        @r###"
+    (
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2017-01-02T15:04:05.123456Z,
+                ),
+            ),
+            message: "failed to sync node",
+        },
+        Some(
+            Error,
+        ),
+        Some(
+            SourceLocation {
+                file: "pkg/controller/node.go",
+                line: 123,
+            },
+        ),
+        [
+            (
+                "tid",
+                "12345",
+            ),
+            (
+                "file",
+                "pkg/controller/node.go",
+            ),
+            (
+                "line",
+                "123",
+            ),
+        ],
+    )
+    "###
+    );
+    assert_eq!(
+        LogEntry::parse(b"I0102 15:04:05.000000 1 main.go:12] started").format(),
+        Some(FormatKind::Klog)
+    );
+    assert!(parse_klog_log_entry(b"I0102 15:04:05 12345 main.go:1] no micros", &ctx).is_none());
+}
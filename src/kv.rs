@@ -0,0 +1,84 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref PAIR_RE: Regex = Regex::new(
+        r#"(?x)
+        (?:^|[\t\x20,;])
+            ([A-Za-z_][A-Za-z0-9_.-]*)
+            =
+            (?:
+                "((?:[^"\\]|\\.)*)"
+                |
+                ([^\t\x20",;]*)
+            )
+    "#
+    )
+    .unwrap();
+}
+
+/// Iterates over the `key=value` and `key="quoted value"` pairs in a message.
+///
+/// Pairs have to be separated by whitespace, a comma or a semicolon.  The
+/// quotes are removed from quoted values but escapes are left as they are.
+pub(crate) fn pairs(message: &str) -> impl Iterator<Item = (&str, &str)> {
+    PAIR_RE.captures_iter(message).map(|caps| {
+        let value = caps.get(2).or_else(|| caps.get(3)).unwrap();
+        (caps.get(1).unwrap().as_str(), value.as_str())
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_pairs() {
+    assert_debug_snapshot!(
+        pairs(r#"request done method=GET path="/api/0/ a" status=200, user.id=42;empty= x==y trailing="#)
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            "method",
+            "GET",
+        ),
+        (
+            "path",
+            "/api/0/ a",
+        ),
+        (
+            "status",
+            "200",
+        ),
+        (
+            "user.id",
+            "42",
+        ),
+        (
+            "empty",
+            "",
+        ),
+        (
+            "x",
+            "=y",
+        ),
+        (
+            "trailing",
+            "",
+        ),
+    ]
+    "###
+    );
+    assert_debug_snapshot!(pairs(r#"msg="say \"hi\"" url=http://x/?a=b"#).collect::<Vec<_>>(), @r###"
+    [
+        (
+            "msg",
+            "say \\\"hi\\\"",
+        ),
+        (
+            "url",
+            "http://x/?a=b",
+        ),
+    ]
+    "###);
+}
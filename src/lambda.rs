@@ -0,0 +1,166 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref PLATFORM_RE: Regex = Regex::new(
+        r#"^(?:START|END|REPORT|INIT_START|INIT_REPORT)\x20RequestId:\x20([0-9a-f\-]+)"#
+    )
+    .unwrap();
+    static ref RUNTIME_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})
+            \t(?:(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\t)?
+        "#
+    )
+    .unwrap();
+    static ref PYTHON_RE: Regex =
+        Regex::new(r#"^\[(DEBUG|INFO|WARNING|ERROR|CRITICAL)\]\t"#).unwrap();
+}
+
+/// Parses a line of an AWS Lambda function as stored by CloudWatch Logs.
+///
+/// The `START`, `END` and `REPORT` lines of the platform, the lines the
+/// Node.js runtime writes for `console.log` and the lines of the Python
+/// runtime's logger are understood.  The request id is exposed as the
+/// `request_id` field and the level of the runtime lines sets the level.
+/// Platform lines need the timestamp prefix CloudWatch adds on export.
+///
+/// Example: `2021-06-01T12:00:00.123Z\tb7e5f1c4-...\tINFO\tmessage`
+pub fn parse_lambda_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let (offset, level) = match PYTHON_RE.captures(bytes) {
+        Some(caps) => (caps.get(0).unwrap().end(), caps.get(1)),
+        None => (0, None),
+    };
+    let entry = parser::parse_rfc3339_log_entry(&bytes[offset..], ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let ts_span = offset + ts_span.start..offset + ts_span.end;
+    let message_span = entry.message_span()?;
+    let message_span = offset + message_span.start..bytes.len();
+    let rest = &bytes[message_span.clone()];
+
+    // the request id and levels only match ASCII
+    let text = |x: regex::bytes::Match<'a>| str::from_utf8(x.as_bytes()).unwrap();
+    let (request_id, level, message) = if let Some(caps) = PLATFORM_RE.captures(rest) {
+        (caps.get(1).unwrap(), level, message_span)
+    } else if let Some(caps) = RUNTIME_RE.captures(rest) {
+        let start = message_span.start + caps.get(0).unwrap().end();
+        (
+            caps.get(1).unwrap(),
+            level.or_else(|| caps.get(2)),
+            start..bytes.len(),
+        )
+    } else {
+        return None;
+    };
+    let entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_field("request_id", text(request_id));
+    Some(match level {
+        Some(level) => entry.with_level(Cow::Borrowed(text(level))),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_lambda_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01T12:00:00.100Z START RequestId: 6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d Version: $LATEST"[..]),
+            parse(b"2021-06-01T12:00:00.123Z\t6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d\tERROR\tInvoke Error"),
+            parse(b"[WARNING]\t2021-06-01T12:00:00.200Z\t6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d\tslow query"),
+            parse(b"2021-06-01T12:00:00.300Z REPORT RequestId: 6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d\tDuration: 102.25 ms\tBilled Duration: 103 ms"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Lambda,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.100Z,
+                ),
+            ),
+            None,
+            "START RequestId: 6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d Version: $LATEST",
+            [
+                "request_id=6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d",
+            ],
+        ),
+        (
+            Some(
+                Lambda,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.123Z,
+                ),
+            ),
+            Some(
+                Error,
+            ),
+            "Invoke Error",
+            [
+                "request_id=6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d",
+            ],
+        ),
+        (
+            Some(
+                Lambda,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.200Z,
+                ),
+            ),
+            Some(
+                Warning,
+            ),
+            "slow query",
+            [
+                "request_id=6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d",
+            ],
+        ),
+        (
+            Some(
+                Lambda,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.300Z,
+                ),
+            ),
+            None,
+            "REPORT RequestId: 6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d\tDuration: 102.25 ms\tBilled Duration: 103 ms",
+            [
+                "request_id=6f1c3c1e-7a4e-4b5b-9d2b-0f3c2a1b4c5d",
+            ],
+        ),
+    ]
+    "###
+    );
+}
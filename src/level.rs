@@ -0,0 +1,186 @@
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+lazy_static! {
+    static ref MARKER_RE: Regex = Regex::new(
+        r#"(?x)
+        ^(?:
+            # [INFO], [ warn ]
+            \[\x20*([A-Za-z]+)\x20*\]:?
+            |
+            # <warn>
+            <([A-Za-z]+)>:?
+            |
+            # ERROR -, DEBUG:, INFO
+            ([A-Z]{3,})(?::|\x20-|\x20\|)?[\t\x20]
+            |
+            # W/Tag(123): as written by Android
+            ([VDIWEF])/[^\t\x20:(/]+[\t\x20]*(?:\([\t\x20]*[0-9]+\))?:
+        )
+        [\t\x20]*
+    "#
+    )
+    .unwrap();
+}
+
+/// The severity of a log entry.
+///
+/// Levels are ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Level {
+    /// Very detailed tracing output.
+    Trace,
+    /// Debugging output.
+    Debug,
+    /// Informational messages.
+    Info,
+    /// Something unexpected that is not an error.
+    Warning,
+    /// An error.
+    Error,
+    /// An error that stops the program or a critical condition.
+    Fatal,
+}
+
+impl Level {
+    /// Maps a level name as used by common logging libraries to a level.
+    ///
+    /// This understands names like `WARN`, `warning`, `crit` or `SEVERE` as
+    /// well as single letter abbreviations (`W`).  The comparison ignores
+    /// case.
+    pub fn from_name(name: &str) -> Option<Level> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "trace" | "finest" | "finer" | "verbose" | "vrb" | "v" | "t" => Level::Trace,
+            "debug" | "dbg" | "fine" | "config" | "d" => Level::Debug,
+            "info" | "inf" | "information" | "informational" | "notice" | "note" | "system"
+            | "i" | "n" => Level::Info,
+            "warn" | "warning" | "wrn" | "w" => Level::Warning,
+            "err" | "error" | "eror" | "severe" | "e" => Level::Error,
+            "fatal" | "ftl" | "crit" | "critical" | "alert" | "emerg" | "emergency" | "panic"
+            | "f" | "c" | "a" => Level::Fatal,
+            _ => return None,
+        })
+    }
+
+    /// Returns the lowercase name of the level.
+    #[cfg(feature = "serde")]
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+
+    /// Returns the name of the level as used for Sentry breadcrumbs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Trace | Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+}
+
+/// Finds a severity marker at the start of a message.
+///
+/// Returns the level, the marker as written and the number of bytes to
+/// strip from the message.  For Android lines only the level prefix is
+/// stripped so that the tag stays in the message.
+pub(crate) fn find_level_marker(message: &[u8]) -> Option<(Level, &str, usize)> {
+    let caps = MARKER_RE.captures(message)?;
+    let (name, strip) = match caps.get(4) {
+        Some(letter) => (letter, 2),
+        None => (
+            caps.get(1)
+                .or_else(|| caps.get(2))
+                .or_else(|| caps.get(3))?,
+            caps.get(0).unwrap().end(),
+        ),
+    };
+    // markers are ASCII only, so this cannot fail
+    let name = std::str::from_utf8(name.as_bytes()).ok()?;
+    Some((Level::from_name(name)?, name, strip))
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_find_level_marker() {
+    assert_debug_snapshot!(
+        [
+            &b"[INFO] server started"[..],
+            &b"[ warn ] disk almost full"[..],
+            &b"ERROR - connection refused"[..],
+            &b"DEBUG: cache miss"[..],
+            &b"<warn> low memory"[..],
+            &b"W/ActivityManager( 1234): Slow operation"[..],
+            &b"I/O error on /dev/sda"[..],
+            &b"GET /index.html"[..],
+            &b"Error while loading"[..],
+        ]
+        .iter()
+        .map(|line| find_level_marker(line))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            (
+                Info,
+                "INFO",
+                7,
+            ),
+        ),
+        Some(
+            (
+                Warning,
+                "warn",
+                9,
+            ),
+        ),
+        Some(
+            (
+                Error,
+                "ERROR",
+                8,
+            ),
+        ),
+        Some(
+            (
+                Debug,
+                "DEBUG",
+                7,
+            ),
+        ),
+        Some(
+            (
+                Warning,
+                "warn",
+                7,
+            ),
+        ),
+        Some(
+            (
+                Warning,
+                "W",
+                2,
+            ),
+        ),
+        None,
+        None,
+        None,
+    ]
+    "###
+    );
+}
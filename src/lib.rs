@@ -5,7 +5,89 @@
 //! This crate is used by [Sentry](https://sentry.io/) to parse logfiles into
 //! breadcrumbs.
 
+mod access;
+mod actions;
+mod age;
+mod apple;
+mod audit;
+mod build;
+mod capnslog;
+mod category;
+mod ceph;
+mod chrome;
+mod cisco;
+mod clock;
+mod correlation;
+mod crash;
+mod cri;
+mod cryengine;
+mod cups;
+mod custom;
+mod dotnet;
+mod envoy;
+mod epoch;
+mod error;
+mod exception;
+mod filter;
+mod format;
+pub mod formats;
+#[cfg(feature = "json")]
+mod gelf;
+mod grok;
+mod haproxy;
+mod hclog;
+mod header;
+mod heroku;
+mod index;
+mod java;
+#[cfg(feature = "json")]
+mod json;
+mod kafka;
+mod klog;
+mod kv;
+mod lambda;
+mod level;
+mod location;
+mod logcat;
+#[cfg(feature = "logset")]
+mod logset;
+mod minecraft;
+mod mongodb;
+mod mysql;
+mod npm;
 mod parser;
+#[cfg(test)]
+mod proptests;
+mod redis;
+mod ruby;
+mod scan;
+#[cfg(feature = "serde")]
+mod serialize;
+mod source;
+mod squid;
+mod stream;
+mod syslog;
 mod types;
+mod unity;
+mod w3c;
+mod windows;
+mod xorg;
 
-pub use crate::types::LogEntry;
+pub use crate::age::Age;
+pub use crate::clock::{CachedClock, Clock, FixedClock, SystemClock};
+pub use crate::custom::{CustomFormat, FieldMap, FormatError, StaticFormat};
+pub use crate::epoch::{EpochHeuristics, EpochUnit};
+pub use crate::error::Error;
+pub use crate::exception::{Frame, StackTrace, TraceKind};
+pub use crate::filter::{CollapseBursts, EntriesExt, MaxAge, Sample, UntimedPolicy};
+pub use crate::format::{FormatKind, LogFormat, Registry};
+pub use crate::index::TimeIndex;
+#[cfg(feature = "json")]
+pub use crate::json::JsonFormat;
+pub use crate::level::Level;
+pub use crate::location::SourceLocation;
+#[cfg(feature = "logset")]
+pub use crate::logset::{FileEntries, LogSet, MergedEntries};
+pub use crate::parser::{Context, DateOrder, ImplausibleAction, Parser, ParserBuilder};
+pub use crate::stream::{read_entries, Entries};
+pub use crate::types::{EntryTime, LogEntry, NaiveTimezone, Precision, Timestamp};
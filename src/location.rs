@@ -0,0 +1,115 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref LOCATION_RE: Regex = Regex::new(
+        r#"(?x)
+        (?:^|[\t\x20("'\[<@])
+        (
+            [A-Za-z0-9_.~/\\:-]*?
+            [A-Za-z0-9_-]+
+            \.(?:c|cc|cpp|cxx|h|hh|hpp|m|mm|rs|go|py|java|kt|scala|js|ts|rb|cs|swift|php|lua|erl|ex|exs)
+        )
+        (?:
+            \(([0-9]+)\)
+            |
+            :([0-9]+)
+            |
+            ",\x20line\x20([0-9]+)
+        )
+        (?:$|[^0-9A-Za-z_])
+    "#
+    )
+    .unwrap();
+}
+
+/// A source file and line referenced by a log message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SourceLocation<'a> {
+    file: &'a str,
+    line: u32,
+}
+
+impl<'a> SourceLocation<'a> {
+    pub(crate) fn new(file: &'a str, line: u32) -> SourceLocation<'a> {
+        SourceLocation { file, line }
+    }
+
+    /// Returns the path of the source file as it appeared in the message.
+    pub fn file(&self) -> &'a str {
+        self.file
+    }
+
+    /// Returns the line number within the source file.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+}
+
+/// Finds the first source location in a message.
+///
+/// This understands `file.cc(441)` (MSVC), `controller.go:123` and
+/// `File "module.py", line 45` (Python tracebacks).
+pub(crate) fn find_source_location(message: &str) -> Option<SourceLocation<'_>> {
+    LOCATION_RE.captures_iter(message).find_map(|caps| {
+        let line = caps
+            .get(2)
+            .or_else(|| caps.get(3))
+            .or_else(|| caps.get(4))?;
+        Some(SourceLocation::new(
+            caps.get(1)?.as_str(),
+            line.as_str().parse().ok()?,
+        ))
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_find_source_location() {
+    assert_debug_snapshot!(
+        [
+            r"d:\build\engine\source\renderer.cc(441): assertion failed",
+            "I0601 12:00:00.123456 1 controller.go:123] syncing pod",
+            r#"  File "app/module.py", line 45, in handler"#,
+            "at com.example.Main.run(Main.java:12)",
+            "connected to db.example.com:5432",
+            "no location here",
+        ]
+        .iter()
+        .map(|x| find_source_location(x))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            SourceLocation {
+                file: "d:\\build\\engine\\source\\renderer.cc",
+                line: 441,
+            },
+        ),
+        Some(
+            SourceLocation {
+                file: "controller.go",
+                line: 123,
+            },
+        ),
+        Some(
+            SourceLocation {
+                file: "app/module.py",
+                line: 45,
+            },
+        ),
+        Some(
+            SourceLocation {
+                file: "Main.java",
+                line: 12,
+            },
+        ),
+        None,
+        None,
+    ]
+    "###
+    );
+}
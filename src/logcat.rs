@@ -0,0 +1,232 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::{Captures, Regex};
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref THREADTIME_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?P<ts>
+                (?:(?P<year>[0-9]{4})-)?(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+                \x20
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})\.(?P<ms>[0-9]{3})
+            )
+            \x20+(?P<pid>[0-9]+)
+            \x20+(?P<tid>[0-9]+)
+            \x20(?P<pri>[VDIWEFA])
+            \x20(?P<tag>[^:]*?)\x20*:(?:\x20|$)
+            (?P<msg>.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref TIME_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (?P<ts>
+                (?:(?P<year>[0-9]{4})-)?(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+                \x20
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})\.(?P<ms>[0-9]{3})
+            )
+            \x20(?P<pri>[VDIWEFA])/(?P<tag>[^(]*?)\(\x20*(?P<pid>[0-9]+)\):(?:\x20|$)
+            (?P<msg>.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref LONG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[\x20
+            (?P<ts>
+                (?:(?P<year>[0-9]{4})-)?(?P<month>[0-9]{2})-(?P<day>[0-9]{2})
+                \x20
+                (?P<h>[0-9]{2}):(?P<m>[0-9]{2}):(?P<s>[0-9]{2})\.(?P<ms>[0-9]{3})
+            )
+            \x20+(?P<pid>[0-9]+):\x20*(?P<tid>0x[0-9a-f]+|[0-9]+)
+            \x20(?P<pri>[VDIWEFA])/(?P<tag>.*?)
+            \x20+\]
+            (?P<msg>)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+fn text<'a>(caps: &Captures<'a>, name: &str) -> Option<&'a str> {
+    caps.name(name)
+        .map(|x| str::from_utf8(x.as_bytes()).unwrap())
+}
+
+fn num<T: str::FromStr>(caps: &Captures, name: &str) -> Option<T> {
+    text(caps, name)?.parse().ok()
+}
+
+fn log_entry_from_caps<'a>(
+    bytes: &'a [u8],
+    caps: Captures<'a>,
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    let (month, day) = (num(&caps, "month")?, num(&caps, "day")?);
+    let (h, m, s) = (num(&caps, "h")?, num(&caps, "m")?, num(&caps, "s")?);
+    let ms: i64 = num(&caps, "ms")?;
+    let year = match num(&caps, "year") {
+        Some(year) => year,
+        None => ctx.infer_year(month, day, h, m, s),
+    };
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::milliseconds(ms);
+
+    let message = caps.name("msg").unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.name("ts").unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(&caps, "pri")?))
+        .with_field("procid", text(&caps, "pid")?);
+    if let Some(tid) = text(&caps, "tid") {
+        entry = entry.with_field("tid", tid);
+    }
+    Some(entry.with_field("tag", text(&caps, "tag")?))
+}
+
+/// Parses a line of Android `logcat` output.
+///
+/// The `threadtime` (default), `time` and `long` output formats are
+/// understood, with or without the year.  The priority sets the level and
+/// the process id, thread id and tag are exposed as the `procid`, `tid`
+/// and `tag` fields.  In the `long` format the message follows on the next
+/// lines, so the header line has an empty message.
+///
+/// Example: `03-17 16:13:38.811  1702  8671 D SomeTag: message`
+pub fn parse_logcat_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = THREADTIME_RE
+        .captures(bytes)
+        .or_else(|| TIME_RE.captures(bytes))
+        .or_else(|| LONG_RE.captures(bytes))?;
+    log_entry_from_caps(bytes, caps, ctx)
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use crate::types::NaiveTimezone;
+#[cfg(test)]
+use chrono::prelude::*;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_logcat_log_entry() {
+    let ctx = Context::new(
+        NaiveTimezone::Utc,
+        &FixedClock::new(Utc.with_ymd_and_hms(2017, 6, 1, 0, 0, 0).unwrap()),
+    );
+    let parse = |line| {
+        let entry = parse_logcat_log_entry(line, &ctx)?;
+        Some((
+            entry.timestamp().cloned(),
+            entry.level_name().map(|x| x.to_string()),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"03-17 16:13:38.811  1702  8671 D SomeTag: message"),
+            parse(b"2021-03-17 16:13:38.811  1702  8671 W ActivityManager  : Slow operation"),
+            parse(b"03-17 16:13:38.811 I/ActivityManager( 1702): Start proc"),
+            parse(b"[ 03-17 16:13:38.811  1702: 8671 E/AndroidRuntime ]"),
+            parse(b"--------- beginning of main"),
+        ],
+        @r###"
+    [
+        Some(
+            (
+                Some(
+                    Utc(
+                        2017-03-17T16:13:38.811Z,
+                    ),
+                ),
+                Some(
+                    "D",
+                ),
+                "message",
+                [
+                    "procid=1702",
+                    "tid=8671",
+                    "tag=SomeTag",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2021-03-17T16:13:38.811Z,
+                    ),
+                ),
+                Some(
+                    "W",
+                ),
+                "Slow operation",
+                [
+                    "procid=1702",
+                    "tid=8671",
+                    "tag=ActivityManager",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2017-03-17T16:13:38.811Z,
+                    ),
+                ),
+                Some(
+                    "I",
+                ),
+                "Start proc",
+                [
+                    "procid=1702",
+                    "tag=ActivityManager",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2017-03-17T16:13:38.811Z,
+                    ),
+                ),
+                Some(
+                    "E",
+                ),
+                "",
+                [
+                    "procid=1702",
+                    "tid=8671",
+                    "tag=AndroidRuntime",
+                ],
+            ),
+        ),
+        None,
+    ]
+    "###
+    );
+    assert_eq!(
+        LogEntry::parse(b"03-17 16:13:38.811  1702  8671 D SomeTag: message").format(),
+        Some(FormatKind::Logcat)
+    );
+}
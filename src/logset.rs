@@ -0,0 +1,269 @@
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+use flate2::bufread::MultiGzDecoder;
+
+use crate::parser::Parser;
+use crate::stream::Entries;
+use crate::types::LogEntry;
+
+/// The position of a file within a rotation series.
+///
+/// The derived ordering sorts oldest first: numbered rotations with the
+/// highest number come first, followed by date suffixed rotations and
+/// finally the live file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Generation {
+    Numbered(Reverse<u32>),
+    Dated(String),
+    Current,
+}
+
+fn rotation_key(path: &Path) -> (PathBuf, Generation) {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+
+    let (base, generation) = if let Some((base, num)) = name
+        .rsplit_once('.')
+        .and_then(|(base, suffix)| Some((base, suffix.parse::<u32>().ok()?)))
+    {
+        (base, Generation::Numbered(Reverse(num)))
+    } else if let Some((base, date)) = name
+        .rsplit_once('-')
+        .filter(|(_, suffix)| suffix.len() == 8 && suffix.bytes().all(|b| b.is_ascii_digit()))
+    {
+        (base, Generation::Dated(date.to_string()))
+    } else {
+        (name, Generation::Current)
+    };
+
+    (path.with_file_name(base), generation)
+}
+
+/// Opens a log file, transparently decompressing gzip files.
+fn open_log_file(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// An iterator over the entries of a single log file.
+pub struct FileEntries {
+    entries: Entries<Box<dyn BufRead>>,
+}
+
+impl FileEntries {
+    /// Opens the given file for reading entries.
+    ///
+    /// Gzip compressed files are detected by their magic bytes and
+    /// decompressed on the fly.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<FileEntries> {
+        FileEntries::open_with_parser(path, &Parser::new())
+    }
+
+    /// Like `open` but parses the lines with the given parser.
+    pub fn open_with_parser<P: AsRef<Path>>(path: P, parser: &Parser) -> io::Result<FileEntries> {
+        Ok(FileEntries {
+            entries: parser.read_entries(open_log_file(path.as_ref())?),
+        })
+    }
+}
+
+impl Iterator for FileEntries {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+/// A set of log files discovered from a glob pattern.
+///
+/// Files are kept in rotation order: rotated files of the same log
+/// (`app.log.2.gz`, `app.log.1`, `app.log`) are ordered oldest first and
+/// different logs are ordered by name.
+#[derive(Debug, Clone)]
+pub struct LogSet {
+    files: Vec<PathBuf>,
+    parser: Parser,
+}
+
+impl LogSet {
+    /// Discovers all files matching the given glob pattern.
+    ///
+    /// The pattern supports the usual glob syntax including `**` for
+    /// recursive matching, for instance `logs/**/*.log*`.
+    pub fn from_glob(pattern: &str) -> io::Result<LogSet> {
+        let paths =
+            glob::glob(pattern).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let mut files = Vec::new();
+        for path in paths {
+            let path = path.map_err(io::Error::from)?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(LogSet::from_paths(files))
+    }
+
+    /// Creates a log set from an explicit list of files.
+    pub fn from_paths<I: IntoIterator<Item = PathBuf>>(paths: I) -> LogSet {
+        let mut files: Vec<_> = paths.into_iter().collect();
+        files.sort_by_cached_key(|path| rotation_key(path));
+        LogSet {
+            files,
+            parser: Parser::new(),
+        }
+    }
+
+    /// Sets the parser used for the lines of all files.
+    pub fn with_parser(mut self, parser: Parser) -> LogSet {
+        self.parser = parser;
+        self
+    }
+
+    /// Returns the files in rotation order.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Iterates over the files and their entries, one file at a time.
+    pub fn per_file(&self) -> impl Iterator<Item = (&Path, io::Result<FileEntries>)> {
+        self.files.iter().map(move |path| {
+            (
+                path.as_path(),
+                FileEntries::open_with_parser(path, &self.parser),
+            )
+        })
+    }
+
+    /// Returns a single stream over the entries of all files in
+    /// chronological order.
+    ///
+    /// Entries without a timestamp stay attached to the entry preceding
+    /// them in the same file.
+    pub fn merged(&self) -> io::Result<MergedEntries> {
+        Ok(MergedEntries {
+            sources: self
+                .files
+                .iter()
+                .map(|path| Ok(FileEntries::open_with_parser(path, &self.parser)?.peekable()))
+                .collect::<io::Result<_>>()?,
+        })
+    }
+}
+
+/// A chronologically merged stream over multiple log files.
+pub struct MergedEntries {
+    sources: Vec<Peekable<FileEntries>>,
+}
+
+impl Iterator for MergedEntries {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut best = None;
+        for (idx, source) in self.sources.iter_mut().enumerate() {
+            let ts = match source.peek() {
+                Some(Ok(entry)) => entry.utc_timestamp(),
+                Some(Err(_)) => return source.next(),
+                None => continue,
+            };
+            match ts {
+                None => return source.next(),
+                Some(ts) => match best {
+                    Some((_, best_ts)) if best_ts <= ts => {}
+                    _ => best = Some((idx, ts)),
+                },
+            }
+        }
+        best.and_then(|(idx, _)| self.sources[idx].next())
+    }
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+#[cfg(test)]
+use std::io::Write;
+
+#[cfg(test)]
+fn write_test_logs(files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("anylog-logset-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, contents) in files {
+        let mut file = File::create(dir.join(name)).unwrap();
+        if name.ends_with(".gz") {
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            encoder.write_all(contents.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+        } else {
+            file.write_all(contents.as_bytes()).unwrap();
+        }
+    }
+    dir
+}
+
+#[test]
+fn test_rotation_order() {
+    let set = LogSet::from_paths(
+        [
+            "b.log",
+            "a.log",
+            "a.log.1",
+            "a.log.10.gz",
+            "a.log.2.gz",
+            "b.log-20210601",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+    assert_debug_snapshot!(set.files(), @r###"
+    [
+        "a.log.10.gz",
+        "a.log.2.gz",
+        "a.log.1",
+        "a.log",
+        "b.log-20210601",
+        "b.log",
+    ]
+    "###);
+}
+
+#[test]
+fn test_merged_entries() {
+    let dir = write_test_logs(&[
+        (
+            "app.log",
+            "2015-05-13 17:39:16 +0200: second\r\n2015-05-13 17:39:18 +0200: fourth\n",
+        ),
+        (
+            "app.log.1.gz",
+            "2015-05-13 17:39:15 +0200: first\ncontinued\n2015-05-13 17:39:17 +0200: third",
+        ),
+    ]);
+    let set = LogSet::from_glob(&format!("{}/**/*.log*", dir.display())).unwrap();
+    let messages: Vec<_> = set
+        .merged()
+        .unwrap()
+        .map(|entry| entry.unwrap().message().to_string())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_debug_snapshot!(messages, @r###"
+    [
+        "first",
+        "continued",
+        "second",
+        "third",
+        "fourth",
+    ]
+    "###);
+}
@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref MINECRAFT_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[(
+                (?:
+                    ([0-9]{2})(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)([0-9]{4})
+                    \x20
+                )?
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})(?:\.([0-9]{3}))?
+            )\]
+            \x20\[([^\]]+)/(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\]
+            (?:\x20\[([^\]]+)\])?
+            :(?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Minecraft server log or of another game server
+/// using the same log4j pattern.
+///
+/// Lines of `latest.log` only carry the time of day, the date is inferred.
+/// The `debug.log` of Forge also has the date and the logger name.  The
+/// level sets the level of the entry and the thread and logger are exposed
+/// as the `thread` and `logger` fields.
+///
+/// Example: `[12:00:00] [Server thread/INFO]: Done (3.2s)!`
+pub fn parse_minecraft_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = MINECRAFT_RE.captures(bytes)?;
+    // all groups except the message and names only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let (h, m, s) = (num(5), num(6), num(7));
+    let (year, month, day) = match caps.get(4) {
+        Some(_) => (num(4) as i32, get_month(&caps[3])?, num(2)),
+        None => {
+            let date = ctx.infer_date(h, m, s);
+            (date.year(), date.month(), date.day())
+        }
+    };
+    let ms = caps.get(8).map_or(0, |_| num(8));
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::milliseconds(ms.into());
+
+    let message = caps.get(12).unwrap().range();
+    let entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(1).unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(10)))
+        .with_field(
+            "thread",
+            String::from_utf8_lossy(&bytes[caps.get(9).unwrap().range()]),
+        );
+    Some(match caps.get(11) {
+        Some(logger) => entry.with_field("logger", String::from_utf8_lossy(logger.as_bytes())),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use crate::clock::FixedClock;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_minecraft_log_entry() {
+    let parser = Parser::builder()
+        .clock(FixedClock::new(
+            Utc.with_ymd_and_hms(2021, 6, 1, 18, 0, 0).unwrap(),
+        ))
+        .build();
+    let parse = |line| {
+        let entry = parser.parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"[12:00:00] [Server thread/INFO]: Done (3.2s)! For help, type \"help\""[..]),
+            parse(b"[12:00:01] [Server thread/WARN]: Can't keep up! Is the server overloaded?"),
+            parse(b"[01Jun2021 12:00:02.123] [Server thread/DEBUG] [net.minecraftforge.fml.loading.FMLLoader/CORE]: Loading mods"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Minecraft,
+            ),
+            Some(
+                "2021-06-01 12:00:00",
+            ),
+            Some(
+                Info,
+            ),
+            "Done (3.2s)! For help, type \"help\"",
+            [
+                "thread=Server thread",
+            ],
+        ),
+        (
+            Some(
+                Minecraft,
+            ),
+            Some(
+                "2021-06-01 12:00:01",
+            ),
+            Some(
+                Warning,
+            ),
+            "Can't keep up! Is the server overloaded?",
+            [
+                "thread=Server thread",
+            ],
+        ),
+        (
+            Some(
+                Minecraft,
+            ),
+            Some(
+                "2021-06-01 12:00:02.123",
+            ),
+            Some(
+                Debug,
+            ),
+            "Loading mods",
+            [
+                "thread=Server thread",
+                "logger=net.minecraftforge.fml.loading.FMLLoader/CORE",
+            ],
+        ),
+    ]
+    "###
+    );
+}
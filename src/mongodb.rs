@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+#[cfg(feature = "json")]
+use serde_json::Value;
+
+#[cfg(feature = "json")]
+use crate::json;
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref MONGODB_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([FEWID])[1-5]?
+            \x20+([A-Z_]+|-)
+            \x20+\[([^\]]+)\]
+            (?:\x20|$)
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a structured log line as written by MongoDB 4.4 and later.
+#[cfg(feature = "json")]
+fn parse_json_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let mut object = match serde_json::from_slice(bytes).ok()? {
+        Value::Object(object) => object,
+        _ => return None,
+    };
+    let severity = match object.get("s") {
+        Some(Value::String(severity)) => severity.get(..1)?.to_string(),
+        _ => return None,
+    };
+    let ts = json::parse_timestamp(object.get("t")?.as_object()?.get("$date")?, ctx)?;
+    for key in &["t", "s"] {
+        object.remove(*key);
+    }
+    let message = object
+        .remove("msg")
+        .map_or_else(String::new, json::value_to_string);
+
+    let mut entry = LogEntry::from_timestamp(ts, b"")
+        .with_owned_message(message)
+        .with_level(Cow::Owned(severity));
+    for (key, value) in object {
+        let key = match key.as_str() {
+            "c" => "component".to_string(),
+            "ctx" => "context".to_string(),
+            _ => key,
+        };
+        entry = entry.with_field(key, json::value_to_string(value));
+    }
+    Some(entry)
+}
+
+/// Parses a line of a MongoDB server log.
+///
+/// The plain text format of MongoDB before 4.4 is understood and, with the
+/// `json` feature, the structured format of later versions.  The severity
+/// letter sets the level and the component and context are exposed as the
+/// `component` and `context` fields.  The other keys of the structured
+/// format (such as `id` and `attr`) become fields as they are.
+///
+/// Example: `2021-06-01T12:00:00.123+0200 I NETWORK  [conn1] end connection`
+pub fn parse_mongodb_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    #[cfg(feature = "json")]
+    {
+        if bytes.starts_with(b"{") {
+            return parse_json_log_entry(bytes, ctx);
+        }
+    }
+
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = MONGODB_RE.captures(&bytes[message_span.clone()])?;
+
+    // the severity and component only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let mut entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_level(Cow::Borrowed(text(1)));
+    if &caps[2] != b"-" {
+        entry = entry.with_field("component", text(2));
+    }
+    Some(entry.with_field(
+        "context",
+        String::from_utf8_lossy(caps.get(3).unwrap().as_bytes()),
+    ))
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_mongodb_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01T12:00:00.123+0200 I NETWORK  [conn1] end connection 127.0.0.1:50000"[..]),
+            parse(b"2021-06-01T12:00:01.000+0200 D1 COMMAND  [conn2] run command admin.$cmd"),
+            parse(b"2021-06-01T12:00:02.000+0200 E -        [main] fatal assertion"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Mongodb,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:00.123+02:00,
+                ),
+            ),
+            Some(
+                Info,
+            ),
+            "end connection 127.0.0.1:50000",
+            [
+                "component=NETWORK",
+                "context=conn1",
+            ],
+        ),
+        (
+            Some(
+                Mongodb,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:01+02:00,
+                ),
+            ),
+            Some(
+                Debug,
+            ),
+            "run command admin.$cmd",
+            [
+                "component=COMMAND",
+                "context=conn2",
+            ],
+        ),
+        (
+            Some(
+                Mongodb,
+            ),
+            Some(
+                Fixed(
+                    2021-06-01T12:00:02+02:00,
+                ),
+            ),
+            Some(
+                Error,
+            ),
+            "fatal assertion",
+            [
+                "context=main",
+            ],
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_parse_mongodb_json_log_entry() {
+    let entry = LogEntry::parse(
+        br#"{"t":{"$date":"2021-06-01T12:00:00.123+02:00"},"s":"W","c":"NETWORK","id":22944,"ctx":"conn1","msg":"Connection ended","attr":{"remote":"127.0.0.1:50000"}}"#,
+    );
+    assert_debug_snapshot!(
+        (
+            entry.format(),
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ),
+        @r###"
+    (
+        Some(
+            Mongodb,
+        ),
+        Some(
+            Fixed(
+                2021-06-01T12:00:00.123+02:00,
+            ),
+        ),
+        Some(
+            Warning,
+        ),
+        "Connection ended",
+        [
+            "attr={\"remote\":\"127.0.0.1:50000\"}",
+            "component=NETWORK",
+            "context=conn1",
+            "id=22944",
+        ],
+    )
+    "###
+    );
+}
@@ -0,0 +1,190 @@
+use std::borrow::Cow;
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::{Match, Regex};
+
+use crate::parser::{self, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref MYSQL_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9]+)
+            \x20\[(System|Note|Warning|ERROR|Error)\]
+            (?:\x20\[(MY-[0-9]+)\])?
+            (?:\x20\[([A-Za-z]+)\])?
+            (?:\x20|$)
+        "#
+    )
+    .unwrap();
+    static ref LEGACY_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (
+                ([0-9]{2})([0-9]{2})([0-9]{2})
+                \x20+([0-9]{1,2}):([0-9]{2}):([0-9]{2})
+            )
+            \x20(?:([0-9]+)\x20)?
+            \[(Note|Warning|ERROR|Error)\]
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a MySQL or MariaDB error log.
+///
+/// The current format (`2021-06-01T12:00:00.123456Z 0 [Warning] ...`) as
+/// well as the legacy one with a two digit year (`210601 12:00:00 [Note]
+/// ...`) are understood.  The label sets the level and the thread id, error
+/// code and subsystem are exposed as the `thread`, `code` and `subsystem`
+/// fields.
+///
+/// Example: `2021-06-01T12:00:00.123456Z 0 [Warning] [MY-010101] [Server] message`
+pub fn parse_mysql_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    // all groups except the message only match ASCII
+    let text = |x: Match<'a>| str::from_utf8(x.as_bytes()).unwrap();
+
+    if let Some(caps) = LEGACY_RE.captures(bytes) {
+        let num = |idx| -> u32 { text(caps.get(idx).unwrap()).parse().unwrap() };
+        let naive = naive_datetime(2000 + num(2) as i32, num(3), num(4), num(5), num(6), num(7))?;
+        let message = caps.get(10).unwrap().range();
+        let entry = ctx
+            .local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(1).unwrap().range(), message)
+            .with_level(Cow::Borrowed(text(caps.get(9).unwrap())));
+        return Some(match caps.get(8) {
+            Some(thread) => entry.with_field("thread", text(thread)),
+            None => entry,
+        });
+    }
+
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = MYSQL_RE.captures(&bytes[message_span.clone()])?;
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let mut entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message)
+        .with_level(Cow::Borrowed(text(caps.get(2).unwrap())))
+        .with_field("thread", text(caps.get(1).unwrap()));
+    if let Some(code) = caps.get(3) {
+        entry = entry.with_field("code", text(code));
+    }
+    if let Some(subsystem) = caps.get(4) {
+        entry = entry.with_field("subsystem", text(subsystem));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_mysql_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01T12:00:00.123456Z 0 [Warning] [MY-010101] [Server] Insecure configuration"[..]),
+            parse(b"2021-06-01T12:00:01.000000Z 1 [System] [MY-010931] [Server] ready for connections"),
+            parse(b"2021-06-01 12:00:02 140234 [ERROR] Aborting"),
+            parse(b"210601 12:00:03 [Note] InnoDB: Shutdown completed"),
+            parse(b"210601  9:00:04 [Warning] Changed limits"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Mysql,
+            ),
+            Some(
+                "2021-06-01 14:00:00.123456",
+            ),
+            Some(
+                Warning,
+            ),
+            "Insecure configuration",
+            [
+                "thread=0",
+                "code=MY-010101",
+                "subsystem=Server",
+            ],
+        ),
+        (
+            Some(
+                Mysql,
+            ),
+            Some(
+                "2021-06-01 14:00:01",
+            ),
+            Some(
+                Info,
+            ),
+            "ready for connections",
+            [
+                "thread=1",
+                "code=MY-010931",
+                "subsystem=Server",
+            ],
+        ),
+        (
+            Some(
+                Mysql,
+            ),
+            Some(
+                "2021-06-01 12:00:02",
+            ),
+            Some(
+                Error,
+            ),
+            "Aborting",
+            [
+                "thread=140234",
+            ],
+        ),
+        (
+            Some(
+                Mysql,
+            ),
+            Some(
+                "2021-06-01 12:00:03",
+            ),
+            Some(
+                Info,
+            ),
+            "InnoDB: Shutdown completed",
+            [],
+        ),
+        (
+            Some(
+                Mysql,
+            ),
+            Some(
+                "2021-06-01 09:00:04",
+            ),
+            Some(
+                Warning,
+            ),
+            "Changed limits",
+            [],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref NPM_LEVEL_RE: Regex =
+        Regex::new(r#"^(silly|verbose|timing|http|info|notice|warn|error)(?:\x20|$)"#).unwrap();
+    static ref YARN_STEP_RE: Regex = Regex::new(r#"^\[([0-9]{1,2})/([0-9]{1,2})\]\x20"#).unwrap();
+}
+
+/// Parses a line of an npm debug log.
+///
+/// The log level follows the timestamp.  `silly` is mapped to `trace`,
+/// `verbose`, `timing` and `http` to `debug`.
+///
+/// Example: `2021-06-01T12:00:00.123Z verbose cli /usr/bin/node /usr/bin/npm`
+pub fn parse_npm_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = NPM_LEVEL_RE.captures(&bytes[message_span.clone()])?;
+
+    let level = match &caps[1] {
+        b"silly" => "trace",
+        b"verbose" | b"timing" | b"http" => "debug",
+        b"info" => "info",
+        b"notice" => "notice",
+        b"warn" => "warning",
+        _ => "error",
+    };
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        entry
+            .with_message(&bytes[message.clone()])
+            .with_spans(ts_span, message)
+            .with_level(Cow::Borrowed(level)),
+    )
+}
+
+/// Parses a step line of yarn (v1) output.
+///
+/// These lines carry no timestamp.  The step number and the step count
+/// are exposed as the `step` and `steps` fields.
+///
+/// Example: `[2/4] Fetching packages...`
+pub fn parse_yarn_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = YARN_STEP_RE.captures(bytes)?;
+    let message = caps.get(0).unwrap().end()..bytes.len();
+    Some(
+        LogEntry::from_message_only(&bytes[message.clone()])
+            .with_message_span(message)
+            .with_field("step", String::from_utf8_lossy(&caps[1]).into_owned())
+            .with_field("steps", String::from_utf8_lossy(&caps[2]).into_owned()),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_npm_log_entries() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.utc_timestamp().map(|x| x.to_rfc3339()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"2021-06-01T12:00:00.123Z verbose cli /usr/bin/node /usr/bin/npm"),
+            parse(b"2021-06-01T12:00:00.456Z timing npm:load Completed in 12ms"),
+            parse(b"2021-06-01T12:00:01.789Z error code ERESOLVE"),
+            parse(b"[2/4] Fetching packages..."),
+            parse(b"[4/4] Building fresh packages..."),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Npm,
+            ),
+            Some(
+                "2021-06-01T12:00:00.123+00:00",
+            ),
+            Some(
+                Debug,
+            ),
+            "cli /usr/bin/node /usr/bin/npm",
+            [],
+        ),
+        (
+            Some(
+                Npm,
+            ),
+            Some(
+                "2021-06-01T12:00:00.456+00:00",
+            ),
+            Some(
+                Debug,
+            ),
+            "npm:load Completed in 12ms",
+            [],
+        ),
+        (
+            Some(
+                Npm,
+            ),
+            Some(
+                "2021-06-01T12:00:01.789+00:00",
+            ),
+            Some(
+                Error,
+            ),
+            "code ERESOLVE",
+            [],
+        ),
+        (
+            Some(
+                Yarn,
+            ),
+            None,
+            None,
+            "Fetching packages...",
+            [
+                "step=2",
+                "steps=4",
+            ],
+        ),
+        (
+            Some(
+                Yarn,
+            ),
+            None,
+            None,
+            "Building fresh packages...",
+            [
+                "step=4",
+                "steps=4",
+            ],
+        ),
+    ]
+    "###
+    );
+}
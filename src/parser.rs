@@ -1,68 +1,820 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::BufRead;
+use std::ops::Range;
 use std::str;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
 
 use chrono::prelude::*;
+use chrono::Duration;
 use lazy_static::lazy_static;
-use regex::bytes::Regex;
+use regex::bytes::{Captures, Match, Regex};
 
-use crate::types::LogEntry;
+use crate::clock::{CachedClock, Clock, FixedClock, SystemClock};
+use crate::custom::{CustomFormat, FieldMap, FormatError};
+use crate::epoch::EpochHeuristics;
+use crate::error::Error;
+use crate::format::{parse_builtin, FormatKind, LogFormat, Registry};
+use crate::header::Header;
+use crate::scan::scan_log_entry;
+use crate::stream::Entries;
+use crate::syslog;
+use crate::types::{LogEntry, NaiveTimezone};
 
-fn now() -> DateTime<Local> {
-    #[cfg(test)]
-    {
-        Local.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap()
+/// The state available to the individual format parsers.
+pub struct Context {
+    timezone: Cell<NaiveTimezone>,
+    now: DateTime<Utc>,
+    is_reference: bool,
+    is_stream: bool,
+    last_time_only: Cell<Option<NaiveDateTime>>,
+    last_timestamp: Cell<Option<DateTime<Utc>>>,
+    elapsed: Cell<StdDuration>,
+    boot_time: Option<DateTime<Utc>>,
+    date_order: DateOrder,
+    header_start: Cell<Option<NaiveDateTime>>,
+    local_now: Cell<Option<NaiveDateTime>>,
+    w3c_columns: RefCell<Option<Vec<String>>>,
+}
+
+impl Context {
+    /// Creates a context reading the time from the given clock.
+    pub fn new(timezone: NaiveTimezone, clock: &dyn Clock) -> Context {
+        Context {
+            timezone: Cell::new(timezone),
+            now: clock.now(),
+            is_reference: false,
+            is_stream: false,
+            last_time_only: Cell::new(None),
+            last_timestamp: Cell::new(None),
+            elapsed: Cell::new(StdDuration::from_secs(0)),
+            boot_time: None,
+            date_order: DateOrder::Auto,
+            header_start: Cell::new(None),
+            local_now: Cell::new(None),
+            w3c_columns: RefCell::new(None),
+        }
     }
-    #[cfg(not(test))]
-    {
-        Local::now()
+
+    /// Creates a context that resolves missing date components relative
+    /// to a reference time.
+    ///
+    /// Unlike the current time, the reference is treated as an upper bound:
+    /// a timestamp that would land after it is moved back by a year or day.
+    pub fn with_reference(timezone: NaiveTimezone, reference: DateTime<Utc>) -> Context {
+        let mut ctx = Context::new(timezone, &FixedClock::new(reference));
+        ctx.is_reference = true;
+        ctx
+    }
+
+    /// Sets the time relative timestamps and deltas are anchored to.
+    pub(crate) fn boot_time(mut self, boot_time: Option<DateTime<Utc>>) -> Context {
+        self.boot_time = boot_time;
+        self
+    }
+
+    /// Sets how numeric dates with slashes are read.
+    pub(crate) fn date_order(mut self, date_order: DateOrder) -> Context {
+        self.date_order = date_order;
+        self
+    }
+
+    /// Enables tracking of state across consecutive lines.
+    ///
+    /// When a time-only timestamp jumps back by more than twelve hours
+    /// compared to the previous one, the day is assumed to have changed
+    /// and the inferred date advances.  Jumping forward by more than twelve
+    /// hours is treated as an out of order line from the previous day.
+    ///
+    /// Delta timestamps accumulate relative to the previous timestamp.
+    pub(crate) fn for_stream(mut self) -> Context {
+        self.is_stream = true;
+        self
+    }
+
+    /// Applies the context established by a file preamble.
+    ///
+    /// The start time of the file replaces the current time for inferring
+    /// missing years and dates, and becomes the base for the midnight
+    /// tracking of time-only timestamps.
+    pub(crate) fn apply_header(&self, header: &Header) {
+        if let Some(timezone) = header.timezone {
+            self.timezone.set(timezone);
+            self.local_now.set(None);
+        }
+        self.header_start.set(Some(header.start));
+        self.last_time_only.set(Some(header.start));
+    }
+
+    /// Sets the columns declared by a `#Fields:` directive of a W3C
+    /// extended log.
+    pub(crate) fn set_w3c_columns(&self, columns: Vec<String>) {
+        *self.w3c_columns.borrow_mut() = Some(columns);
+    }
+
+    /// Calls `f` with the columns of the last `#Fields:` directive, if any.
+    pub(crate) fn with_w3c_columns<T, F: FnOnce(&[String]) -> T>(&self, f: F) -> Option<T> {
+        self.w3c_columns.borrow().as_deref().map(f)
     }
-}
 
-fn today(offset: Option<FixedOffset>) -> (i32, u32, u32) {
-    match offset {
-        None => {
-            let today = {
-                #[cfg(test)]
-                {
-                    Local.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap()
+    /// Returns the current time in the timezone used for local times.
+    ///
+    /// The conversion is cached as the current time of a context does not
+    /// change.
+    fn local_now(&self) -> NaiveDateTime {
+        if let Some(local_now) = self.local_now.get() {
+            return local_now;
+        }
+        let local_now = self.timezone.get().naive_now(self.now);
+        self.local_now.set(Some(local_now));
+        local_now
+    }
+
+    /// Returns the current time, or the reference time if one is set.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    /// Creates an entry for a timestamp without an offset.
+    ///
+    /// The timestamp is resolved in the timezone configured for naive
+    /// timestamps.
+    pub fn local_entry<'a>(&self, naive: NaiveDateTime, message: &'a [u8]) -> Option<LogEntry<'a>> {
+        Some(LogEntry::from_timestamp(
+            self.timezone.get().resolve(&naive)?,
+            message,
+        ))
+    }
+
+    /// Infers the year for a timestamp that lacks one.
+    pub fn infer_year(&self, month: u32, day: u32, h: u32, m: u32, s: u32) -> i32 {
+        if let Some(start) = self.header_start.get() {
+            // a file opened in december can contain entries from january
+            let year = start.year();
+            return match naive_datetime(year, month, day, h, m, s) {
+                Some(ts) if start - ts > Duration::days(1) => year + 1,
+                _ => year,
+            };
+        }
+        let now = self.local_now();
+        let year = now.year();
+        if self.is_reference {
+            if let Some(ts) = naive_datetime(year, month, day, h, m, s) {
+                if ts > now {
+                    return year - 1;
                 }
-                #[cfg(not(test))]
-                {
-                    Local::now()
+            }
+        }
+        year
+    }
+
+    /// Infers the date for a timestamp that only carries a time.
+    pub fn infer_date(&self, h: u32, m: u32, s: u32) -> NaiveDate {
+        if !self.is_stream && self.header_start.get().is_none() {
+            return self.infer_date_from_now(h, m, s);
+        }
+        let time = match naive_time(h, m, s) {
+            Some(time) => time,
+            None => return self.infer_date_from_now(h, m, s),
+        };
+        let ts = match self.last_time_only.get() {
+            Some(last) => {
+                let ts = last.date().and_time(time);
+                if last - ts > Duration::hours(12) {
+                    ts + Duration::days(1)
+                } else if ts - last > Duration::hours(12) {
+                    ts - Duration::days(1)
+                } else {
+                    ts
                 }
-            };
-            (today.year(), today.month(), today.day())
+            }
+            None => self.infer_date_from_now(h, m, s).and_time(time),
+        };
+        self.last_time_only.set(Some(ts));
+        ts.date()
+    }
+
+    /// Advances the running time by a delta.
+    ///
+    /// Returns the resulting absolute time if one is known and the total
+    /// time elapsed since the start of the stream.
+    fn advance(&self, delta: StdDuration) -> (Option<DateTime<Utc>>, StdDuration) {
+        // deltas too large to add give no absolute time
+        let add = |ts: DateTime<Utc>, delta: StdDuration| {
+            ts.checked_add_signed(Duration::from_std(delta).ok()?)
+        };
+        if !self.is_stream {
+            return (self.boot_time.and_then(|x| add(x, delta)), delta);
         }
-        Some(offset) => {
-            let today = {
-                #[cfg(test)]
-                {
-                    Utc.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap()
+        let elapsed = self.elapsed.get().saturating_add(delta);
+        self.elapsed.set(elapsed);
+        let ts = match self.last_timestamp.get() {
+            Some(last) => add(last, delta),
+            None => self.boot_time.and_then(|x| add(x, elapsed)),
+        };
+        (ts, elapsed)
+    }
+
+    /// Remembers the timestamp of the last entry in a stream.
+    fn record(&self, entry: &LogEntry) {
+        if self.is_stream {
+            if let Some(ts) = entry.utc_timestamp() {
+                self.last_timestamp.set(Some(ts));
+            }
+        }
+    }
+
+    fn infer_date_from_now(&self, h: u32, m: u32, s: u32) -> NaiveDate {
+        let now = self.local_now();
+        let today = now.date();
+        if self.is_reference {
+            if let Some(time) = naive_time(h, m, s) {
+                if time > now.time() {
+                    return today.pred_opt().unwrap_or(today);
                 }
-                #[cfg(not(test))]
-                {
-                    Utc::now()
+            }
+        }
+        today
+    }
+}
+
+/// What to do with timestamps outside of the plausible range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplausibleAction {
+    /// Keeps the timestamp but flags the entry.
+    Flag,
+    /// Clamps the timestamp to the closest plausible time and flags the entry.
+    Clamp,
+    /// Removes the timestamp and flags the entry.
+    Discard,
+}
+
+/// The order of day and month in numeric dates like `03/04/2021`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    /// The month comes first, as in the US (`MM/DD/YYYY`).
+    MonthFirst,
+    /// The day comes first, as in most of Europe (`DD/MM/YYYY`).
+    DayFirst,
+    /// The month comes first unless the first number cannot be a month.
+    #[default]
+    Auto,
+}
+
+impl DateOrder {
+    /// Returns the month and day for the first two numbers of a date.
+    fn resolve(self, first: u32, second: u32) -> (u32, u32) {
+        match self {
+            DateOrder::MonthFirst => (first, second),
+            DateOrder::DayFirst => (second, first),
+            DateOrder::Auto if first > 12 => (second, first),
+            DateOrder::Auto => (first, second),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimestampGuard {
+    max_past: Duration,
+    max_future: Duration,
+    action: ImplausibleAction,
+}
+
+impl TimestampGuard {
+    fn apply<'a>(&self, mut entry: LogEntry<'a>, now: DateTime<Utc>) -> LogEntry<'a> {
+        let ts = match entry.utc_timestamp() {
+            Some(ts) => ts,
+            None => return entry,
+        };
+        let clamped = if ts < now - self.max_past {
+            now - self.max_past
+        } else if ts > now + self.max_future {
+            now + self.max_future
+        } else {
+            return entry;
+        };
+        match self.action {
+            ImplausibleAction::Flag => {}
+            ImplausibleAction::Clamp => entry.clamp_timestamp(clamped),
+            ImplausibleAction::Discard => entry.discard_timestamp(),
+        }
+        entry.flag_implausible_timestamp();
+        entry
+    }
+}
+
+/// A configured log line parser.
+///
+/// The free standing `LogEntry::parse` functions use the system clock and
+/// the local timezone.  A parser allows these to be customized.
+#[derive(Clone)]
+pub struct Parser {
+    timezone: NaiveTimezone,
+    clock: Arc<dyn Clock>,
+    reference: Option<DateTime<Utc>>,
+    guard: Option<TimestampGuard>,
+    max_line_length: Option<usize>,
+    skip_binary: bool,
+    boot_time: Option<DateTime<Utc>>,
+    date_order: DateOrder,
+    scan_line: bool,
+    strip_level_markers: bool,
+    merge_continuation_lines: bool,
+    syslog_headers: bool,
+    group_exceptions: bool,
+    epoch: Option<EpochHeuristics>,
+    inherit_timestamps: bool,
+    bump_inherited_timestamps: bool,
+    custom_formats: Registry,
+    formats: Registry,
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser::builder().build()
+    }
+}
+
+impl fmt::Debug for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("timezone", &self.timezone)
+            .field("reference", &self.reference)
+            .field("guard", &self.guard)
+            .field("max_line_length", &self.max_line_length)
+            .field("skip_binary", &self.skip_binary)
+            .field("boot_time", &self.boot_time)
+            .field("date_order", &self.date_order)
+            .field("scan_line", &self.scan_line)
+            .field("strip_level_markers", &self.strip_level_markers)
+            .field("merge_continuation_lines", &self.merge_continuation_lines)
+            .field("syslog_headers", &self.syslog_headers)
+            .field("group_exceptions", &self.group_exceptions)
+            .field("epoch", &self.epoch)
+            .field("inherit_timestamps", &self.inherit_timestamps)
+            .field("bump_inherited_timestamps", &self.bump_inherited_timestamps)
+            .field("custom_formats", &self.custom_formats)
+            .field("formats", &self.formats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Parser {
+    /// Creates a parser with the default configuration.
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    /// Creates a parser for logs with timestamps in a strftime layout.
+    ///
+    /// This is a shorthand for a parser with a single custom format, see
+    /// [`CustomFormat::from_strftime`].  The built-in formats are still
+    /// tried for lines that do not match.
+    pub fn with_format(format: &str, prefix_len_hint: Option<usize>) -> Parser {
+        Parser::builder()
+            .custom_format(CustomFormat::from_strftime(format, prefix_len_hint))
+            .build()
+    }
+
+    /// Returns a builder to configure a parser.
+    pub fn builder() -> ParserBuilder {
+        ParserBuilder {
+            parser: Parser {
+                timezone: NaiveTimezone::Local,
+                clock: Arc::new(SystemClock),
+                reference: None,
+                guard: None,
+                max_line_length: None,
+                skip_binary: false,
+                boot_time: None,
+                date_order: DateOrder::Auto,
+                scan_line: false,
+                strip_level_markers: false,
+                merge_continuation_lines: false,
+                syslog_headers: false,
+                group_exceptions: false,
+                epoch: Some(EpochHeuristics::default()),
+                inherit_timestamps: false,
+                bump_inherited_timestamps: false,
+                custom_formats: Registry::new(),
+                formats: Registry::builtin(),
+            },
+        }
+    }
+
+    /// Parses a log line into a log entry.
+    pub fn parse<'a>(&self, bytes: &'a [u8]) -> LogEntry<'a> {
+        self.parse_with_context(bytes, &self.context())
+    }
+
+    /// Parses a log line and fails if it carries no timestamp.
+    ///
+    /// Unlike [`parse`](Parser::parse), which falls back to an entry with
+    /// only a message and replaces invalid UTF-8, this reports why the line
+    /// could not be parsed fully.
+    pub fn try_parse<'a>(&self, bytes: &'a [u8]) -> Result<LogEntry<'a>, Error> {
+        str::from_utf8(bytes)?;
+        let entry = self.parse(bytes);
+        if entry.timestamp().is_none() && entry.relative_time().is_none() {
+            return Err(Error::NoTimestamp);
+        }
+        Ok(entry)
+    }
+
+    /// Reads and parses all lines from a reader.
+    ///
+    /// Unlike parsing individual lines, a stream keeps track of the lines
+    /// it has already seen.  Time-only timestamps which cross midnight
+    /// advance the inferred date.
+    pub fn read_entries<R: BufRead>(&self, reader: R) -> Entries<R> {
+        Entries::new(self.clone(), reader)
+    }
+
+    pub(crate) fn context(&self) -> Context {
+        match self.reference {
+            Some(reference) => Context::with_reference(self.timezone, reference),
+            None => Context::new(self.timezone, &*self.clock),
+        }
+        .boot_time(self.boot_time)
+        .date_order(self.date_order)
+    }
+
+    pub(crate) fn max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+
+    pub(crate) fn skips_binary(&self) -> bool {
+        self.skip_binary
+    }
+
+    pub(crate) fn groups_exceptions(&self) -> bool {
+        self.group_exceptions
+    }
+
+    pub(crate) fn merges_continuation_lines(&self) -> bool {
+        self.merge_continuation_lines
+    }
+
+    pub(crate) fn inherits_timestamps(&self) -> bool {
+        self.inherit_timestamps
+    }
+
+    pub(crate) fn bumps_inherited_timestamps(&self) -> bool {
+        self.bump_inherited_timestamps
+    }
+
+    pub(crate) fn parse_with_context<'a>(&self, bytes: &'a [u8], ctx: &Context) -> LogEntry<'a> {
+        let (bytes, truncated) = match self.max_line_length {
+            Some(max) if bytes.len() > max => (truncate_line(bytes, max), true),
+            _ => (bytes, false),
+        };
+        let mut entry = self
+            .custom_formats
+            .parse(bytes, ctx)
+            .or_else(|| {
+                if self.syslog_headers {
+                    syslog::parse_rfc3164_log_entry(bytes, ctx)
+                        .map(|entry| entry.with_format(FormatKind::Rfc3164))
+                } else {
+                    None
                 }
+            })
+            .or_else(|| self.formats.parse(bytes, ctx))
+            .map(|entry| match entry.format() {
+                Some(_) => entry,
+                None => entry.with_format(FormatKind::Custom),
+            })
+            .or_else(|| self.epoch.as_ref()?.parse(bytes, ctx))
+            .or_else(|| {
+                if self.scan_line {
+                    scan_log_entry(bytes, ctx)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| LogEntry::from_message_only(bytes));
+        entry.detect_precision(bytes);
+        entry.detect_level_marker(self.strip_level_markers);
+        if truncated {
+            entry.mark_truncated();
+        }
+        let entry = match self.guard {
+            Some(ref guard) => guard.apply(entry, ctx.now),
+            None => entry,
+        };
+        ctx.record(&entry);
+        entry
+    }
+}
+
+/// A builder for a [`Parser`].
+pub struct ParserBuilder {
+    parser: Parser,
+}
+
+impl ParserBuilder {
+    /// Sets the timezone to use for local time.
+    ///
+    /// If not set, the timezone of the machine is used.  This is a
+    /// shorthand for `naive_timezone` with a fixed offset.
+    pub fn local_timezone(mut self, offset: Option<FixedOffset>) -> ParserBuilder {
+        self.parser.timezone = offset.into();
+        self
+    }
+
+    /// Sets the timezone assumed for timestamps without an offset.
+    ///
+    /// Use [`NaiveTimezone::Utc`] when processing logs of servers that run
+    /// in UTC, regardless of the timezone of the parsing machine.
+    pub fn naive_timezone(mut self, timezone: NaiveTimezone) -> ParserBuilder {
+        self.parser.timezone = timezone;
+        self
+    }
+
+    /// Sets the clock used to infer missing date components.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> ParserBuilder {
+        self.parser.clock = Arc::new(clock);
+        self
+    }
+
+    /// Reads the clock at most once per interval.
+    ///
+    /// Parsing individual lines consults the clock for every line.  For
+    /// bulk workloads a slightly stale time is good enough; this wraps the
+    /// configured clock in a [`CachedClock`].  Call it after `clock`.
+    pub fn cache_clock(mut self, interval: StdDuration) -> ParserBuilder {
+        self.parser.clock = Arc::new(CachedClock::from_arc(self.parser.clock, interval));
+        self
+    }
+
+    /// Resolves missing date components relative to a reference time.
+    ///
+    /// This is useful for old logs where the reference is for instance
+    /// the modification time of the logfile.  Timestamps are assumed to
+    /// not lie after the reference, so `Dec 31` with a reference in March
+    /// resolves to the previous year.  This takes precedence over the clock.
+    pub fn reference(mut self, reference: Option<DateTime<Utc>>) -> ParserBuilder {
+        self.parser.reference = reference;
+        self
+    }
+
+    /// Guards against implausible timestamps.
+    ///
+    /// Timestamps more than `max_past` before or `max_future` after the
+    /// current time (or the reference time) are handled according to the
+    /// given action.  This catches bogus dates from wrong year inference,
+    /// two digit years or devices with skewed clocks.
+    pub fn timestamp_guard(
+        mut self,
+        max_past: Duration,
+        max_future: Duration,
+        action: ImplausibleAction,
+    ) -> ParserBuilder {
+        self.parser.guard = Some(TimestampGuard {
+            max_past,
+            max_future,
+            action,
+        });
+        self
+    }
+
+    /// Limits the length of lines in bytes.
+    ///
+    /// Longer lines are cut off before parsing and the resulting entry is
+    /// marked as truncated.  When reading streams the excess bytes are
+    /// skipped without being buffered.
+    pub fn max_line_length(mut self, max_line_length: Option<usize>) -> ParserBuilder {
+        self.parser.max_line_length = max_line_length;
+        self
+    }
+
+    /// Resynchronizes streams after binary garbage.
+    ///
+    /// Crash truncated or partially binary files contain stretches of NUL
+    /// bytes and other garbage.  With this enabled, streams treat NUL runs
+    /// as line breaks, drop anything up to the last undecodable byte of a
+    /// line and skip lines which consist of garbage only.
+    pub fn skip_binary(mut self, yes: bool) -> ParserBuilder {
+        self.parser.skip_binary = yes;
+        self
+    }
+
+    /// Sets the time relative timestamps are anchored to.
+    ///
+    /// Some formats like the kernel ring buffer only log the seconds since
+    /// boot or process start.  With an anchor these become absolute
+    /// timestamps, without one only the relative time is recorded.
+    ///
+    /// This is also the base for delta timestamps (`+0.123s`) until a
+    /// stream encounters its first absolute timestamp.
+    pub fn boot_time(mut self, boot_time: Option<DateTime<Utc>>) -> ParserBuilder {
+        self.parser.boot_time = boot_time;
+        self
+    }
+
+    /// Sets the order of day and month in numeric dates with slashes.
+    ///
+    /// `03/04/2021` is the 4th of March in the US and the 3rd of April in
+    /// most of Europe.  By default the month is assumed to come first
+    /// unless the first number is larger than 12.  Dates with dots
+    /// (`03.04.2021`) always have the day first.
+    pub fn date_order(mut self, date_order: DateOrder) -> ParserBuilder {
+        self.parser.date_order = date_order;
+        self
+    }
+
+    /// Searches the whole line for a timestamp if no format matches.
+    ///
+    /// Some producers put the timestamp at the end or in the middle of the
+    /// line (uWSGI, HAProxy, S3 access logs).  With this enabled such
+    /// timestamps are found and removed from the message.
+    pub fn scan_line(mut self, yes: bool) -> ParserBuilder {
+        self.parser.scan_line = yes;
+        self
+    }
+
+    /// Splits the hostname and tag off syslog lines.
+    ///
+    /// Syslog lines without a priority prefix are otherwise parsed by the
+    /// short format, which keeps hostname and tag in the message.  With
+    /// this enabled they are exposed through [`LogEntry::hostname`],
+    /// [`LogEntry::app_name`] and [`LogEntry::process_id`] instead.
+    pub fn syslog_headers(mut self, yes: bool) -> ParserBuilder {
+        self.parser.syslog_headers = yes;
+        self
+    }
+
+    /// Removes severity markers such as `[INFO]` from messages.
+    ///
+    /// The level is reported by [`LogEntry::level`] either way.  By default
+    /// messages are left as they are.
+    pub fn strip_level_markers(mut self, yes: bool) -> ParserBuilder {
+        self.parser.strip_level_markers = yes;
+        self
+    }
+
+    /// Merges stack traces into the entry that precedes them.
+    ///
+    /// Python tracebacks, JVM exceptions and Rust panics span many lines
+    /// without timestamps.  With this enabled, streams append such lines
+    /// to the message of the preceding entry, which then reports the trace
+    /// through [`LogEntry::stack_trace`].
+    pub fn group_exceptions(mut self, yes: bool) -> ParserBuilder {
+        self.parser.group_exceptions = yes;
+        self
+    }
+
+    /// Merges all lines without a timestamp into the entry that precedes
+    /// them when reading streams.
+    ///
+    /// This covers indented continuation lines and stack traces of any
+    /// language.  Unlike [`group_exceptions`] it does not look at the
+    /// lines, so untimed lines never show up as entries of their own
+    /// except before the first timestamp.
+    ///
+    /// [`group_exceptions`]: ParserBuilder::group_exceptions
+    pub fn merge_continuation_lines(mut self, yes: bool) -> ParserBuilder {
+        self.parser.merge_continuation_lines = yes;
+        self
+    }
+
+    /// Gives entries without a timestamp the timestamp of the preceding
+    /// entry when reading streams.
+    ///
+    /// Such entries report [`LogEntry::has_inherited_timestamp`].
+    pub fn inherit_timestamps(mut self, yes: bool) -> ParserBuilder {
+        self.parser.inherit_timestamps = yes;
+        self
+    }
+
+    /// Advances each inherited timestamp by a microsecond.
+    ///
+    /// This keeps the order of the lines when entries are later sorted by
+    /// timestamp.  Only has an effect with [`inherit_timestamps`].
+    ///
+    /// [`inherit_timestamps`]: ParserBuilder::inherit_timestamps
+    pub fn bump_inherited_timestamps(mut self, yes: bool) -> ParserBuilder {
+        self.parser.bump_inherited_timestamps = yes;
+        self
+    }
+
+    /// Controls how lines starting with a number are read as epoch
+    /// timestamps.
+    ///
+    /// Defaults to [`EpochHeuristics::default`], which only accepts numbers
+    /// that fall between 2000 and 2100 and need a fraction unless they have
+    /// millisecond or finer width.  Pass `None` to disable epoch
+    /// timestamps, for instance if lines start with ids that happen to
+    /// look like timestamps.
+    pub fn epoch_heuristics(mut self, heuristics: Option<EpochHeuristics>) -> ParserBuilder {
+        self.parser.epoch = heuristics;
+        self
+    }
+
+    /// Adds a custom format.
+    ///
+    /// Custom formats are tried in the order they were added and before
+    /// any of the built-in formats.
+    pub fn custom_format<F: Into<CustomFormat>>(mut self, format: F) -> ParserBuilder {
+        self.parser.custom_formats.register(format.into());
+        self
+    }
+
+    /// Compiles and adds a custom format from a regular expression.
+    ///
+    /// The named capture groups are mapped onto the entry as described for
+    /// [`CustomFormat`], `fields` renames them where the expression uses
+    /// different names.  The expression doubles as the name of the format.
+    pub fn add_custom(self, regex: &str, fields: &FieldMap) -> Result<ParserBuilder, FormatError> {
+        Ok(self.custom_format(CustomFormat::with_fields(regex, regex, fields)?))
+    }
+
+    /// Adds multiple custom formats.
+    pub fn custom_formats<I: IntoIterator<Item = CustomFormat>>(
+        mut self,
+        formats: I,
+    ) -> ParserBuilder {
+        for format in formats {
+            self.parser.custom_formats.register(format);
+        }
+        self
+    }
+
+    /// Adds a format implemented outside of this crate.
+    ///
+    /// Like custom formats it is tried before the built-in formats.
+    pub fn format<F: LogFormat + 'static>(mut self, format: F) -> ParserBuilder {
+        self.parser.custom_formats.register(format);
+        self
+    }
+
+    /// Replaces the registry of formats tried after the custom formats.
+    ///
+    /// Defaults to [`Registry::builtin`].
+    pub fn registry(mut self, registry: Registry) -> ParserBuilder {
+        self.parser.formats = registry;
+        self
+    }
+
+    /// Only tries the given formats, in the given order.
+    ///
+    /// This replaces the registry with the given built-in formats.
+    /// [`FormatKind::Epoch`] enables the default epoch heuristics unless
+    /// others are configured and [`FormatKind::Scanned`] enables
+    /// [`scan_line`].  Custom formats are not affected.
+    ///
+    /// [`scan_line`]: ParserBuilder::scan_line
+    pub fn enable_formats<I: IntoIterator<Item = FormatKind>>(mut self, kinds: I) -> ParserBuilder {
+        let kinds: Vec<_> = kinds.into_iter().collect();
+        self.parser.formats = Registry::with_builtin(kinds.iter().cloned());
+        if kinds.contains(&FormatKind::Epoch) {
+            self.parser
+                .epoch
+                .get_or_insert_with(EpochHeuristics::default);
+        } else {
+            self.parser.epoch = None;
+        }
+        self.parser.scan_line = kinds.contains(&FormatKind::Scanned);
+        self
+    }
+
+    /// Stops trying the given format.
+    pub fn disable_format(mut self, kind: FormatKind) -> ParserBuilder {
+        match kind {
+            FormatKind::Epoch => self.parser.epoch = None,
+            FormatKind::Scanned => self.parser.scan_line = false,
+            kind => {
+                self.parser.formats.remove(kind.as_str());
             }
-            .with_timezone(&offset);
-            (today.year(), today.month(), today.day())
         }
+        self
+    }
+
+    /// Builds the parser.
+    pub fn build(self) -> Parser {
+        self.parser
     }
 }
 
 lazy_static! {
+    static ref DEFAULT_EPOCH: EpochHeuristics = EpochHeuristics::default();
     static ref C_LOG_RE: Regex = Regex::new(
         r#"(?x)
         ^
             \[?
-            (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20
-            (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
-            \x20
-            ([0-9]+)
-            \x20
-            ([0-9]{2}):([0-9]{2}):([0-9]{2})
-            (?:\.[0-9]+)?
-            \x20
-            ([0-9]+)
+            (
+                (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20
+                (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+                \x20
+                ([0-9]+)
+                \x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.[0-9]+)?
+                \x20
+                ([0-9]+)
+            )
             \]?
             [\t\x20]
             (.*)
@@ -73,13 +825,15 @@ lazy_static! {
         r#"(?x)
         ^
             \[?
-            (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
-            (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
-            \x20
-            ([0-9]+)
-            \x20
-            ([0-9]{2}):([0-9]{2}):([0-9]{2})
-            (?:\.[0-9]+)?
+            (
+                (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
+                (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+                \x20
+                ([0-9]+)
+                \x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.([0-9]{1,9})[0-9]*)?
+            )
             \]?
             [\t\x20]
             (.*)
@@ -90,9 +844,12 @@ lazy_static! {
         r#"(?x)
         ^
             \[?
+            (
                 ([0-9]+):
                 ([0-9]+):
                 ([0-9]+)
+                (?:\x20?(AM|PM))?
+            )
             \]?
             [\t\x20]
             (.*)
@@ -103,12 +860,14 @@ lazy_static! {
         r#"(?x)
         ^
             \[?
-            ([0-9]{4}?)-(0[1-9]|1[0-2])-(0[1-9]|[12][0-9]|3[01])
-            \x20
-            ([0-9]{2}):([0-9]{2}):([0-9]{2})
-            \x20
-            ([+-])
-            ([0-9]{2})([0-9]{2})
+            (
+                ([0-9]{4}?)-(0[1-9]|1[0-2])-(0[1-9]|[12][0-9]|3[01])
+                \x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+                \x20
+                ([+-])
+                ([0-9]{2})([0-9]{2})
+            )
             :?
             \]?
             [\t\x20]
@@ -116,19 +875,42 @@ lazy_static! {
         $
     "#
     ).unwrap();
+    static ref RFC3339_LOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[?
+            (
+                ([0-9]{4})-(0[1-9]|1[0-2])-(0[1-9]|[12][0-9]|3[01])
+                [Tt\x20]
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:[.,]([0-9]{1,9})[0-9]*)?
+                (?:
+                    ([Zz])
+                    |
+                    ([+-])([0-9]{2}):?([0-9]{2})
+                )?
+            )
+            \]?
+            [\t\x20]+
+            (.*)
+        $
+    "#
+    ).unwrap();
     static ref COMMON_ALT_LOG_RE: Regex = Regex::new(
         r#"(?x)
         ^
             \[?
-            (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
-            (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
-            \x20+
-            ([0-9]+)
-            \x20
-            ([0-9]{2}):([0-9]{2}):([0-9]{2})
-            (?:\.[0-9]+)?
-            \x20
-            ([0-9]{4})
+            (
+                (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
+                (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+                \x20+
+                ([0-9]+)
+                \x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.[0-9]+)?
+                \x20
+                ([0-9]{4})
+            )
             \]?
             [\t\x20]
             (.*)
@@ -139,63 +921,209 @@ lazy_static! {
         r#"(?x)
         ^
             \[?
-            (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
-            (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
-            \x20+
-            ([0-9]+),?
-            \x20
-            ([0-9]{4})
-            \x20
-            ([0-9]{2}):([0-9]{2}):([0-9]{2})
-            (?:\.[0-9]+)?
+            (
+                (?:(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20)?
+                (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+                \x20+
+                ([0-9]+),?
+                \x20
+                ([0-9]{4})
+                \x20
+                ([0-9]{1,2}):([0-9]{2}):([0-9]{2})
+                (?:\.[0-9]+)?
+                (?:\x20?(AM|PM))?
+            )
             \]?
             [\t\x20]
             (.*)
         $
     "#
     ).unwrap();
+    static ref SLASH_DATE_LOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[?
+            (
+                ([0-9]{1,2})/([0-9]{1,2})/([0-9]{4})
+                ,?\x20
+                ([0-9]{1,2}):([0-9]{2}):([0-9]{2})
+                (?:[.,]([0-9]{1,9})[0-9]*)?
+                (?:\x20?(AM|PM))?
+            )
+            \]?
+            [\t\x20]+
+            (.*)
+        $
+    "#
+    ).unwrap();
+    static ref DOT_DATE_LOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[?
+            (
+                (0?[1-9]|[12][0-9]|3[01])\.(0?[1-9]|1[0-2])\.([0-9]{4})
+                \x20
+                ([0-9]{1,2}):([0-9]{2}):([0-9]{2})
+                (?:[.,]([0-9]{1,9})[0-9]*)?
+            )
+            \]?
+            [\t\x20]+
+            (.*)
+        $
+    "#
+    ).unwrap();
+    static ref COMPACT_LOG_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            \[?
+            (
+                ([0-9]{4})(0[1-9]|1[0-2])(0[1-9]|[12][0-9]|3[01])
+                (?:
+                    [-T_\x20]?([01][0-9]|2[0-3])([0-5][0-9])([0-5][0-9]|60)
+                    |
+                    [-T_\x20]([0-9]{2}):([0-9]{2}):([0-9]{2})
+                )
+                (?:[.,]([0-9]{1,9})[0-9]*)?
+            )
+            \]?
+            [\t\x20]+
+            (.*)
+        $
+    "#
+    ).unwrap();
+    static ref UPTIME_LOG_RE: Regex = Regex::new(
+        // [   12.345678] usb 1-1: new high-speed USB device
+        r#"(?x)
+        ^
+            (?:
+                # dmesg -r
+                <([0-9]{1,3})>
+                |
+                # dmesg -x
+                ([a-z]+)\x20*:([a-z]+)\x20*:\x20
+            )?
+            (
+                \[\x20*
+                    ([0-9]+\.[0-9]+)
+                \]
+            )
+            \x20?
+            (.*)
+        $
+    "#
+    ).unwrap();
+    static ref DELTA_LOG_RE: Regex = Regex::new(
+        // +0.123s some message
+        r#"(?x)
+        ^
+            (
+                \+
+                ([0-9]+(?:\.[0-9]+)?)
+                (s|ms|us|µs|ns)
+            )
+            [\t\x20]
+            (.*)
+        $
+    "#
+    ).unwrap();
     static ref UE4_LOG_RE: Regex = Regex::new(
         // [2018.10.29-16.56.37:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]
         r#"(?x)
         ^
             \[
-                ([0-9]{4}?)\.(0[1-9]|1[0-2])\.(0[1-9]|[12][0-9]|3[01])
-                -
-                ([0-9]+)\.([0-9]+)\.([0-9]+)
-                :
-                (?:[0-9]+)
+                (
+                    ([0-9]{4}?)\.(0[1-9]|1[0-2])\.(0[1-9]|[12][0-9]|3[01])
+                    -
+                    ([0-9]+)\.([0-9]+)\.([0-9]+)
+                    :
+                    ([0-9]{1,9})[0-9]*
+                )
             \]
-            \[\x20*[0-9]+\]
-            (.*)
+            \[\x20*([0-9]+)\]
+            (
+                (?:
+                    (Log[A-Za-z0-9_]*):\x20
+                    (?:(Fatal|Error|Warning|Display|Log|Verbose|VeryVerbose):\x20)?
+                )?
+                .*
+            )
         $
     "#
     ).unwrap();
 }
 
+/// Creates a time of day, tolerating leap seconds.
+///
+/// A second value of `60` is represented the way chrono represents leap
+/// seconds, as the 59th second with an extra second worth of nanoseconds.
+pub(crate) fn naive_time(h: u32, m: u32, s: u32) -> Option<NaiveTime> {
+    if s == 60 {
+        NaiveTime::from_hms_nano_opt(h, m, 59, 1_000_000_000)
+    } else {
+        NaiveTime::from_hms_opt(h, m, s)
+    }
+}
+
+pub(crate) fn naive_datetime(
+    year: i32,
+    month: u32,
+    day: u32,
+    h: u32,
+    m: u32,
+    s: u32,
+) -> Option<NaiveDateTime> {
+    Some(NaiveDate::from_ymd_opt(year, month, day)?.and_time(naive_time(h, m, s)?))
+}
+
 #[allow(clippy::too_many_arguments)]
-fn log_entry_from_local_time(
-    offset: Option<FixedOffset>,
+pub(crate) fn log_entry_from_local_time<'a>(
+    ctx: &Context,
     year: i32,
     month: u32,
     day: u32,
     hh: u32,
     mm: u32,
     ss: u32,
-    message: &[u8],
-) -> Option<LogEntry> {
-    match offset {
-        Some(offset) => offset
-            .with_ymd_and_hms(year, month, day, hh, mm, ss)
-            .latest()
-            .map(|date| LogEntry::from_fixed_time(date, message)),
-        None => Local
-            .with_ymd_and_hms(year, month, day, hh, mm, ss)
-            .latest()
-            .map(|date| LogEntry::from_local_time(date, message)),
+    message: &'a [u8],
+) -> Option<LogEntry<'a>> {
+    ctx.local_entry(naive_datetime(year, month, day, hh, mm, ss)?, message)
+}
+
+/// Cuts a line to at most `max` bytes without splitting a UTF-8 character.
+fn truncate_line(bytes: &[u8], max: usize) -> &[u8] {
+    let mut end = max;
+    while end > 0 && max - end < 3 && bytes[end] & 0xc0 == 0x80 {
+        end -= 1;
+    }
+    if bytes[end] & 0xc0 == 0x80 {
+        end = max;
+    }
+    &bytes[..end]
+}
+
+fn span(caps: &Captures, idx: usize) -> Range<usize> {
+    caps.get(idx).map_or(0..0, |x| x.range())
+}
+
+/// Converts an hour on a 12-hour clock to a 24-hour clock.
+///
+/// Without an `AM` or `PM` marker the hour is returned as is.  Only the
+/// uppercase markers are understood, as `am` is a common word in messages.
+fn to_24_hour(h: u32, meridiem: Option<Match>) -> Option<u32> {
+    let pm = match meridiem {
+        Some(meridiem) => meridiem.as_bytes() == b"PM",
+        None => return Some(h),
+    };
+    match (h, pm) {
+        (1..=11, false) => Some(h),
+        (12, false) => Some(0),
+        (1..=11, true) => Some(h + 12),
+        (12, true) => Some(12),
+        _ => None,
     }
 }
 
-fn get_month(bytes: &[u8]) -> Option<u32> {
+pub(crate) fn get_month(bytes: &[u8]) -> Option<u32> {
     Some(match bytes {
         b"Jan" => 1,
         b"Feb" => 2,
@@ -213,204 +1141,435 @@ fn get_month(bytes: &[u8]) -> Option<u32> {
     })
 }
 
-pub fn parse_c_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match C_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
-    };
+/// Parses a line starting with a C `ctime` timestamp.
+///
+/// Example: `Tue Nov 21 00:30:05 2017 message`
+pub fn parse_c_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = C_LOG_RE.captures(bytes)?;
 
-    let month = get_month(&caps[1]).unwrap();
-    let day: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
-    let year: i32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let month = get_month(&caps[2]).unwrap();
+    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let year: i32 = str::from_utf8(&caps[7]).unwrap().parse().unwrap();
 
     log_entry_from_local_time(
-        offset,
+        ctx,
         year,
         month,
         day,
         h,
         m,
         s,
-        caps.get(7).map(|x| x.as_bytes()).unwrap(),
+        caps.get(8).map(|x| x.as_bytes()).unwrap(),
     )
+    .map(|entry| entry.with_spans(span(&caps, 1), span(&caps, 8)))
 }
 
-pub fn parse_short_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match SHORT_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
-    };
+/// Parses a syslog style line without a year.
+///
+/// Example: `Nov 20 21:56:01 host process[1]: message`
+pub fn parse_short_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SHORT_LOG_RE.captures(bytes)?;
 
-    let year = now().year();
-    let month = get_month(&caps[1]).unwrap();
-    let day: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let month = get_month(&caps[2]).unwrap();
+    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let nanos = caps.get(7).map_or(0, |x| {
+        let digits = x.as_bytes();
+        str::from_utf8(digits).unwrap().parse::<u32>().unwrap() * 10u32.pow(9 - digits.len() as u32)
+    });
+    let year = ctx.infer_year(month, day, h, m, s);
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(nanos.into());
 
-    log_entry_from_local_time(
-        offset,
-        year,
-        month,
-        day,
-        h,
-        m,
-        s,
-        caps.get(6).map(|x| x.as_bytes()).unwrap(),
-    )
+    ctx.local_entry(naive, caps.get(8).map(|x| x.as_bytes()).unwrap())
+        .map(|entry| entry.with_spans(span(&caps, 1), span(&caps, 8)))
 }
 
-pub fn parse_simple_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match SIMPLE_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
-    };
+/// Parses a line starting with a time of day only.
+///
+/// The time can be on a 12-hour clock with `AM` or `PM`.
+///
+/// Example: `22:07:10 message`
+pub fn parse_simple_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SIMPLE_LOG_RE.captures(bytes)?;
 
-    let h: u32 = str::from_utf8(&caps[1]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let h = to_24_hour(h, caps.get(5))?;
 
-    let (year, month, day) = today(offset);
+    let today = ctx.infer_date(h, m, s);
     log_entry_from_local_time(
-        offset,
-        year,
-        month,
-        day,
+        ctx,
+        today.year(),
+        today.month(),
+        today.day(),
         h,
         m,
         s,
-        caps.get(4).map(|x| x.as_bytes()).unwrap(),
+        caps.get(6).map(|x| x.as_bytes()).unwrap(),
     )
+    .map(|entry| entry.with_spans(span(&caps, 1), span(&caps, 6)))
 }
 
-pub fn parse_common_log_entry(bytes: &[u8], _offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match COMMON_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
-    };
+/// Parses a line starting with a date, time and offset.
+///
+/// Example: `2015-05-13 17:39:16 +0200: message`
+pub fn parse_common_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = COMMON_LOG_RE.captures(bytes)?;
 
-    let year: i32 = str::from_utf8(&caps[1]).unwrap().parse().unwrap();
-    let month: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let year: i32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
+    let month: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let day: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[7]).unwrap().parse().unwrap();
 
     let offset = FixedOffset::east_opt(
-        ((if &caps[7] == b"+" { 1i32 } else { -1i32 })
-            * str::from_utf8(&caps[8]).unwrap().parse::<i32>().unwrap()
-            * 60
-            + str::from_utf8(&caps[9]).unwrap().parse::<i32>().unwrap())
+        (if &caps[8] == b"+" { 1i32 } else { -1i32 })
+            * (str::from_utf8(&caps[9]).unwrap().parse::<i32>().unwrap() * 60
+                + str::from_utf8(&caps[10]).unwrap().parse::<i32>().unwrap())
             * 60,
     )?;
 
-    Some(LogEntry::from_fixed_time(
-        offset
-            .with_ymd_and_hms(year, month, day, h, m, s)
-            .single()?,
-        caps.get(10).map(|x| x.as_bytes()).unwrap(),
-    ))
+    Some(
+        LogEntry::from_fixed_time(
+            offset
+                .from_local_datetime(&naive_datetime(year, month, day, h, m, s)?)
+                .single()?,
+            caps.get(11).map(|x| x.as_bytes()).unwrap(),
+        )
+        .with_spans(span(&caps, 1), span(&caps, 11)),
+    )
 }
 
-pub fn parse_common_alt_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match COMMON_ALT_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
+/// Parses a line starting with an RFC 3339 or ISO 8601 timestamp.
+///
+/// The separator may be `T` or a space, fractional seconds and the `Z` or
+/// offset suffix are optional.  Timestamps without a suffix are in local
+/// time.
+///
+/// Example: `2018-10-29T16:56:37.123Z message`
+pub fn parse_rfc3339_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = RFC3339_LOG_RE.captures(bytes)?;
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let (year, month, day) = (num(2) as i32, num(3), num(4));
+    let (h, m, s) = (num(5), num(6), num(7));
+    let nanos = caps.get(8).map_or(0, |x| {
+        let digits = x.as_bytes();
+        num(8) * 10u32.pow(9 - digits.len() as u32)
+    });
+    let message = caps.get(13).map(|x| x.as_bytes()).unwrap();
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(nanos.into());
+
+    let entry = if caps.get(9).is_some() {
+        LogEntry::from_utc_time(Utc.from_utc_datetime(&naive), message)
+    } else if let Some(sign) = caps.get(10) {
+        let secs = (num(11) * 60 + num(12)) as i32 * 60;
+        let offset = FixedOffset::east_opt(if sign.as_bytes() == b"-" { -secs } else { secs })?;
+        LogEntry::from_fixed_time(offset.from_local_datetime(&naive).single()?, message)
+    } else {
+        ctx.local_entry(naive, message)?
     };
+    Some(entry.with_spans(span(&caps, 1), span(&caps, 13)))
+}
 
-    let month = get_month(&caps[1]).unwrap();
-    let day: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
-    let year: i32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+/// Parses a line starting with a `ctime` like timestamp with an optional
+/// weekday, a padded day or fractional seconds.
+///
+/// Example: `Mon Oct  5 11:40:10 2015 message`
+pub fn parse_common_alt_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = COMMON_ALT_LOG_RE.captures(bytes)?;
+
+    let month = get_month(&caps[2]).unwrap();
+    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let year: i32 = str::from_utf8(&caps[7]).unwrap().parse().unwrap();
 
     log_entry_from_local_time(
-        offset,
+        ctx,
         year,
         month,
         day,
         h,
         m,
         s,
-        caps.get(7).map(|x| x.as_bytes()).unwrap(),
+        caps.get(8).map(|x| x.as_bytes()).unwrap(),
     )
+    .map(|entry| entry.with_spans(span(&caps, 1), span(&caps, 8)))
 }
 
-pub fn parse_common_alt2_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match COMMON_ALT2_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
-    };
+/// Parses a line starting with a `Mon DD, YYYY` date.
+///
+/// Example: `Jan 03, 2016 22:29:55 message`
+pub fn parse_common_alt2_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = COMMON_ALT2_LOG_RE.captures(bytes)?;
 
-    let month = get_month(&caps[1]).unwrap();
-    let day: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let year: i32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let month = get_month(&caps[2]).unwrap();
+    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let year: i32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[7]).unwrap().parse().unwrap();
+    let h = to_24_hour(h, caps.get(8))?;
 
     log_entry_from_local_time(
-        offset,
+        ctx,
         year,
         month,
         day,
         h,
         m,
         s,
-        caps.get(7).map(|x| x.as_bytes()).unwrap(),
+        caps.get(9).map(|x| x.as_bytes()).unwrap(),
     )
+    .map(|entry| entry.with_spans(span(&caps, 1), span(&caps, 9)))
 }
 
-pub fn parse_ue4_log_entry(bytes: &[u8], _offset: Option<FixedOffset>) -> Option<LogEntry> {
-    let caps = match UE4_LOG_RE.captures(bytes) {
-        Some(caps) => caps,
-        None => return None,
+/// Parses a line starting with a `MM/DD/YYYY` or `DD/MM/YYYY` date, as
+/// written by many Windows applications.
+///
+/// The order of day and month is configured with
+/// [`ParserBuilder::date_order`].  The time can be on a 12-hour clock with
+/// `AM` or `PM`.
+///
+/// Example: `03/17/2021 4:05:06 PM message`
+pub fn parse_slash_date_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SLASH_DATE_LOG_RE.captures(bytes)?;
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let (month, day) = ctx.date_order.resolve(num(2), num(3));
+    let h = to_24_hour(num(5), caps.get(9))?;
+    log_entry_from_numeric_date(ctx, &caps, (num(4) as i32, month, day), h, 10)
+}
+
+/// Parses a line starting with a `DD.MM.YYYY` date, as written with
+/// European locales.
+///
+/// Example: `17.03.2021 16:05:06 message`
+pub fn parse_dot_date_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = DOT_DATE_LOG_RE.captures(bytes)?;
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    log_entry_from_numeric_date(ctx, &caps, (num(4) as i32, num(3), num(2)), num(5), 9)
+}
+
+/// Creates an entry for the numeric date formats.
+///
+/// The time is expected in groups 6 to 8 and the message in `message`.
+fn log_entry_from_numeric_date<'a>(
+    ctx: &Context,
+    caps: &Captures<'a>,
+    (year, month, day): (i32, u32, u32),
+    h: u32,
+    message: usize,
+) -> Option<LogEntry<'a>> {
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+    let nanos = caps.get(8).map_or(0, |x| {
+        let digits = x.as_bytes();
+        num(8) * 10u32.pow(9 - digits.len() as u32)
+    });
+    let naive =
+        naive_datetime(year, month, day, h, num(6), num(7))? + Duration::nanoseconds(nanos.into());
+    Some(
+        ctx.local_entry(naive, caps.get(message).unwrap().as_bytes())?
+            .with_spans(span(caps, 1), span(caps, message)),
+    )
+}
+
+/// Parses a line starting with a compact all-digit date and time.
+///
+/// To not mistake numeric ids for timestamps, the year has to be between
+/// 1970 and 2100 and the timestamp has to be followed by whitespace.
+///
+/// Example: `20181029 165637 message` or `20181029-16:56:37 message`
+pub fn parse_compact_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = COMPACT_LOG_RE.captures(bytes)?;
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let year = num(2) as i32;
+    if !(1970..=2100).contains(&year) {
+        return None;
+    }
+    let (h, m, s) = if caps.get(5).is_some() {
+        (num(5), num(6), num(7))
+    } else {
+        (num(8), num(9), num(10))
     };
+    let nanos = caps.get(11).map_or(0, |x| {
+        let digits = x.as_bytes();
+        num(11) * 10u32.pow(9 - digits.len() as u32)
+    });
+    let naive =
+        naive_datetime(year, num(3), num(4), h, m, s)? + Duration::nanoseconds(nanos.into());
+    Some(
+        ctx.local_entry(naive, caps.get(12).unwrap().as_bytes())?
+            .with_spans(span(&caps, 1), span(&caps, 12)),
+    )
+}
 
-    let year: i32 = str::from_utf8(&caps[1]).unwrap().parse().unwrap();
-    let month: u32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
-    let day: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
-    let h: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
-    let m: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
-    let s: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+/// Parses an Unreal Engine 4 or 5 log line.
+///
+/// The category and the verbosity at the start of the message are exposed
+/// as the `category` and `verbosity` fields and set the level (`Display`
+/// and `Log` are info, `Verbose` and `VeryVerbose` debug and trace).  The
+/// message is kept as is.  The frame counter is the `frame` field.
+///
+/// Example: `[2018.10.29-16.56.37:542][  0]LogInit: Display: message`
+pub fn parse_ue4_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = UE4_LOG_RE.captures(bytes)?;
 
-    Some(LogEntry::from_utc_time(
-        Utc.with_ymd_and_hms(year, month, day, h, m, s).single()?,
-        caps.get(7).map(|x| x.as_bytes()).unwrap(),
-    ))
+    let year: i32 = str::from_utf8(&caps[2]).unwrap().parse().unwrap();
+    let month: u32 = str::from_utf8(&caps[3]).unwrap().parse().unwrap();
+    let day: u32 = str::from_utf8(&caps[4]).unwrap().parse().unwrap();
+    let h: u32 = str::from_utf8(&caps[5]).unwrap().parse().unwrap();
+    let m: u32 = str::from_utf8(&caps[6]).unwrap().parse().unwrap();
+    let s: u32 = str::from_utf8(&caps[7]).unwrap().parse().unwrap();
+    let nanos = caps.get(8).map_or(0, |x| {
+        let digits = x.as_bytes();
+        str::from_utf8(digits).unwrap().parse::<u32>().unwrap() * 10u32.pow(9 - digits.len() as u32)
+    });
+    // the frame counter, category and verbosity only match ASCII
+    let text = |idx| caps.get(idx).map(|x| str::from_utf8(x.as_bytes()).unwrap());
+
+    let mut entry = LogEntry::from_utc_time(
+        Utc.from_utc_datetime(
+            &(naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(nanos.into())),
+        ),
+        caps.get(10).map(|x| x.as_bytes()).unwrap(),
+    )
+    .with_spans(span(&caps, 1), span(&caps, 10))
+    .with_field("frame", text(9).unwrap());
+    if let Some(category) = text(11) {
+        entry = entry.with_field("category", category);
+    }
+    if let Some(verbosity) = text(12) {
+        entry = entry.with_field("verbosity", verbosity);
+        let level = match verbosity {
+            "Display" | "Log" => "info",
+            "Verbose" => "debug",
+            "VeryVerbose" => "trace",
+            other => other,
+        };
+        entry = entry.with_level(Cow::Borrowed(level));
+    }
+    Some(entry)
+}
+
+/// Parses seconds with an optional fraction into a duration.
+fn parse_seconds(bytes: &[u8]) -> Option<StdDuration> {
+    let s = str::from_utf8(bytes).ok()?;
+    let (secs, frac) = s.split_once('.').unwrap_or((s, ""));
+    let nanos = format!("{:0<9}", frac.get(..9).unwrap_or(frac));
+    Some(StdDuration::new(secs.parse().ok()?, nanos.parse().ok()?))
+}
+
+/// Creates an entry for a timestamp relative to boot or process start.
+fn log_entry_from_relative_time<'a>(
+    ctx: &Context,
+    relative: StdDuration,
+    message: &'a [u8],
+) -> Option<LogEntry<'a>> {
+    let ts = ctx
+        .boot_time
+        .and_then(|boot_time| boot_time.checked_add_signed(Duration::from_std(relative).ok()?));
+    let mut entry = match ts {
+        Some(ts) => LogEntry::from_utc_time(ts, message),
+        None => LogEntry::from_message_only(message),
+    };
+    entry.set_relative_time(relative);
+    Some(entry)
 }
 
-pub fn parse_log_entry(bytes: &[u8], offset: Option<FixedOffset>) -> Option<LogEntry> {
-    macro_rules! attempt {
-        ($func:ident) => {
-            if let Some(rv) = $func(bytes, offset) {
-                return Some(rv);
-            }
-        };
+/// Parses a line starting with the seconds since boot, as written by
+/// the kernel ring buffer.
+///
+/// The prefixes written by `dmesg -r` (`<6>`) and `dmesg -x`
+/// (`kern  :info  : `) set the level and the `facility` field.  Without a
+/// boot time only the relative time is recorded, see
+/// [`ParserBuilder::boot_time`].
+///
+/// Example: `[   12.345678] message`
+pub fn parse_uptime_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = UPTIME_LOG_RE.captures(bytes)?;
+
+    let mut entry = log_entry_from_relative_time(
+        ctx,
+        parse_seconds(&caps[5])?,
+        caps.get(6).map(|x| x.as_bytes()).unwrap(),
+    )?
+    .with_spans(span(&caps, 4), span(&caps, 6));
+    if let Some(priority) = caps.get(1) {
+        let priority: u32 = str::from_utf8(priority.as_bytes()).ok()?.parse().ok()?;
+        entry = entry
+            .with_level(Cow::Borrowed(syslog::severity_name(priority % 8)))
+            .with_field("facility", (priority / 8).to_string());
+    } else if let (Some(facility), Some(level)) = (caps.get(2), caps.get(3)) {
+        // both only match ASCII letters
+        entry = entry
+            .with_level(Cow::Borrowed(str::from_utf8(level.as_bytes()).unwrap()))
+            .with_field("facility", str::from_utf8(facility.as_bytes()).unwrap());
     }
+    Some(entry)
+}
+
+/// Parses a line starting with the time since the previous line.
+///
+/// Example: `+0.5s message`
+pub fn parse_delta_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = DELTA_LOG_RE.captures(bytes)?;
 
-    attempt!(parse_c_log_entry);
-    attempt!(parse_short_log_entry);
-    attempt!(parse_simple_log_entry);
-    attempt!(parse_common_log_entry);
-    attempt!(parse_common_alt_log_entry);
-    attempt!(parse_common_alt2_log_entry);
-    attempt!(parse_ue4_log_entry);
+    let value = parse_seconds(&caps[2])?;
+    let delta = match &caps[3] {
+        b"s" => value,
+        b"ms" => value / 1_000,
+        b"ns" => value / 1_000_000_000,
+        _ => value / 1_000_000,
+    };
+    let (ts, elapsed) = ctx.advance(delta);
+    let message = caps.get(4).map(|x| x.as_bytes()).unwrap();
+    let mut entry = match ts {
+        Some(ts) => LogEntry::from_utc_time(ts, message),
+        None => LogEntry::from_message_only(message),
+    };
+    entry.set_relative_time(elapsed);
+    Some(entry.with_spans(span(&caps, 1), span(&caps, 4)))
+}
 
-    None
+pub fn parse_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let mut entry = parse_builtin(bytes, ctx).or_else(|| DEFAULT_EPOCH.parse(bytes, ctx))?;
+    entry.detect_precision(bytes);
+    Some(entry)
 }
 
 #[cfg(test)]
 use insta::assert_debug_snapshot;
 
+/// A parser builder with the clock fixed at the start of 2017.
+#[cfg(test)]
+pub(crate) fn test_builder() -> ParserBuilder {
+    Parser::builder().clock(FixedClock::new(
+        Utc.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap(),
+    ))
+}
+
+/// A context for the parser of [`test_builder`].
+#[cfg(test)]
+pub(crate) fn test_ctx() -> Context {
+    test_builder().build().context()
+}
+
 #[test]
 fn test_parse_c_log_entry() {
     assert_debug_snapshot!(
-        parse_c_log_entry(b"Tue Nov 21 00:30:05 2017 More stuff here", None),
+        parse_c_log_entry(b"Tue Nov 21 00:30:05 2017 More stuff here", &test_ctx()),
         @r###"
         Some(
             LogEntry {
@@ -431,7 +1590,7 @@ fn test_parse_short_log_entry() {
     assert_debug_snapshot!(
         parse_short_log_entry(
             b"Nov 20 21:56:01 herzog com.apple.xpc.launchd[1] (com.apple.preference.displays.MirrorDisplays): Service only ran for 0 seconds. Pushing respawn out by 10 seconds.",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
@@ -453,14 +1612,14 @@ fn test_parse_short_log_entry_extra() {
     assert_debug_snapshot!(
         parse_short_log_entry(
             b"Mon Nov 20 00:31:19.005 <kernel> en0: Received EAPOL packet (length = 161)",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
             LogEntry {
                 timestamp: Some(
                     Local(
-                        2017-11-20T00:31:19+01:00,
+                        2017-11-20T00:31:19.005+01:00,
                     ),
                 ),
                 message: "<kernel> en0: Received EAPOL packet (length = 161)",
@@ -475,7 +1634,7 @@ fn test_parse_simple_log_entry() {
     assert_debug_snapshot!(
         parse_simple_log_entry(
             b"22:07:10 server  | detected binary path: /Users/mitsuhiko/.virtualenvs/sentry/bin/uwsgi",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
@@ -497,7 +1656,7 @@ fn test_parse_common_log_entry() {
     assert_debug_snapshot!(
         parse_common_log_entry(
             b"2015-05-13 17:39:16 +0200: Repaired 'Library/Printers/Canon/IJScanner/Resources/Parameters/CNQ9601'",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
@@ -514,12 +1673,73 @@ fn test_parse_common_log_entry() {
     );
 }
 
+#[test]
+fn test_parse_rfc3339_log_entry() {
+    assert_debug_snapshot!(
+        [
+            &b"2018-10-29T16:56:37.123Z message"[..],
+            &b"2021-06-01T12:00:00+02:00 msg"[..],
+            &b"2021-06-01T12:00:00.123456789-0530 go"[..],
+            &b"[2021-06-01 12:00:00,500] local"[..],
+            &b"2021-06-01T12:00 no seconds"[..],
+        ]
+        .iter()
+        .map(|line| parse_rfc3339_log_entry(line, &test_ctx()))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            LogEntry {
+                timestamp: Some(
+                    Utc(
+                        2018-10-29T16:56:37.123Z,
+                    ),
+                ),
+                message: "message",
+            },
+        ),
+        Some(
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2021-06-01T12:00:00+02:00,
+                    ),
+                ),
+                message: "msg",
+            },
+        ),
+        Some(
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2021-06-01T12:00:00.123456789-05:30,
+                    ),
+                ),
+                message: "go",
+            },
+        ),
+        Some(
+            LogEntry {
+                timestamp: Some(
+                    Local(
+                        2021-06-01T12:00:00.500+02:00,
+                    ),
+                ),
+                message: "local",
+            },
+        ),
+        None,
+    ]
+    "###
+    );
+}
+
 #[test]
 fn test_parse_common_alt_log_entry() {
     assert_debug_snapshot!(
         parse_common_alt_log_entry(
             b"Mon Oct  5 11:40:10 2015	[INFO] PDApp.ExternalGateway - NativePlatformHandler destructed",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
@@ -541,7 +1761,7 @@ fn test_parse_common_alt2_log_entry() {
     assert_debug_snapshot!(
         parse_common_alt2_log_entry(
             b"Jan 03, 2016 22:29:55 [0x70000073b000] DEBUG - Responding HTTP/1.1 200",
-            None
+            &test_ctx()
         ),
         @r###"
         Some(
@@ -561,7 +1781,7 @@ fn test_parse_common_alt2_log_entry() {
 #[test]
 fn test_parse_webserver_log() {
     assert_debug_snapshot!(
-        parse_common_alt_log_entry(b"[Sun Feb 25 06:11:12.043123448 2018] [:notice] [pid 1:tid 2] process manager initialized (pid 1)", None),
+        parse_common_alt_log_entry(b"[Sun Feb 25 06:11:12.043123448 2018] [:notice] [pid 1:tid 2] process manager initialized (pid 1)", &test_ctx()),
         @r###"
         Some(
             LogEntry {
@@ -581,7 +1801,30 @@ fn test_parse_webserver_log() {
 fn test_parse_invalid_time() {
     // same as test_parse_c_log_entry, except for invalid timestamp
     assert_debug_snapshot!(
-        parse_c_log_entry(b"Tue Nov 21 99:99:99 2017 More stuff here", None),
+        parse_c_log_entry(b"Tue Nov 21 99:99:99 2017 More stuff here", &test_ctx()),
+        @"None"
+    );
+}
+
+#[test]
+fn test_parse_leap_second() {
+    assert_debug_snapshot!(
+        parse_common_log_entry(b"2016-12-31 23:59:60 +0000: leap second inserted", &test_ctx()),
+        @r###"
+    Some(
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2016-12-31T23:59:60+00:00,
+                ),
+            ),
+            message: "leap second inserted",
+        },
+    )
+    "###
+    );
+    assert_debug_snapshot!(
+        parse_c_log_entry(b"Sat Dec 31 23:59:61 2016 not a leap second", &test_ctx()),
         @"None"
     );
 }
@@ -589,13 +1832,13 @@ fn test_parse_invalid_time() {
 #[test]
 fn test_parse_ue4_log() {
     assert_debug_snapshot!(
-        parse_ue4_log_entry(b"[2018.10.29-16.56.37:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]", None),
+        parse_ue4_log_entry(b"[2018.10.29-16.56.37:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]", &test_ctx()),
         @r###"
         Some(
             LogEntry {
                 timestamp: Some(
                     Utc(
-                        2018-10-29T16:56:37Z,
+                        2018-10-29T16:56:37.542Z,
                     ),
                 ),
                 message: "LogInit: Selected Device Profile: [WindowsNoEditor]",
@@ -604,13 +1847,13 @@ fn test_parse_ue4_log() {
         "###
     );
     assert_debug_snapshot!(
-        parse_ue4_log_entry(b"[2022.09.14-11.13.24:829][316]LogShaderCompilers: Display: ================================================", None),
+        parse_ue4_log_entry(b"[2022.09.14-11.13.24:829][316]LogShaderCompilers: Display: ================================================", &test_ctx()),
         @r###"
         Some(
             LogEntry {
                 timestamp: Some(
                     Utc(
-                        2022-09-14T11:13:24Z,
+                        2022-09-14T11:13:24.829Z,
                     ),
                 ),
                 message: "LogShaderCompilers: Display: ================================================",
@@ -620,12 +1863,651 @@ fn test_parse_ue4_log() {
     );
 }
 
+#[test]
+fn test_parse_ue4_log_fields() {
+    let parse = |line| {
+        let entry = parse_ue4_log_entry(line, &test_ctx())?;
+        Some((
+            entry.timestamp().cloned(),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"[2018.10.29-16.56.37:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]"),
+            parse(b"[2022.09.14-11.13.24:829][316]LogShaderCompilers: Display: ================================================"),
+            parse(b"[2022.09.14-11.13.25:001][317]LogNet: Warning: Connection timed out"),
+            parse(b"[2022.09.14-11.13.26:000][318]Unstructured text"),
+        ],
+        @r###"
+    [
+        Some(
+            (
+                Some(
+                    Utc(
+                        2018-10-29T16:56:37.542Z,
+                    ),
+                ),
+                None,
+                "LogInit: Selected Device Profile: [WindowsNoEditor]",
+                [
+                    "frame=0",
+                    "category=LogInit",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2022-09-14T11:13:24.829Z,
+                    ),
+                ),
+                Some(
+                    Info,
+                ),
+                "LogShaderCompilers: Display: ================================================",
+                [
+                    "frame=316",
+                    "category=LogShaderCompilers",
+                    "verbosity=Display",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2022-09-14T11:13:25.001Z,
+                    ),
+                ),
+                Some(
+                    Warning,
+                ),
+                "LogNet: Warning: Connection timed out",
+                [
+                    "frame=317",
+                    "category=LogNet",
+                    "verbosity=Warning",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Utc(
+                        2022-09-14T11:13:26Z,
+                    ),
+                ),
+                None,
+                "Unstructured text",
+                [
+                    "frame=318",
+                ],
+            ),
+        ),
+    ]
+    "###
+    );
+}
+
 #[test]
 fn test_parse_ue4_log_fail() {
     assert_debug_snapshot!(
-        parse_ue4_log_entry(b"[2022.13.29-16.63.27:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]", None),
+        parse_ue4_log_entry(b"[2022.13.29-16.63.27:542][  0]LogInit: Selected Device Profile: [WindowsNoEditor]", &test_ctx()),
         @r###"
         None
         "###
     );
 }
+
+#[test]
+fn test_parse_ue4_log_fraction() {
+    let parse = |line| Some(parse_ue4_log_entry(line, &test_ctx())?.timestamp().cloned());
+    assert_debug_snapshot!(
+        [
+            parse(b"[2018.10.29-16.56.37:5][  0]LogInit: short"),
+            parse(b"[2018.10.29-16.56.37:54][  0]LogInit: short"),
+            parse(b"[2018.10.29-16.56.37:542123][  0]LogInit: long"),
+        ],
+        @r###"
+    [
+        Some(
+            Some(
+                Utc(
+                    2018-10-29T16:56:37.500Z,
+                ),
+            ),
+        ),
+        Some(
+            Some(
+                Utc(
+                    2018-10-29T16:56:37.540Z,
+                ),
+            ),
+        ),
+        Some(
+            Some(
+                Utc(
+                    2018-10-29T16:56:37.542123Z,
+                ),
+            ),
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_timestamp_guard() {
+    let guarded = |action| {
+        let entry = test_builder()
+            .timestamp_guard(Duration::days(365), Duration::days(1), action)
+            .build()
+            .parse(b"Tue Nov 21 00:30:05 2017 More stuff here");
+        (entry.utc_timestamp(), entry.has_implausible_timestamp())
+    };
+    assert_debug_snapshot!(guarded(ImplausibleAction::Flag), @r###"
+    (
+        Some(
+            2017-11-20T23:30:05Z,
+        ),
+        true,
+    )
+    "###);
+    assert_debug_snapshot!(guarded(ImplausibleAction::Clamp), @r###"
+    (
+        Some(
+            2017-01-02T00:00:00Z,
+        ),
+        true,
+    )
+    "###);
+    assert_debug_snapshot!(guarded(ImplausibleAction::Discard), @r###"
+    (
+        None,
+        true,
+    )
+    "###);
+}
+
+#[test]
+fn test_max_line_length() {
+    let parser = Parser::builder().max_line_length(Some(24)).build();
+    let entry = parser.parse("12:00:00 Zürich".as_bytes());
+    assert_eq!(entry.message(), "Zürich");
+    assert!(!entry.is_truncated());
+    let entry = parser.parse("12:00:00 Zürich Zürich Zürich".as_bytes());
+    assert_eq!(entry.message(), "Zürich Zürich");
+    assert!(entry.is_truncated());
+    let entry = parser.parse("12:00:00 Zürich xxxxxZürich".as_bytes());
+    assert_eq!(entry.message(), "Zürich xxxxxZ");
+}
+
+#[test]
+fn test_parse_uptime_log_entry() {
+    let line = b"[   12.345678] usb 1-1: new high-speed USB device number 2 using xhci_hcd";
+    let entry = parse_uptime_log_entry(line, &test_ctx()).unwrap();
+    assert_debug_snapshot!((entry.relative_time(), entry.utc_timestamp()), @r###"
+    (
+        Some(
+            12.345678s,
+        ),
+        None,
+    )
+    "###);
+
+    let boot_time = Utc.with_ymd_and_hms(2021, 6, 1, 12, 0, 0).unwrap();
+    let entry = parse_uptime_log_entry(line, &test_ctx().boot_time(Some(boot_time))).unwrap();
+    assert_debug_snapshot!(entry, @r###"
+    LogEntry {
+        timestamp: Some(
+            Utc(
+                2021-06-01T12:00:12.345678Z,
+            ),
+        ),
+        message: "usb 1-1: new high-speed USB device number 2 using xhci_hcd",
+    }
+    "###);
+
+    // too far after the boot time to be anchored
+    let line = b"[99999999999999.000000] x";
+    let entry = parse_uptime_log_entry(line, &test_ctx().boot_time(Some(boot_time))).unwrap();
+    assert_eq!(entry.utc_timestamp(), None);
+    assert_eq!(
+        entry.relative_time(),
+        Some(StdDuration::from_secs(99_999_999_999_999))
+    );
+
+    let parse = |line| {
+        let entry = parse_uptime_log_entry(line, &test_ctx()).unwrap();
+        (
+            entry.relative_time(),
+            entry.level(),
+            entry.field("facility").map(|x| x.to_string()),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"<6>[    1.234567] usb 1-1: new high-speed USB device"),
+            parse(b"kern  :err   : [    2.000000] EXT4-fs error"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                1.234567s,
+            ),
+            Some(
+                Info,
+            ),
+            Some(
+                "0",
+            ),
+            "usb 1-1: new high-speed USB device",
+        ),
+        (
+            Some(
+                2s,
+            ),
+            Some(
+                Error,
+            ),
+            Some(
+                "kern",
+            ),
+            "EXT4-fs error",
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_naive_timezone() {
+    let parse = |timezone| {
+        Parser::builder()
+            .naive_timezone(timezone)
+            .build()
+            .parse(b"Tue Nov 21 00:30:05 2017 More stuff here")
+    };
+    assert_debug_snapshot!(parse(NaiveTimezone::Utc), @r###"
+    LogEntry {
+        timestamp: Some(
+            Utc(
+                2017-11-21T00:30:05Z,
+            ),
+        ),
+        message: "More stuff here",
+    }
+    "###);
+    assert_debug_snapshot!(
+        parse(NaiveTimezone::Fixed(FixedOffset::west_opt(5 * 3600).unwrap())),
+        @r###"
+    LogEntry {
+        timestamp: Some(
+            Fixed(
+                2017-11-21T00:30:05-05:00,
+            ),
+        ),
+        message: "More stuff here",
+    }
+    "###
+    );
+}
+
+#[cfg(feature = "chrono-tz")]
+#[test]
+fn test_naive_timezone_named() {
+    let parse = |bytes| {
+        Parser::builder()
+            .naive_timezone(NaiveTimezone::Named(chrono_tz::America::New_York))
+            .build()
+            .parse(bytes)
+            .utc_timestamp()
+    };
+    assert_debug_snapshot!(parse(b"Tue Jan 13 17:39:16 2015 winter"), @r###"
+    Some(
+        2015-01-13T22:39:16Z,
+    )
+    "###);
+    assert_debug_snapshot!(parse(b"Wed May 13 17:39:16 2015 summer"), @r###"
+    Some(
+        2015-05-13T21:39:16Z,
+    )
+    "###);
+}
+
+#[test]
+fn test_try_parse() {
+    let parser = Parser::new();
+    assert_debug_snapshot!(
+        [
+            &b"2015-05-13 17:39:16 +0200: ok"[..],
+            &b"no timestamp here"[..],
+            &b"2015-05-13 17:39:16 +0200: caf\xe9"[..],
+        ]
+        .iter()
+        .map(|line| parser.try_parse(line).map(|x| x.into_owned()).map_err(|err| err.to_string()))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Ok(
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2015-05-13T17:39:16+02:00,
+                    ),
+                ),
+                message: "ok",
+            },
+        ),
+        Err(
+            "no timestamp found",
+        ),
+        Err(
+            "line is not valid UTF-8",
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_enable_formats() {
+    let parser = test_builder()
+        .enable_formats(vec![FormatKind::Common, FormatKind::Epoch])
+        .build();
+    assert_debug_snapshot!(
+        [
+            &b"2015-05-13 17:39:16 +0200: common"[..],
+            &b"1494692356 epoch"[..],
+            &b"Tue Nov 21 00:30:05 2017 not enabled"[..],
+        ]
+        .iter()
+        .map(|line| parser.parse(line).format())
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            Common,
+        ),
+        None,
+        None,
+    ]
+    "###
+    );
+    let parser = Parser::builder().disable_format(FormatKind::C).build();
+    assert_debug_snapshot!(
+        parser.parse(b"Tue Nov 21 00:30:05 2017 c disabled").format(),
+        @r###"
+    Some(
+        Short,
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_add_custom() {
+    let mut fields = FieldMap::new();
+    fields.insert("hour".into(), "h".into());
+    fields.insert("minute".into(), "m".into());
+    let parser = test_builder()
+        .add_custom(
+            r"^svc\[(?P<h>[0-9]{2})(?P<m>[0-9]{2})\] (?P<msg>.*)$",
+            &fields,
+        )
+        .unwrap()
+        .build();
+    assert_debug_snapshot!(parser.parse(b"svc[1230] request handled"), @r###"
+    LogEntry {
+        timestamp: Some(
+            Local(
+                2017-01-01T12:30:00+01:00,
+            ),
+        ),
+        message: "request handled",
+    }
+    "###);
+    assert_debug_snapshot!(
+        Parser::builder()
+            .add_custom(r"^(?P<msg>.*)$", &fields)
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        @r###"
+    Err(
+        "format ^(?P<msg>.*)$: no capture group named h",
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_syslog_headers() {
+    let parser = test_builder().syslog_headers(true).build();
+    let entry = parser.parse(b"Nov 20 21:56:01 herzog com.apple.xpc.launchd[1]: Service exited");
+    assert_debug_snapshot!(
+        (
+            entry.hostname(),
+            entry.app_name(),
+            entry.process_id(),
+            entry.message(),
+            entry.format(),
+        ),
+        @r###"
+    (
+        Some(
+            "herzog",
+        ),
+        Some(
+            "com.apple.xpc.launchd",
+        ),
+        Some(
+            "1",
+        ),
+        "Service exited",
+        Some(
+            Rfc3164,
+        ),
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_parse_12_hour_clock() {
+    let parse = |line| {
+        let entry = Parser::builder()
+            .clock(FixedClock::new(
+                Utc.with_ymd_and_hms(2021, 3, 18, 0, 0, 0).unwrap(),
+            ))
+            .build()
+            .parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local()),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"Jan 03, 2016 10:29:55 PM message"),
+            parse(b"03/17/2021 4:05:06 PM message"),
+            parse(b"3/7/2021 12:05:06.250 AM message"),
+            parse(b"03/17/2021 16:05:06 message"),
+            parse(b"12:30:00 PM message"),
+            parse(b"13:30:00 PM message"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                CommonAlt2,
+            ),
+            Some(
+                2016-01-03T22:29:55,
+            ),
+            "message",
+        ),
+        (
+            Some(
+                SlashDate,
+            ),
+            Some(
+                2021-03-17T16:05:06,
+            ),
+            "message",
+        ),
+        (
+            Some(
+                SlashDate,
+            ),
+            Some(
+                2021-03-07T00:05:06.250,
+            ),
+            "message",
+        ),
+        (
+            Some(
+                SlashDate,
+            ),
+            Some(
+                2021-03-17T16:05:06,
+            ),
+            "message",
+        ),
+        (
+            Some(
+                Simple,
+            ),
+            Some(
+                2021-03-18T12:30:00,
+            ),
+            "message",
+        ),
+        (
+            None,
+            None,
+            "13:30:00 PM message",
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_date_order() {
+    let parse = |order, line| {
+        Parser::builder()
+            .date_order(order)
+            .build()
+            .parse(line)
+            .local_timestamp()
+            .map(|x| x.naive_local().to_string())
+    };
+    assert_debug_snapshot!(
+        [
+            parse(DateOrder::Auto, &b"03/04/2021 12:00:00 message"[..]),
+            parse(DateOrder::Auto, b"17/03/2021 12:00:00 message"),
+            parse(DateOrder::DayFirst, b"03/04/2021 12:00:00 message"),
+            parse(DateOrder::MonthFirst, b"17/03/2021 12:00:00 message"),
+            parse(DateOrder::MonthFirst, b"03.04.2021 12:00:00,5 message"),
+        ],
+        @r###"
+    [
+        Some(
+            "2021-03-04 12:00:00",
+        ),
+        Some(
+            "2021-03-17 12:00:00",
+        ),
+        Some(
+            "2021-04-03 12:00:00",
+        ),
+        None,
+        Some(
+            "2021-04-03 12:00:00.500",
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_parse_compact_log_entry() {
+    let parse = |line| {
+        let entry = Parser::new().parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"20181029 165637 message"[..]),
+            parse(b"20181029-16:56:37.250 message"),
+            parse(b"20181029165637 message"),
+            parse(b"[20181029T165637] message"),
+            parse(b"12345678 901234 order id"),
+            parse(b"20181029165637123 message"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Compact,
+            ),
+            Some(
+                "2018-10-29 16:56:37",
+            ),
+            "message",
+        ),
+        (
+            Some(
+                Compact,
+            ),
+            Some(
+                "2018-10-29 16:56:37.250",
+            ),
+            "message",
+        ),
+        (
+            Some(
+                Compact,
+            ),
+            Some(
+                "2018-10-29 16:56:37",
+            ),
+            "message",
+        ),
+        (
+            Some(
+                Compact,
+            ),
+            Some(
+                "2018-10-29 16:56:37",
+            ),
+            "message",
+        ),
+        (
+            None,
+            None,
+            "12345678 901234 order id",
+        ),
+        (
+            None,
+            None,
+            "20181029165637123 message",
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,292 @@
+//! Property based tests that render random timestamps through the
+//! canonical layout of each format and check that parsing recovers them.
+
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use proptest::prelude::*;
+use regex::bytes::{Captures, Regex};
+
+use crate::parser::{get_month, Parser};
+use crate::scan::{find_c, find_clf, find_iso, ScannedTimestamp, ScannedZone};
+use crate::types::NaiveTimezone;
+
+lazy_static! {
+    // the regexes the hand written timestamp scanners replaced
+    static ref ISO_TS_RE: Regex = Regex::new(
+        r#"(?x)
+        (?-u:\b)
+            ([0-9]{4})-(0[1-9]|1[0-2])-(0[1-9]|[12][0-9]|3[01])
+            [T\x20]
+            ([0-9]{2}):([0-9]{2}):([0-9]{2})
+            (?:[.,]([0-9]{1,9})[0-9]*)?
+            (?:
+                (Z)
+                |
+                ([+-])([0-9]{2}):?([0-9]{2})
+            )?
+        (?-u:\b)
+    "#
+    )
+    .unwrap();
+    static ref CLF_TS_RE: Regex = Regex::new(
+        r#"(?x)
+        (?-u:\b)
+            ([0-9]{2})/(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)/([0-9]{4})
+            :
+            ([0-9]{2}):([0-9]{2}):([0-9]{2})
+            (?:\.([0-9]{1,9})[0-9]*)?
+            (?:
+                \x20
+                ([+-])([0-9]{2})([0-9]{2})
+            )?
+        (?-u:\b)
+    "#
+    )
+    .unwrap();
+    static ref C_TS_RE: Regex = Regex::new(
+        r#"(?x)
+        (?-u:\b)
+            (?:Mon|Tue|Wed|Thu|Fri|Sat|Sun)\x20
+            (Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)
+            \x20+
+            ([0-9]{1,2})
+            \x20
+            ([0-9]{2}):([0-9]{2}):([0-9]{2})
+            (?:\.([0-9]{1,9})[0-9]*)?
+            \x20
+            ([0-9]{4})
+        (?-u:\b)
+    "#
+    )
+    .unwrap();
+}
+
+fn num(caps: &Captures, idx: usize) -> u32 {
+    str::from_utf8(&caps[idx]).unwrap().parse().unwrap()
+}
+
+fn nanos(caps: &Captures, idx: usize) -> u32 {
+    caps.get(idx).map_or(0, |x| {
+        num(caps, idx) * 10u32.pow(9 - x.as_bytes().len() as u32)
+    })
+}
+
+fn offset(caps: &Captures, idx: usize) -> Option<ScannedZone> {
+    let sign = caps.get(idx)?;
+    let secs = ((num(caps, idx + 1) * 60 + num(caps, idx + 2)) * 60) as i32;
+    Some(ScannedZone::Offset(if sign.as_bytes() == b"-" {
+        -secs
+    } else {
+        secs
+    }))
+}
+
+fn reference_iso(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    let caps = ISO_TS_RE.captures(bytes)?;
+    Some(ScannedTimestamp {
+        span: caps.get(0).unwrap().range(),
+        date: (num(&caps, 1) as i32, num(&caps, 2), num(&caps, 3)),
+        time: (num(&caps, 4), num(&caps, 5), num(&caps, 6)),
+        nanos: nanos(&caps, 7),
+        zone: caps
+            .get(8)
+            .map(|_| ScannedZone::Utc)
+            .or_else(|| offset(&caps, 9)),
+    })
+}
+
+fn reference_clf(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    let caps = CLF_TS_RE.captures(bytes)?;
+    Some(ScannedTimestamp {
+        span: caps.get(0).unwrap().range(),
+        date: (
+            num(&caps, 3) as i32,
+            get_month(&caps[2]).unwrap(),
+            num(&caps, 1),
+        ),
+        time: (num(&caps, 4), num(&caps, 5), num(&caps, 6)),
+        nanos: nanos(&caps, 7),
+        zone: offset(&caps, 8),
+    })
+}
+
+fn reference_c(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    let caps = C_TS_RE.captures(bytes)?;
+    Some(ScannedTimestamp {
+        span: caps.get(0).unwrap().range(),
+        date: (
+            num(&caps, 7) as i32,
+            get_month(&caps[1]).unwrap(),
+            num(&caps, 2),
+        ),
+        time: (num(&caps, 3), num(&caps, 4), num(&caps, 5)),
+        nanos: nanos(&caps, 6),
+        zone: None,
+    })
+}
+
+/// Lines assembled from pieces of timestamps, so that they often almost
+/// contain one.
+fn timestamp_soup() -> impl Strategy<Value = Vec<u8>> {
+    let pieces: Vec<&[u8]> = vec![
+        b"2021",
+        b"-",
+        b"06",
+        b"01",
+        b"13",
+        b"00",
+        b"31",
+        b"T",
+        b" ",
+        b":",
+        b".",
+        b",",
+        b"123",
+        b"1234567891",
+        b"Z",
+        b"z",
+        b"+",
+        b"02",
+        b"0200",
+        b"x",
+        b"_",
+        b"/",
+        b"Jun",
+        b"jun",
+        b"[",
+        b"]",
+        b"5",
+        b"Tue",
+        b"tue",
+        b"Sun",
+        b"   ",
+        b"\xff",
+    ];
+    prop::collection::vec(prop::sample::select(pieces), 0..24).prop_map(|pieces| pieces.concat())
+}
+
+/// Valid timestamps with a few bytes overwritten, between two soups.
+fn damaged_timestamps() -> impl Strategy<Value = Vec<u8>> {
+    let templates: Vec<&[u8]> = vec![
+        b"2021-06-01T13:00:31.123+02:00",
+        b"2021-06-01 13:00:31,1234567891Z",
+        b"01/Jun/2021:13:00:31.5 +0200",
+        b"Tue Jun  1 13:00:31.5 2021",
+        b"Sun Jun 13 13:00:31 2021",
+    ];
+    let edits = prop::collection::vec(
+        (
+            any::<prop::sample::Index>(),
+            prop::sample::select(b" .:+-/0Z_a".to_vec()),
+        ),
+        0..3,
+    );
+    (
+        timestamp_soup(),
+        prop::sample::select(templates),
+        edits,
+        timestamp_soup(),
+    )
+        .prop_map(|(prefix, template, edits, suffix)| {
+            let mut ts = template.to_vec();
+            for (idx, byte) in edits {
+                ts[idx.index(template.len())] = byte;
+            }
+            [prefix, ts, suffix].concat()
+        })
+}
+
+/// Whole second timestamps between 1971 and 2099.
+fn timestamps() -> impl Strategy<Value = DateTime<Utc>> {
+    (31_536_000i64..4_070_908_800).prop_map(|secs| Utc.timestamp_opt(secs, 0).unwrap())
+}
+
+/// Offsets from -12:00 to +14:00 in quarter hours.
+fn offsets() -> impl Strategy<Value = FixedOffset> {
+    (-48i32..=56).prop_map(|quarters| FixedOffset::east_opt(quarters * 15 * 60).unwrap())
+}
+
+/// Parses a line as if it was written shortly before `ts`, in UTC.
+fn parse_near(ts: DateTime<Utc>, line: &str) -> Option<DateTime<Utc>> {
+    Parser::builder()
+        .naive_timezone(NaiveTimezone::Utc)
+        .reference(Some(ts + Duration::hours(1)))
+        .scan_line(true)
+        .build()
+        .parse(line.as_bytes())
+        .utc_timestamp()
+}
+
+/// Layouts without an offset, rendered in UTC.
+static NAIVE_LAYOUTS: &[(&str, &str)] = &[
+    ("c", "%a %b %-d %H:%M:%S %Y message"),
+    ("common_alt", "%a %b %-d %H:%M:%S %Y\t[INFO] message"),
+    ("common_alt2", "%b %d, %Y %H:%M:%S [0x1] DEBUG - message"),
+    ("short", "%b %d %H:%M:%S host message"),
+    ("ue4", "[%Y.%m.%d-%H.%M.%S:000][  0]LogInit: message"),
+    ("scan_iso", "request finished at %Y-%m-%dT%H:%M:%S"),
+    (
+        "scan_clf",
+        "1.2.3.4 - - [%d/%b/%Y:%H:%M:%S] \"GET / HTTP/1.1\"",
+    ),
+];
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2048))]
+
+    #[test]
+    fn differential_timestamp_scanners(line in prop_oneof![1 => timestamp_soup(), 3 => damaged_timestamps()]) {
+        prop_assert_eq!(find_iso(&line), reference_iso(&line), "{:?}", String::from_utf8_lossy(&line));
+        prop_assert_eq!(find_clf(&line), reference_clf(&line), "{:?}", String::from_utf8_lossy(&line));
+        prop_assert_eq!(find_c(&line), reference_c(&line), "{:?}", String::from_utf8_lossy(&line));
+    }
+}
+
+proptest! {
+    #[test]
+    fn roundtrip_naive_layouts(ts in timestamps()) {
+        for (name, layout) in NAIVE_LAYOUTS {
+            let line = ts.format(layout).to_string();
+            prop_assert_eq!(parse_near(ts, &line), Some(ts), "format {}: {}", name, line);
+        }
+    }
+
+    #[test]
+    fn roundtrip_common_with_offset(ts in timestamps(), offset in offsets()) {
+        let line = ts.with_timezone(&offset).format("%Y-%m-%d %H:%M:%S %z: message").to_string();
+        prop_assert_eq!(parse_near(ts, &line), Some(ts), "{}", line);
+    }
+
+    #[test]
+    fn differential_iso8601(
+        year in 1971i32..2100,
+        month in 1u32..=12,
+        day in 1u32..=31,
+        hour in 0u32..24,
+        minute in 0u32..60,
+        second in 0u32..60,
+        offset in offsets(),
+    ) {
+        let offset = offset.to_string();
+        let rfc3339 = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+            year, month, day, hour, minute, second, offset
+        );
+        let expected = DateTime::parse_from_rfc3339(&rfc3339).ok().map(|x| x.with_timezone(&Utc));
+        let line = format!("event at {}", rfc3339);
+        let reference = expected.unwrap_or_else(Utc::now);
+        prop_assert_eq!(parse_near(reference, &line), expected, "{}", line);
+
+        let common = format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}: message",
+            year, month, day, hour, minute, second, offset.replace(':', "")
+        );
+        let expected = DateTime::parse_from_str(&common, "%Y-%m-%d %H:%M:%S %z: message")
+            .ok()
+            .map(|x| x.with_timezone(&Utc));
+        prop_assert_eq!(parse_near(reference, &common), expected, "{}", common);
+    }
+}
@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref REDIS_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9]+):([XCSM])
+            \x20(
+                ([0-9]{2})\x20(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)\x20([0-9]{4})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{3})
+            )
+            \x20([.\-*\#])
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Redis server log.
+///
+/// The level glyph sets the level.  Redis' own debug and verbose levels
+/// (`.` and `-`) map to trace and debug, notice (`*`) to info and warning
+/// (`#`) to warning.  The process id and role (`M` for a master, `S` for a
+/// replica, `C` for a child process and `X` for a sentinel) are exposed as
+/// the `procid` and `role` fields.
+///
+/// Example: `1234:M 01 Jun 2021 12:00:00.123 * Ready to accept connections`
+pub fn parse_redis_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = REDIS_RE.captures(bytes)?;
+    // all groups except the message only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let naive = naive_datetime(
+        num(6) as i32,
+        get_month(&caps[5])?,
+        num(4),
+        num(7),
+        num(8),
+        num(9),
+    )? + Duration::milliseconds(num(10).into());
+    let level = match &caps[11] {
+        b"." => "trace",
+        b"-" => "debug",
+        b"*" => "notice",
+        _ => "warning",
+    };
+
+    let message = caps.get(12).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(3).unwrap().range(), message)
+            .with_level(Cow::Borrowed(level))
+            .with_field("procid", text(1))
+            .with_field("role", text(2)),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_redis_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"1234:M 01 Jun 2021 12:00:00.123 * Ready to accept connections"[..]),
+            parse(b"1234:M 01 Jun 2021 12:00:01.000 # WARNING overcommit_memory is set to 0!"),
+            parse(b"5678:C 01 Jun 2021 12:00:02.500 - Fork CoW for RDB: current 1 MB"),
+            parse(b"1234:S 01 Jun 2021 12:00:03.000 . Client closed connection"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Redis,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123",
+            ),
+            Some(
+                Info,
+            ),
+            "Ready to accept connections",
+            [
+                "procid=1234",
+                "role=M",
+            ],
+        ),
+        (
+            Some(
+                Redis,
+            ),
+            Some(
+                "2021-06-01 12:00:01",
+            ),
+            Some(
+                Warning,
+            ),
+            "WARNING overcommit_memory is set to 0!",
+            [
+                "procid=1234",
+                "role=M",
+            ],
+        ),
+        (
+            Some(
+                Redis,
+            ),
+            Some(
+                "2021-06-01 12:00:02.500",
+            ),
+            Some(
+                Debug,
+            ),
+            "Fork CoW for RDB: current 1 MB",
+            [
+                "procid=5678",
+                "role=C",
+            ],
+        ),
+        (
+            Some(
+                Redis,
+            ),
+            Some(
+                "2021-06-01 12:00:03",
+            ),
+            Some(
+                Trace,
+            ),
+            "Client closed connection",
+            [
+                "procid=1234",
+                "role=S",
+            ],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref RUBY_LOGGER_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            [DIWEFA],\x20\[
+            (
+                ([0-9]{4})-([0-9]{2})-([0-9]{2})
+                T([0-9]{2}):([0-9]{2}):([0-9]{2})
+                (?:\.([0-9]{1,9}))?
+            )
+            \x20\#([0-9]+)\]
+            \x20+([A-Z]+)
+            \x20--\x20(.*?):(?:\x20|$)
+            ((?:\[[^\]]*\]\x20)*)
+            (.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line written by Ruby's default `Logger` formatter.
+///
+/// The severity sets the level and the process id and program name are
+/// exposed as the `procid` and `progname` fields.  Tags added by Rails'
+/// tagged logging (such as the request id) are taken off the message and
+/// exposed as the `tags` field, separated by commas.
+///
+/// Example: `I, [2021-06-01T12:00:00.123456 #1234]  INFO -- main: message`
+pub fn parse_ruby_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = RUBY_LOGGER_RE.captures(bytes)?;
+    // the severity and the numbers only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let nanos = caps
+        .get(8)
+        .map_or(0, |x| num(8) * 10u32.pow(9 - x.as_bytes().len() as u32));
+    let naive = naive_datetime(num(2) as i32, num(3), num(4), num(5), num(6), num(7))?
+        + Duration::nanoseconds(nanos.into());
+
+    let message = caps.get(13).unwrap().range();
+    let mut entry = ctx
+        .local_entry(naive, &bytes[message.clone()])?
+        .with_spans(caps.get(1).unwrap().range(), message)
+        .with_level(Cow::Borrowed(text(10)))
+        .with_field("procid", text(9));
+    let progname = caps.get(11).unwrap().as_bytes();
+    if !progname.is_empty() {
+        entry = entry.with_field("progname", String::from_utf8_lossy(progname));
+    }
+    let tags = caps.get(12).unwrap().as_bytes();
+    if !tags.is_empty() {
+        let tags = String::from_utf8_lossy(&tags[1..tags.len() - 2]).replace("] [", ",");
+        entry = entry.with_field("tags", tags);
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_ruby_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"I, [2021-06-01T12:00:00.123456 #1234]  INFO -- main: message"[..]),
+            parse(b"E, [2021-06-01T12:00:01.5 #1234] ERROR -- : boom"),
+            parse(b"I, [2021-06-01T12:00:02.000000 #7]  INFO -- : [6c1e0e9b-1f2a-4e2b-9b5e-3d8f0a6b1c2d] [web] Started GET \"/\" for 127.0.0.1"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Ruby,
+            ),
+            Some(
+                "2021-06-01 12:00:00.123456",
+            ),
+            Some(
+                Info,
+            ),
+            "message",
+            [
+                "procid=1234",
+                "progname=main",
+            ],
+        ),
+        (
+            Some(
+                Ruby,
+            ),
+            Some(
+                "2021-06-01 12:00:01.500",
+            ),
+            Some(
+                Error,
+            ),
+            "boom",
+            [
+                "procid=1234",
+            ],
+        ),
+        (
+            Some(
+                Ruby,
+            ),
+            Some(
+                "2021-06-01 12:00:02",
+            ),
+            Some(
+                Info,
+            ),
+            "Started GET \"/\" for 127.0.0.1",
+            [
+                "procid=7",
+                "tags=6c1e0e9b-1f2a-4e2b-9b5e-3d8f0a6b1c2d,web",
+            ],
+        ),
+    ]
+    "###
+    );
+    assert_eq!(
+        LogEntry::parse(b"W, [2021-06-01T12:00:00.123456 #1]  WARN -- app: careful").format(),
+        Some(FormatKind::Ruby)
+    );
+}
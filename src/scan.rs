@@ -0,0 +1,441 @@
+use std::ops::Range;
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use crate::format::FormatKind;
+use crate::parser::{get_month, naive_datetime, Context};
+use crate::types::LogEntry;
+
+/// Checks that all bytes are ASCII digits, eight bytes at a time.
+fn is_digits(bytes: &[u8]) -> bool {
+    const ZEROS: u64 = u64::from_ne_bytes([b'0'; 8]);
+    const OVER_NINE: u64 = u64::from_ne_bytes([0x7f - b'9'; 8]);
+    const HIGH_BITS: u64 = u64::from_ne_bytes([0x80; 8]);
+
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let x = u64::from_ne_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7],
+        ]);
+        // a byte below `0` borrows into its high bit, a byte above `9`
+        // carries into it.  Borrows and carries between bytes only start at
+        // a byte that already failed.
+        if (x.wrapping_sub(ZEROS) | x.wrapping_add(OVER_NINE)) & HIGH_BITS != 0 {
+            return false;
+        }
+    }
+    chunks.remainder().iter().all(u8::is_ascii_digit)
+}
+
+fn is_word(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Reads a run of exactly `len` digits at `at`.
+fn digits_at(bytes: &[u8], at: usize, len: usize) -> Option<u32> {
+    let digits = bytes.get(at..at + len)?;
+    if !is_digits(digits) {
+        return None;
+    }
+    Some(
+        digits
+            .iter()
+            .fold(0, |acc, x| acc * 10 + u32::from(x - b'0')),
+    )
+}
+
+/// Checks that a match of word characters can end at `end`.
+fn ends_word(bytes: &[u8], end: usize) -> bool {
+    !matches!(bytes.get(end), Some(&x) if is_word(x))
+}
+
+/// The zone of a scanned timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScannedZone {
+    Utc,
+    Offset(i32),
+}
+
+/// The parts of a timestamp found in a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScannedTimestamp {
+    pub span: Range<usize>,
+    pub date: (i32, u32, u32),
+    pub time: (u32, u32, u32),
+    pub nanos: u32,
+    pub zone: Option<ScannedZone>,
+}
+
+/// Reads the digits after a `.` or `,` as nanoseconds and returns them with
+/// the end of the fraction.
+fn fraction_at(bytes: &[u8], at: usize, separators: &[u8]) -> Option<(u32, usize)> {
+    if !separators.contains(bytes.get(at)?) {
+        return None;
+    }
+    let digits = bytes[at + 1..]
+        .iter()
+        .take_while(|x| x.is_ascii_digit())
+        .count();
+    if digits == 0 {
+        return None;
+    }
+    let used = digits.min(9);
+    let value = digits_at(bytes, at + 1, used)?;
+    Some((value * 10u32.pow(9 - used as u32), at + 1 + digits))
+}
+
+/// Reads a `+HH:MM` or `+HHMM` offset, trying the colon form first.
+fn offsets_at(bytes: &[u8], at: usize, colon: bool) -> Vec<(i32, usize)> {
+    let sign = match bytes.get(at) {
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        _ => return Vec::new(),
+    };
+    let hours = match digits_at(bytes, at + 1, 2) {
+        Some(hours) => hours as i32,
+        None => return Vec::new(),
+    };
+    let mut offsets = Vec::new();
+    if colon && bytes.get(at + 3) == Some(&b':') {
+        if let Some(minutes) = digits_at(bytes, at + 4, 2) {
+            offsets.push((sign * (hours * 60 + minutes as i32) * 60, at + 6));
+        }
+    }
+    if let Some(minutes) = digits_at(bytes, at + 3, 2) {
+        offsets.push((sign * (hours * 60 + minutes as i32) * 60, at + 5));
+    }
+    offsets
+}
+
+/// Picks the longest way to end a timestamp at a word boundary.
+///
+/// The fraction and zone are optional, so like a regex this falls back to
+/// shorter matches when the longer ones run into a word character.
+fn finish_timestamp(
+    bytes: &[u8],
+    base: usize,
+    fraction: Option<(u32, usize)>,
+    zones: impl Fn(usize) -> Vec<(ScannedZone, usize)>,
+) -> Option<(u32, Option<ScannedZone>, usize)> {
+    for (nanos, end) in fraction.into_iter().chain(Some((0, base))) {
+        for (zone, end) in zones(end) {
+            if ends_word(bytes, end) {
+                return Some((nanos, Some(zone), end));
+            }
+        }
+        if ends_word(bytes, end) {
+            return Some((nanos, None, end));
+        }
+    }
+    None
+}
+
+/// Matches `YYYY-MM-DD[T ]HH:MM:SS[.fraction][Z|+HH:MM]` at `start`.
+fn iso_at(bytes: &[u8], start: usize) -> Option<ScannedTimestamp> {
+    if start > 0 && is_word(bytes[start - 1]) {
+        return None;
+    }
+    let year = digits_at(bytes, start, 4)? as i32;
+    let month = digits_at(bytes, start + 5, 2).filter(|x| (1..=12).contains(x))?;
+    let day = digits_at(bytes, start + 8, 2).filter(|x| (1..=31).contains(x))?;
+    if bytes.get(start + 4) != Some(&b'-')
+        || bytes.get(start + 7) != Some(&b'-')
+        || !matches!(bytes.get(start + 10), Some(b'T' | b' '))
+    {
+        return None;
+    }
+    let h = digits_at(bytes, start + 11, 2)?;
+    let m = digits_at(bytes, start + 14, 2)?;
+    let s = digits_at(bytes, start + 17, 2)?;
+    if bytes.get(start + 13) != Some(&b':') || bytes.get(start + 16) != Some(&b':') {
+        return None;
+    }
+
+    let base = start + 19;
+    let (nanos, zone, end) =
+        finish_timestamp(bytes, base, fraction_at(bytes, base, b".,"), |at| {
+            if bytes.get(at) == Some(&b'Z') {
+                return vec![(ScannedZone::Utc, at + 1)];
+            }
+            offsets_at(bytes, at, true)
+                .into_iter()
+                .map(|(secs, end)| (ScannedZone::Offset(secs), end))
+                .collect()
+        })?;
+    Some(ScannedTimestamp {
+        span: start..end,
+        date: (year, month, day),
+        time: (h, m, s),
+        nanos,
+        zone,
+    })
+}
+
+/// Matches `DD/Mon/YYYY:HH:MM:SS[.fraction][ +HHMM]` at `start`.
+fn clf_at(bytes: &[u8], start: usize) -> Option<ScannedTimestamp> {
+    if start > 0 && is_word(bytes[start - 1]) {
+        return None;
+    }
+    let day = digits_at(bytes, start, 2)?;
+    let month = get_month(bytes.get(start + 3..start + 6)?)?;
+    let year = digits_at(bytes, start + 7, 4)? as i32;
+    let h = digits_at(bytes, start + 12, 2)?;
+    let m = digits_at(bytes, start + 15, 2)?;
+    let s = digits_at(bytes, start + 18, 2)?;
+    for (at, sep) in [(2, b'/'), (6, b'/'), (11, b':'), (14, b':'), (17, b':')] {
+        if bytes[start + at] != sep {
+            return None;
+        }
+    }
+
+    let base = start + 20;
+    let (nanos, zone, end) = finish_timestamp(bytes, base, fraction_at(bytes, base, b"."), |at| {
+        if bytes.get(at) != Some(&b' ') {
+            return Vec::new();
+        }
+        offsets_at(bytes, at + 1, false)
+            .into_iter()
+            .map(|(secs, end)| (ScannedZone::Offset(secs), end))
+            .collect()
+    })?;
+    Some(ScannedTimestamp {
+        span: start..end,
+        date: (year, month, day),
+        time: (h, m, s),
+        nanos,
+        zone,
+    })
+}
+
+/// Matches `Www Mmm DD HH:MM:SS[.fraction] YYYY` around the colon at `colon`.
+///
+/// The day is padded with spaces, so the match is found by walking back from
+/// the time.
+fn c_at(bytes: &[u8], colon: usize) -> Option<ScannedTimestamp> {
+    let hour = colon.checked_sub(2)?;
+    let h = digits_at(bytes, hour, 2)?;
+    let m = digits_at(bytes, colon + 1, 2)?;
+    let s = digits_at(bytes, colon + 4, 2)?;
+    if bytes.get(colon + 3) != Some(&b':') || bytes.get(hour.checked_sub(1)?) != Some(&b' ') {
+        return None;
+    }
+    let day_start = match hour.checked_sub(3).and_then(|at| digits_at(bytes, at, 2)) {
+        Some(_) => hour - 3,
+        None => hour.checked_sub(2)?,
+    };
+    let day = digits_at(bytes, day_start, hour - 1 - day_start)?;
+    let spaces = bytes[..day_start]
+        .iter()
+        .rev()
+        .take_while(|&&x| x == b' ')
+        .count();
+    let month_end = day_start - spaces;
+    let start = month_end.checked_sub(7)?;
+    if spaces == 0
+        || bytes[start + 3] != b' '
+        || (start > 0 && is_word(bytes[start - 1]))
+        || !matches!(
+            &bytes[start..start + 3],
+            b"Mon" | b"Tue" | b"Wed" | b"Thu" | b"Fri" | b"Sat" | b"Sun"
+        )
+    {
+        return None;
+    }
+    let month = get_month(&bytes[start + 4..month_end])?;
+
+    let (nanos, at) = fraction_at(bytes, colon + 6, b".").unwrap_or((0, colon + 6));
+    let year = digits_at(bytes, at + 1, 4)? as i32;
+    if bytes[at] != b' ' || !ends_word(bytes, at + 5) {
+        return None;
+    }
+    Some(ScannedTimestamp {
+        span: start..at + 5,
+        date: (year, month, day),
+        time: (h, m, s),
+        nanos,
+        zone: None,
+    })
+}
+
+/// Finds the first ISO 8601 timestamp in a line.
+///
+/// Candidates are the dashes found with memchr that are preceded by four
+/// digits, so most lines are rejected without looking at every byte.
+pub(crate) fn find_iso(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    memchr::memchr_iter(b'-', bytes)
+        .filter(|&pos| pos >= 4 && is_digits(&bytes[pos - 4..pos]))
+        .find_map(|pos| iso_at(bytes, pos - 4))
+}
+
+/// Finds the first Common Log Format timestamp in a line.
+pub(crate) fn find_clf(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    memchr::memchr_iter(b'/', bytes)
+        .filter(|&pos| pos >= 2 && is_digits(&bytes[pos - 2..pos]))
+        .find_map(|pos| clf_at(bytes, pos - 2))
+}
+
+/// Finds the first C `ctime` timestamp in a line.
+pub(crate) fn find_c(bytes: &[u8]) -> Option<ScannedTimestamp> {
+    memchr::memchr_iter(b':', bytes).find_map(|pos| c_at(bytes, pos))
+}
+
+/// Creates an entry from a scanned timestamp with an optional offset.
+fn log_entry_from_parts<'a>(
+    ctx: &Context,
+    offset: Option<FixedOffset>,
+    naive: NaiveDateTime,
+) -> Option<LogEntry<'a>> {
+    match offset {
+        Some(offset) => Some(LogEntry::from_fixed_time(
+            offset.from_local_datetime(&naive).single()?,
+            b"",
+        )),
+        None => ctx.local_entry(naive, b""),
+    }
+}
+
+fn scanned_entry<'a>(ts: ScannedTimestamp, ctx: &Context) -> Option<(LogEntry<'a>, Range<usize>)> {
+    let offset = match ts.zone {
+        Some(ScannedZone::Utc) => Some(FixedOffset::east_opt(0)?),
+        Some(ScannedZone::Offset(secs)) => Some(FixedOffset::east_opt(secs)?),
+        None => None,
+    };
+    let (year, month, day) = ts.date;
+    let (h, m, s) = ts.time;
+    let naive = naive_datetime(year, month, day, h, m, s)? + Duration::nanoseconds(ts.nanos.into());
+    let entry = log_entry_from_parts(ctx, offset, naive)?;
+    Some((entry, ts.span))
+}
+
+/// Widens a timestamp span to include enclosing brackets.
+fn widen_to_brackets(bytes: &[u8], span: Range<usize>) -> Range<usize> {
+    if span.start > 0 && span.end < bytes.len() {
+        match (bytes[span.start - 1], bytes[span.end]) {
+            (b'[', b']') | (b'(', b')') | (b'<', b'>') => return span.start - 1..span.end + 1,
+            _ => {}
+        }
+    }
+    span
+}
+
+/// Searches the whole line for a timestamp.
+///
+/// This is used for producers which put the timestamp at the end or in the
+/// middle of the line.  The timestamp (and brackets around it) is removed
+/// from the message.  The more specific timestamp formats are preferred.
+pub fn scan_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let (entry, ts_span) = find_iso(bytes)
+        .and_then(|ts| scanned_entry(ts, ctx))
+        .or_else(|| find_clf(bytes).and_then(|ts| scanned_entry(ts, ctx)))
+        .or_else(|| find_c(bytes).and_then(|ts| scanned_entry(ts, ctx)))?;
+
+    let cut = widen_to_brackets(bytes, ts_span.clone());
+    let start = cut.end
+        + bytes[cut.end..]
+            .iter()
+            .take_while(|x| x.is_ascii_whitespace())
+            .count();
+    let before = String::from_utf8_lossy(&bytes[..cut.start]);
+    let after = String::from_utf8_lossy(&bytes[start..]);
+    let before = before.trim_end();
+    let message = if before.is_empty() || after.is_empty() {
+        format!("{}{}", before, after)
+    } else {
+        format!("{} {}", before, after)
+    };
+
+    let entry = entry
+        .with_owned_message(message)
+        .with_format(FormatKind::Scanned);
+    Some(if cut.start == 0 {
+        // the timestamp was at the start, so the message is contiguous
+        entry.with_spans(ts_span, start..bytes.len())
+    } else {
+        entry.with_timestamp_span(ts_span)
+    })
+}
+
+#[cfg(test)]
+use crate::parser::test_ctx;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_scan_trailing_timestamp() {
+    assert_debug_snapshot!(
+        scan_log_entry(b"[pid: 1|app: 0|req: 1/1] 127.0.0.1 () {34 vars in 1 bytes} [Tue Jun  1 12:00:00 2021] GET / => generated 2 bytes", &test_ctx()),
+        @r###"
+    Some(
+        LogEntry {
+            timestamp: Some(
+                Local(
+                    2021-06-01T12:00:00+02:00,
+                ),
+            ),
+            message: "[pid: 1|app: 0|req: 1/1] 127.0.0.1 () {34 vars in 1 bytes} GET / => generated 2 bytes",
+        },
+    )
+    "###
+    );
+    assert_debug_snapshot!(
+        scan_log_entry(b"bucket-owner my-bucket [01/Jun/2021:12:00:00 +0000] 192.0.2.3 REST.GET.OBJECT", &test_ctx()),
+        @r###"
+    Some(
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2021-06-01T12:00:00+00:00,
+                ),
+            ),
+            message: "bucket-owner my-bucket 192.0.2.3 REST.GET.OBJECT",
+        },
+    )
+    "###
+    );
+    assert_debug_snapshot!(
+        scan_log_entry(b"request finished in 12ms at 2021-06-01T12:00:00.123Z", &test_ctx()),
+        @r###"
+    Some(
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2021-06-01T12:00:00.123+00:00,
+                ),
+            ),
+            message: "request finished in 12ms at",
+        },
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_scan_invalid_utf8_after_timestamp() {
+    for line in [
+        &b"2021-06-01T12:00:00 \xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff"[..],
+        b"(2021-06-01T12:00:00)  \xff\xffmessage",
+    ] {
+        let entry = scan_log_entry(line, &test_ctx()).unwrap();
+        let span = entry.message_span().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&line[span]).trim_start(),
+            entry.message()
+        );
+    }
+}
+
+#[test]
+fn test_is_digits() {
+    assert!(is_digits(b""));
+    assert!(is_digits(b"0123456789012345678"));
+    for len in 1..20 {
+        for pos in 0..len {
+            for bad in [b'/', b':', b' ', 0, 0x7f, 0x80, 0xff] {
+                let mut bytes = vec![b'9'; len];
+                bytes[pos] = bad;
+                assert!(!is_digits(&bytes), "{:?}", bytes);
+            }
+        }
+    }
+}
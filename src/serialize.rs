@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::format::FormatKind;
+use crate::level::Level;
+use crate::types::{LogEntry, Timestamp};
+
+/// Timestamps are written as RFC 3339 strings.
+///
+/// Local timestamps keep their offset but read back as fixed offset
+/// timestamps, `Z` reads back as UTC.
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = match *self {
+            Timestamp::Utc(ts) => ts.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            Timestamp::Local(ts) => ts.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            Timestamp::Fixed(ts) => ts.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        };
+        serializer.serialize_str(&value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let value = Cow::<str>::deserialize(deserializer)?;
+        let ts = DateTime::parse_from_rfc3339(&value).map_err(de::Error::custom)?;
+        Ok(if value.ends_with('Z') {
+            Timestamp::Utc(ts.with_timezone(&Utc))
+        } else {
+            Timestamp::Fixed(ts)
+        })
+    }
+}
+
+/// The serialized form of an entry.
+#[derive(Serialize, Deserialize)]
+struct SerializedEntry<'a> {
+    timestamp: Option<Timestamp>,
+    message: Cow<'a, str>,
+    level: Option<Level>,
+    format: Option<FormatKind>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    fields: BTreeMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+impl<'a> Serialize for LogEntry<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedEntry {
+            timestamp: self.timestamp().cloned(),
+            message: Cow::Borrowed(self.message()),
+            level: self.level(),
+            format: self.format(),
+            fields: self
+                .fields()
+                .map(|(key, value)| (Cow::Borrowed(key), Cow::Borrowed(value)))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LogEntry<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<LogEntry<'static>, D::Error> {
+        let entry = SerializedEntry::deserialize(deserializer)?;
+        let mut rv = LogEntry::from_parts(entry.timestamp, entry.message.into_owned());
+        if let Some(level) = entry.level {
+            rv = rv.with_level(Cow::Borrowed(level.name()));
+        }
+        if let Some(format) = entry.format {
+            rv = rv.with_format(format);
+        }
+        for (key, value) in entry.fields {
+            rv = rv.with_field(Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned()));
+        }
+        Ok(rv)
+    }
+}
+
+#[cfg(test)]
+use insta::assert_snapshot;
+
+#[test]
+fn test_serialize_entry() {
+    let entry = LogEntry::parse(b"2015-05-13 17:39:16 +0200: [WARN] disk almost full");
+    let json = serde_json::to_string(&entry).unwrap();
+    assert_snapshot!(json, @r#"{"timestamp":"2015-05-13T17:39:16+02:00","message":"[WARN] disk almost full","level":"warning","format":"common"}"#);
+    let back: LogEntry<'static> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+    let entry = LogEntry::from_utc_time(Utc.with_ymd_and_hms(2021, 6, 1, 12, 0, 0).unwrap(), b"hi");
+    let json = serde_json::to_string(&entry).unwrap();
+    assert_snapshot!(json, @r#"{"timestamp":"2021-06-01T12:00:00Z","message":"hi","level":null,"format":null}"#);
+    let back: LogEntry<'static> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.timestamp(), entry.timestamp());
+}
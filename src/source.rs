@@ -0,0 +1,90 @@
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref SOURCE_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            L\x20(
+                (0[1-9]|1[0-2])/(0[1-9]|[12][0-9]|3[01])/([0-9]{4})
+                \x20-\x20
+                ([0-9]{2}):([0-9]{2}):([0-9]{2})
+            ):
+            (?:\x20|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Valve Source engine dedicated server log.
+///
+/// The dates are always month first.
+///
+/// Example: `L 06/01/2021 - 12:00:00: "Player<2><STEAM_1:0:123><CT>" entered the game`
+pub fn parse_source_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SOURCE_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let naive = naive_datetime(num(4) as i32, num(2), num(3), num(5), num(6), num(7))?;
+    let message = caps.get(8).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(1).unwrap().range(), message),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_source_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"L 06/01/2021 - 12:00:00: Log file started (file \"logs/L0601000.log\") (game \"/srv/csgo\") (version \"7929\")"[..]),
+            parse(b"L 06/01/2021 - 12:00:01: \"Player<2><STEAM_1:0:123><CT>\" entered the game"),
+            parse(b"L 13/01/2021 - 12:00:02: not a month"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Source,
+            ),
+            Some(
+                "2021-06-01 12:00:00",
+            ),
+            "Log file started (file \"logs/L0601000.log\") (game \"/srv/csgo\") (version \"7929\")",
+        ),
+        (
+            Some(
+                Source,
+            ),
+            Some(
+                "2021-06-01 12:00:01",
+            ),
+            "\"Player<2><STEAM_1:0:123><CT>\" entered the game",
+        ),
+        (
+            None,
+            None,
+            "L 13/01/2021 - 12:00:02: not a month",
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,168 @@
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::epoch::EpochHeuristics;
+use crate::parser::Context;
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref SQUID_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9]+)
+            \x20+([^\x20]+)
+            \x20([A-Z_]+)/([0-9]{3})
+            \x20([0-9]+)
+            \x20([A-Z]+|-)
+            \x20([^\x20]+)
+            (?:
+                \x20([^\x20]+)
+                \x20([A-Z_]+)/([^\x20]+)
+                (?:\x20([^\x20]+))?
+            )?
+        "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Squid `access.log` in the native format.
+///
+/// The timestamp is read like an epoch timestamp with a fraction.  The
+/// message is the rest of the line.  The elapsed time in milliseconds,
+/// client address, result code, status, response size, request method,
+/// URL, user, hierarchy code, peer and content type are exposed as the
+/// `elapsed`, `client_ip`, `result_code`, `status`, `bytes`, `method`,
+/// `url`, `user`, `hierarchy`, `peer` and `content_type` fields.  Missing
+/// values (`-`) are left out.
+///
+/// Example: `1612345678.123    456 1.2.3.4 TCP_MISS/200 1234 GET http://example.com/ - HIER_DIRECT/93.184.216.34 text/html`
+pub fn parse_squid_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let mut entry = EpochHeuristics::default()
+        .bare_integers(false)
+        .parse(bytes, ctx)?;
+    let message_span = entry.message_span()?;
+    let caps = SQUID_RE.captures(&bytes[message_span])?;
+
+    let fields = [
+        ("elapsed", caps.get(1)),
+        ("client_ip", caps.get(2)),
+        ("result_code", caps.get(3)),
+        ("status", caps.get(4)),
+        ("bytes", caps.get(5)),
+        ("method", caps.get(6)),
+        ("url", caps.get(7)),
+        ("user", caps.get(8)),
+        ("hierarchy", caps.get(9)),
+        ("peer", caps.get(10)),
+        ("content_type", caps.get(11)),
+    ];
+    for (key, value) in fields.iter() {
+        let value = match value {
+            Some(value) if value.as_bytes() != b"-" => value,
+            _ => continue,
+        };
+        entry = entry.with_field(*key, String::from_utf8_lossy(value.as_bytes()));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_squid_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.utc_timestamp().map(|x| x.to_rfc3339()),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"1612345678.123    456 1.2.3.4 TCP_MISS/200 1234 GET http://example.com/ - HIER_DIRECT/93.184.216.34 text/html"[..]),
+            parse(b"1612345678.456      0 10.0.0.1 TCP_DENIED/403 3900 CONNECT example.org:443 alice HIER_NONE/- text/html"),
+            parse(b"1612345678.789 12 10.0.0.2 NONE_NONE/400 0 - error:invalid-request - HIER_NONE/- -"),
+            parse(b"1612345678.123 not a squid line"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Squid,
+            ),
+            Some(
+                "2021-02-03T09:47:58.123+00:00",
+            ),
+            "456 1.2.3.4 TCP_MISS/200 1234 GET http://example.com/ - HIER_DIRECT/93.184.216.34 text/html",
+            [
+                "elapsed=456",
+                "client_ip=1.2.3.4",
+                "result_code=TCP_MISS",
+                "status=200",
+                "bytes=1234",
+                "method=GET",
+                "url=http://example.com/",
+                "hierarchy=HIER_DIRECT",
+                "peer=93.184.216.34",
+                "content_type=text/html",
+            ],
+        ),
+        (
+            Some(
+                Squid,
+            ),
+            Some(
+                "2021-02-03T09:47:58.456+00:00",
+            ),
+            "0 10.0.0.1 TCP_DENIED/403 3900 CONNECT example.org:443 alice HIER_NONE/- text/html",
+            [
+                "elapsed=0",
+                "client_ip=10.0.0.1",
+                "result_code=TCP_DENIED",
+                "status=403",
+                "bytes=3900",
+                "method=CONNECT",
+                "url=example.org:443",
+                "user=alice",
+                "hierarchy=HIER_NONE",
+                "content_type=text/html",
+            ],
+        ),
+        (
+            Some(
+                Squid,
+            ),
+            Some(
+                "2021-02-03T09:47:58.789+00:00",
+            ),
+            "12 10.0.0.2 NONE_NONE/400 0 - error:invalid-request - HIER_NONE/- -",
+            [
+                "elapsed=12",
+                "client_ip=10.0.0.2",
+                "result_code=NONE_NONE",
+                "status=400",
+                "bytes=0",
+                "url=error:invalid-request",
+                "hierarchy=HIER_NONE",
+            ],
+        ),
+        (
+            Some(
+                Epoch,
+            ),
+            Some(
+                "2021-02-03T09:47:58.123+00:00",
+            ),
+            "not a squid line",
+            [],
+        ),
+    ]
+    "###
+    );
+}
@@ -0,0 +1,663 @@
+use std::io::{self, BufRead};
+use std::str;
+
+use chrono::Duration;
+use memchr::{memchr2, memchr3};
+
+use crate::cri;
+use crate::exception::TraceGrouper;
+use crate::header::parse_header;
+use crate::java;
+use crate::parser::{Context, Parser};
+use crate::types::{LogEntry, Timestamp};
+use crate::w3c;
+
+/// Splits a reader into lines.
+///
+/// Lines can be terminated by `\n`, `\r\n` or a lone `\r`, and the
+/// terminators can be mixed within the same input.  The last line does not
+/// need a terminator.
+pub(crate) struct LineReader<R> {
+    reader: R,
+    skip_lf: bool,
+    split_on_nul: bool,
+    position: u64,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R) -> LineReader<R> {
+        LineReader {
+            reader,
+            skip_lf: false,
+            split_on_nul: false,
+            position: 0,
+        }
+    }
+
+    /// Returns the number of bytes consumed from the reader so far.
+    ///
+    /// Between lines this is the offset of the next line, except that the
+    /// `\n` of a `\r\n` pair split across reads is not consumed yet.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt);
+        self.position += amt as u64;
+    }
+
+    /// Also treats runs of NUL bytes as line terminators.
+    ///
+    /// Unlike other terminators NUL runs never produce empty lines.
+    pub fn split_on_nul(mut self, yes: bool) -> LineReader<R> {
+        self.split_on_nul = yes;
+        self
+    }
+
+    /// Reads a line without its terminator into `buf` but keeps at most
+    /// `limit` bytes of it.
+    ///
+    /// Returns `false` once the reader is exhausted.
+    pub fn read_line(&mut self, buf: &mut Vec<u8>, limit: usize) -> io::Result<bool> {
+        let mut read_any = false;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if available.is_empty() {
+                return Ok(read_any);
+            }
+
+            // the \n of a \r\n pair might only show up after a refill
+            if self.skip_lf {
+                self.skip_lf = false;
+                if available[0] == b'\n' {
+                    self.consume(1);
+                    continue;
+                }
+            }
+
+            let terminator = if self.split_on_nul {
+                memchr3(b'\n', b'\r', 0, available)
+            } else {
+                memchr2(b'\n', b'\r', available)
+            };
+            match terminator {
+                Some(pos) if available[pos] == 0 => {
+                    let keep = pos.min(limit.saturating_sub(buf.len()));
+                    buf.extend_from_slice(&available[..keep]);
+                    let nul_run = available[pos..].iter().take_while(|&&b| b == 0).count();
+                    self.consume(pos + nul_run);
+                    if read_any || pos > 0 {
+                        return Ok(true);
+                    }
+                }
+                Some(pos) => {
+                    let keep = pos.min(limit.saturating_sub(buf.len()));
+                    buf.extend_from_slice(&available[..keep]);
+                    self.skip_lf = available[pos] == b'\r';
+                    self.consume(pos + 1);
+                    return Ok(true);
+                }
+                None => {
+                    read_any = true;
+                    let used = available.len();
+                    let keep = used.min(limit.saturating_sub(buf.len()));
+                    buf.extend_from_slice(&available[..keep]);
+                    self.consume(used);
+                }
+            }
+        }
+    }
+}
+
+/// Reads and parses all lines from a reader with the default parser.
+///
+/// Lines may end in `\n`, `\r\n` or `\r`.  This is a shortcut for
+/// `Parser::new().read_entries(reader)`.
+pub fn read_entries<R: BufRead>(reader: R) -> Entries<R> {
+    Parser::new().read_entries(reader)
+}
+
+/// An iterator over the entries read from a reader.
+///
+/// This is created by [`Parser::read_entries`] or [`read_entries`].
+pub struct Entries<R> {
+    parser: Parser,
+    ctx: Context,
+    lines: LineReader<R>,
+    buf: Vec<u8>,
+    grouper: Option<TraceGrouper>,
+    lookahead: Option<io::Result<LogEntry<'static>>>,
+    pending: Option<LogEntry<'static>>,
+    last_timestamp: Option<Timestamp>,
+}
+
+impl<R: BufRead> Entries<R> {
+    pub(crate) fn new(parser: Parser, reader: R) -> Entries<R> {
+        Entries {
+            ctx: parser.context().for_stream(),
+            lines: LineReader::new(reader).split_on_nul(parser.skips_binary()),
+            grouper: if parser.groups_exceptions() {
+                Some(TraceGrouper::default())
+            } else {
+                None
+            },
+            parser,
+            buf: Vec::new(),
+            lookahead: None,
+            pending: None,
+            last_timestamp: None,
+        }
+    }
+}
+
+/// Returns `true` for bytes that do not show up in text logs.
+///
+/// Tabs and escape sequences for terminal colors are common enough in logs
+/// to not count as binary.
+fn is_binary_byte(b: u8) -> bool {
+    (b < 0x20 && b != b'\t' && b != 0x1b) || b == 0x7f
+}
+
+/// Returns the offset after the last stretch of binary garbage in a line.
+///
+/// Garbage is anything that is not valid UTF-8 or contains control
+/// characters.
+fn find_resync_point(line: &[u8]) -> Option<usize> {
+    let mut rv = None;
+    let mut pos = 0;
+    while pos < line.len() {
+        let (valid, garbage) = match str::from_utf8(&line[pos..]) {
+            Ok(_) => (line.len() - pos, 0),
+            Err(err) => (
+                err.valid_up_to(),
+                err.error_len()
+                    .unwrap_or(line.len() - pos - err.valid_up_to()),
+            ),
+        };
+        if let Some(idx) = line[pos..pos + valid]
+            .iter()
+            .rposition(|&b| is_binary_byte(b))
+        {
+            rv = Some(pos + idx + 1);
+        }
+        pos += valid;
+        if garbage > 0 {
+            pos += garbage;
+            rv = Some(pos);
+        }
+    }
+    rv
+}
+
+impl<R: BufRead> Entries<R> {
+    fn read_line_entry(&mut self) -> Option<io::Result<LogEntry<'static>>> {
+        self.buf.clear();
+        // keep one extra byte so that overlong lines are detected as such
+        // by the parser.
+        let limit = self
+            .parser
+            .max_line_length()
+            .map_or(usize::MAX, |max| max.saturating_add(1));
+        loop {
+            match self.lines.read_line(&mut self.buf, limit) {
+                Ok(false) => return None,
+                Ok(true) => {}
+                Err(err) => return Some(Err(err)),
+            }
+            let mut line = &self.buf[..];
+            if self.parser.skips_binary() {
+                if let Some(pos) = find_resync_point(line) {
+                    line = &line[pos..];
+                    if line.is_empty() {
+                        self.buf.clear();
+                        continue;
+                    }
+                }
+            }
+            if let Some(header) = parse_header(line) {
+                self.ctx.apply_header(&header);
+            }
+            if let Some(columns) = w3c::parse_fields_directive(line) {
+                self.ctx.set_w3c_columns(columns);
+            }
+            return Some(Ok(self
+                .parser
+                .parse_with_context(line, &self.ctx)
+                .into_owned()));
+        }
+    }
+}
+
+impl<R: BufRead> Entries<R> {
+    /// Reads the next entry, joining partial CRI lines and the two lines of
+    /// `java.util.logging` records.
+    fn read_entry(&mut self) -> Option<io::Result<LogEntry<'static>>> {
+        let mut entry = match self.lookahead.take().or_else(|| self.read_line_entry())? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        while cri::is_partial(&entry) {
+            match self.read_line_entry() {
+                Some(Ok(next)) if cri::join_partial(&mut entry, &next) => {}
+                Some(next) => {
+                    self.lookahead = Some(next);
+                    break;
+                }
+                None => break,
+            }
+        }
+        if java::is_jul_header(&entry) {
+            if let Some(next) = self.read_line_entry() {
+                match next
+                    .as_ref()
+                    .ok()
+                    .and_then(|next| java::join_jul_record(&entry, next))
+                {
+                    Some(joined) => entry = joined,
+                    None => self.lookahead = Some(next),
+                }
+            }
+        }
+        Some(Ok(entry))
+    }
+
+    fn inherit_timestamp(&mut self, entry: &mut LogEntry<'static>) {
+        match entry.timestamp() {
+            Some(ts) => self.last_timestamp = Some(*ts),
+            None => {
+                if let Some(ref mut ts) = self.last_timestamp {
+                    if self.parser.bumps_inherited_timestamps() {
+                        *ts = ts.with_utc(ts.to_utc() + Duration::microseconds(1));
+                    }
+                    entry.inherit_timestamp(*ts);
+                }
+            }
+        }
+    }
+
+    fn next_grouped(&mut self) -> Option<io::Result<LogEntry<'static>>> {
+        loop {
+            let entry = match self.read_entry() {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Some(Err(err)),
+                None => return self.pending.take().map(Ok),
+            };
+            let merge = self.parser.merges_continuation_lines();
+            if self.grouper.is_none() && !merge {
+                return Some(Ok(entry));
+            }
+            if let Some(ref mut pending) = self.pending {
+                if entry.timestamp().is_none()
+                    && (merge
+                        || self
+                            .grouper
+                            .as_mut()
+                            .is_some_and(|grouper| grouper.continues(entry.message())))
+                {
+                    pending.append_line(entry.message());
+                    continue;
+                }
+            }
+            if let Some(ref mut grouper) = self.grouper {
+                grouper.start(entry.message());
+            }
+            if let Some(pending) = self.pending.replace(entry) {
+                return Some(Ok(pending));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Entries<R> {
+    type Item = io::Result<LogEntry<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = match self.next_grouped()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        if self.parser.inherits_timestamps() {
+            self.inherit_timestamp(&mut entry);
+        }
+        Some(Ok(entry))
+    }
+}
+
+#[cfg(test)]
+use crate::parser::test_builder;
+#[cfg(test)]
+use chrono::prelude::*;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_midnight_rollover() {
+    let input = &b"23:59:58 before midnight\n23:59:59 still before\n00:00:01 after midnight\n23:59:59 out of order\n00:00:02 next day"[..];
+    let timestamps: Vec<_> = test_builder()
+        .build()
+        .read_entries(input)
+        .map(|entry| entry.unwrap().local_timestamp().unwrap())
+        .collect();
+    assert_debug_snapshot!(timestamps, @r###"
+    [
+        2017-01-01T23:59:58+01:00,
+        2017-01-01T23:59:59+01:00,
+        2017-01-02T00:00:01+01:00,
+        2017-01-01T23:59:59+01:00,
+        2017-01-02T00:00:02+01:00,
+    ]
+    "###);
+}
+
+#[test]
+fn test_max_line_length() {
+    let input = format!("12:00:00 {}\r\n12:00:01 short\r\n", "x".repeat(100_000));
+    let entries: Vec<_> = Parser::builder()
+        .max_line_length(Some(20))
+        .build()
+        .read_entries(input.as_bytes())
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.message().to_string(), entry.is_truncated())
+        })
+        .collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        (
+            "xxxxxxxxxxx",
+            true,
+        ),
+        (
+            "short",
+            false,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_mixed_line_endings() {
+    let input = &b"one\r\ntwo\rthree\n\r\nfour\r\r\nfive"[..];
+    // a tiny buffer splits the \r\n pairs across refills
+    let reader = io::BufReader::with_capacity(4, input);
+    let messages: Vec<_> = Parser::new()
+        .read_entries(reader)
+        .map(|entry| entry.unwrap().message().to_string())
+        .collect();
+    assert_debug_snapshot!(messages, @r###"
+    [
+        "one",
+        "two",
+        "three",
+        "",
+        "four",
+        "",
+        "five",
+    ]
+    "###);
+}
+
+#[test]
+fn test_read_entries() {
+    let input = &b"2015-05-13 17:39:16 +0200: first\r\nsecond\r\n"[..];
+    let entries: Vec<_> = read_entries(input).map(|entry| entry.unwrap()).collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2015-05-13T17:39:16+02:00,
+                ),
+            ),
+            message: "first",
+        },
+        LogEntry {
+            timestamp: None,
+            message: "second",
+        },
+    ]
+    "###);
+}
+
+#[test]
+fn test_skip_binary() {
+    let input = &b"12:00:00 before crash\n12:00:01 trunc\0\0\0\0\0\0\xff\xfe\x01\x02garbage\x0312:00:02 after restart\n\xde\xad\xbe\xef\n\n12:00:03 done"[..];
+    let messages: Vec<_> = Parser::builder()
+        .skip_binary(true)
+        .build()
+        .read_entries(input)
+        .map(|entry| entry.unwrap().message().to_string())
+        .collect();
+    assert_debug_snapshot!(messages, @r###"
+    [
+        "before crash",
+        "trunc",
+        "after restart",
+        "",
+        "done",
+    ]
+    "###);
+}
+
+#[test]
+fn test_delta_timestamps() {
+    let input = &b"+0.5s starting\n2017-01-01 12:00:00 +0000: absolute\n+250ms first\n+1.5s second\n+1000us third"[..];
+    let entries: Vec<_> = Parser::builder()
+        .boot_time(Some(Utc.with_ymd_and_hms(2017, 1, 1, 11, 0, 0).unwrap()))
+        .build()
+        .read_entries(input)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.utc_timestamp().unwrap(), entry.relative_time())
+        })
+        .collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        (
+            2017-01-01T11:00:00.500Z,
+            Some(
+                500ms,
+            ),
+        ),
+        (
+            2017-01-01T12:00:00Z,
+            None,
+        ),
+        (
+            2017-01-01T12:00:00.250Z,
+            Some(
+                750ms,
+            ),
+        ),
+        (
+            2017-01-01T12:00:01.750Z,
+            Some(
+                2.25s,
+            ),
+        ),
+        (
+            2017-01-01T12:00:01.751Z,
+            Some(
+                2.251s,
+            ),
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_group_exceptions() {
+    let input = &b"12:00:00 request failed\nTraceback (most recent call last):\n  File \"app.py\", line 12, in handler\n    return 1 / 0\nZeroDivisionError: division by zero\n12:00:01 worker crashed\njava.lang.IllegalStateException: boom\n\tat com.example.Main.run(Main.java:12)\n\t... 3 more\nCaused by: java.io.IOException: closed\n\tat com.example.Io.read(Io.java:7)\n12:00:02 done\nplain continuation"[..];
+    let entries: Vec<_> = Parser::builder()
+        .group_exceptions(true)
+        .build()
+        .read_entries(input)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let trace = entry
+                .stack_trace()
+                .map(|trace| (trace.kind(), trace.frames().len()));
+            (entry.message().to_string(), trace)
+        })
+        .collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        (
+            "request failed\nTraceback (most recent call last):\n  File \"app.py\", line 12, in handler\n    return 1 / 0\nZeroDivisionError: division by zero",
+            Some(
+                (
+                    Python,
+                    1,
+                ),
+            ),
+        ),
+        (
+            "worker crashed\njava.lang.IllegalStateException: boom\n\tat com.example.Main.run(Main.java:12)\n\t... 3 more\nCaused by: java.io.IOException: closed\n\tat com.example.Io.read(Io.java:7)",
+            Some(
+                (
+                    Java,
+                    2,
+                ),
+            ),
+        ),
+        (
+            "done",
+            None,
+        ),
+        (
+            "plain continuation",
+            None,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_merge_continuation_lines() {
+    let input = &b"untimed preamble\n12:00:00 Exception in thread \"main\" java.lang.IllegalStateException: boom\n\tat com.example.App.main(App.java:12)\n12:00:01 request failed\n  status: 500\n  body: empty\n12:00:02 done"[..];
+    let messages: Vec<_> = test_builder()
+        .merge_continuation_lines(true)
+        .build()
+        .read_entries(input)
+        .map(|entry| entry.unwrap().message().to_string())
+        .collect();
+    assert_debug_snapshot!(messages, @r###"
+    [
+        "untimed preamble",
+        "Exception in thread \"main\" java.lang.IllegalStateException: boom\n\tat com.example.App.main(App.java:12)",
+        "request failed\n  status: 500\n  body: empty",
+        "done",
+    ]
+    "###);
+}
+
+#[test]
+fn test_inherit_timestamps() {
+    let input = &b"2017-01-01 12:00:00 +0000: starting\nplain stdout\nmore stdout\n2017-01-01 12:00:01 +0000: done"[..];
+    let entries: Vec<_> = Parser::builder()
+        .inherit_timestamps(true)
+        .bump_inherited_timestamps(true)
+        .build()
+        .read_entries(input)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (
+                entry.utc_timestamp().unwrap(),
+                entry.has_inherited_timestamp(),
+            )
+        })
+        .collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        (
+            2017-01-01T12:00:00Z,
+            false,
+        ),
+        (
+            2017-01-01T12:00:00.000001Z,
+            true,
+        ),
+        (
+            2017-01-01T12:00:00.000002Z,
+            true,
+        ),
+        (
+            2017-01-01T12:00:01Z,
+            false,
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_file_header() {
+    let input = &b"Log file open, 12/31/20 23:59:50\n[23:59:58] before midnight\n[00:00:02] after midnight\nDec 31 23:59:59 late line"[..];
+    let timestamps: Vec<_> = test_builder()
+        .build()
+        .read_entries(input)
+        .map(|entry| entry.unwrap().local_timestamp())
+        .collect();
+    assert_debug_snapshot!(timestamps, @r###"
+    [
+        None,
+        Some(
+            2020-12-31T23:59:58+01:00,
+        ),
+        Some(
+            2021-01-01T00:00:02+01:00,
+        ),
+        Some(
+            2020-12-31T23:59:59+01:00,
+        ),
+    ]
+    "###);
+
+    let input = &b"#Software: Microsoft Internet Information Services 10.0\n#Date: 2021-03-04 12:34:56\n12:35:00 request"[..];
+    let timestamps: Vec<_> = test_builder()
+        .build()
+        .read_entries(input)
+        .map(|entry| entry.unwrap().timestamp().cloned())
+        .collect();
+    assert_debug_snapshot!(timestamps, @r###"
+    [
+        None,
+        None,
+        Some(
+            Utc(
+                2021-03-04T12:35:00Z,
+            ),
+        ),
+    ]
+    "###);
+}
+
+#[test]
+fn test_oversized_delta() {
+    let input = &b"2017-01-01 12:00:00 +0000: start\n+9000000000000s boom"[..];
+    let entries: Vec<_> = Parser::new()
+        .read_entries(input)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (entry.utc_timestamp(), entry.message().to_string())
+        })
+        .collect();
+    assert_debug_snapshot!(entries, @r###"
+    [
+        (
+            Some(
+                2017-01-01T12:00:00Z,
+            ),
+            "start",
+        ),
+        (
+            None,
+            "boom",
+        ),
+    ]
+    "###);
+}
@@ -0,0 +1,499 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::prelude::*;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref RFC5424_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            <([0-9]{1,3})>1
+            \x20([^\x20]+)
+            \x20([^\x20]+)
+            \x20([^\x20]+)
+            \x20([^\x20]+)
+            \x20([^\x20]+)
+            \x20(
+                -
+                |
+                (?:\[[^\x20\]]+(?:\x20[^=\x20\]]+="(?:[^"\\]|\\.)*")*\])+
+            )
+            (?:\x20(.*))?
+        $
+    "#
+    )
+    .unwrap();
+    static ref PRI_RE: Regex = Regex::new(r#"^<([0-9]{1,3})>"#).unwrap();
+    static ref RFC3164_HEADER_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([^\x20:\[\]<>]+)
+            \x20
+            ([^\x20:\[\]]+)
+            (?:\[([0-9]+)\])?
+            :(?:\x20|$)
+        "#
+    )
+    .unwrap();
+    static ref SD_ELEMENT_RE: Regex =
+        Regex::new(r#"\[([^\x20\]]+)((?:\x20[^=\x20\]]+="(?:[^"\\]|\\.)*")*)\]"#).unwrap();
+    static ref SD_PARAM_RE: Regex = Regex::new(r#"([^=\x20\]]+)="((?:[^"\\]|\\.)*)""#).unwrap();
+}
+
+/// The keyword of a syslog severity as understood by `Level::from_name`.
+pub(crate) fn severity_name(severity: u32) -> &'static str {
+    match severity {
+        0 => "emerg",
+        1 => "alert",
+        2 => "crit",
+        3 => "err",
+        4 => "warning",
+        5 => "notice",
+        6 => "info",
+        _ => "debug",
+    }
+}
+
+fn text(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes)
+}
+
+/// Removes the backslash escapes of structured data parameter values.
+fn unescape_param(value: &[u8]) -> Cow<'_, str> {
+    if !value.contains(&b'\\') {
+        return text(value);
+    }
+    let mut rv = Vec::with_capacity(value.len());
+    let mut iter = value.iter();
+    while let Some(&b) = iter.next() {
+        match (b, iter.as_slice().first()) {
+            (b'\\', Some(&next)) if next == b'"' || next == b'\\' || next == b']' => {
+                rv.push(next);
+                iter.next();
+            }
+            _ => rv.push(b),
+        }
+    }
+    Cow::Owned(String::from_utf8_lossy(&rv).into_owned())
+}
+
+/// Parses an RFC 5424 syslog line.
+///
+/// The priority, hostname, app name, process id and message id are exposed
+/// as the `priority`, `facility`, `hostname`, `app_name`, `procid` and
+/// `msgid` fields, with nil values (`-`) left out.  Structured data
+/// parameters become fields named `<sd-id>.<param>`.  The severity sets the
+/// level of the entry.
+///
+/// Example: `<165>1 2003-10-11T22:14:15.003Z host app 1234 ID47 [sd@123 k="v"] msg`
+pub fn parse_rfc5424_log_entry<'a>(bytes: &'a [u8], _ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = RFC5424_RE.captures(bytes)?;
+    let (priority, _) = parse_priority(bytes)?;
+
+    let message = match caps.get(8) {
+        Some(message) => {
+            let bom = if message.as_bytes().starts_with(b"\xef\xbb\xbf") {
+                3
+            } else {
+                0
+            };
+            message.start() + bom..message.end()
+        }
+        None => bytes.len()..bytes.len(),
+    };
+    let ts = caps.get(2).unwrap();
+    let mut entry = if ts.as_bytes() == b"-" {
+        LogEntry::from_message_only(&bytes[message.clone()]).with_message_span(message)
+    } else {
+        let parsed = DateTime::parse_from_rfc3339(str::from_utf8(ts.as_bytes()).ok()?).ok()?;
+        let entry = if ts.as_bytes().ends_with(b"Z") {
+            LogEntry::from_utc_time(parsed.with_timezone(&Utc), &bytes[message.clone()])
+        } else {
+            LogEntry::from_fixed_time(parsed, &bytes[message.clone()])
+        };
+        entry.with_spans(ts.range(), message)
+    };
+
+    entry = entry
+        .with_level(Cow::Borrowed(severity_name(priority % 8)))
+        .with_field("priority", priority.to_string())
+        .with_field("facility", (priority / 8).to_string());
+    for (idx, key) in [
+        (3, "hostname"),
+        (4, "app_name"),
+        (5, "procid"),
+        (6, "msgid"),
+    ] {
+        let value = caps.get(idx).unwrap().as_bytes();
+        if value != b"-" {
+            entry = entry.with_field(key, text(value));
+        }
+    }
+    for element in SD_ELEMENT_RE.captures_iter(caps.get(7).unwrap().as_bytes()) {
+        let id = text(&element[1]);
+        for param in SD_PARAM_RE.captures_iter(element.get(2).unwrap().as_bytes()) {
+            let key = format!("{}.{}", id, text(&param[1]));
+            entry = entry.with_field(key, unescape_param(param.get(2).unwrap().as_bytes()));
+        }
+    }
+    Some(entry)
+}
+
+/// Parses the priority prefix of a syslog line.
+fn parse_priority(bytes: &[u8]) -> Option<(u32, usize)> {
+    let caps = PRI_RE.captures(bytes)?;
+    let priority = str::from_utf8(&caps[1]).ok()?.parse().ok()?;
+    if priority > 191 {
+        return None;
+    }
+    Some((priority, caps.get(0).unwrap().end()))
+}
+
+/// Splits the hostname and the tag off the message of an entry parsed from
+/// `bytes[start..]`.
+fn split_header<'a>(bytes: &'a [u8], start: usize, entry: LogEntry<'a>) -> Option<LogEntry<'a>> {
+    let rest = &bytes[start..];
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = RFC3164_HEADER_RE.captures(&rest[message_span.clone()])?;
+
+    let message = start + message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span.start + start..ts_span.end + start, message)
+        .with_field("hostname", text(caps.get(1).unwrap().as_bytes()))
+        .with_field("app_name", text(caps.get(2).unwrap().as_bytes()));
+    Some(match caps.get(3) {
+        Some(pid) => entry.with_field("procid", text(pid.as_bytes())),
+        None => entry,
+    })
+}
+
+/// Parses an RFC 3164 (BSD) syslog line.
+///
+/// Unlike the short format this splits the hostname and the tag off the
+/// message and exposes them as the `hostname`, `app_name` and `procid`
+/// fields.  The `<PRI>` prefix is optional, if present it sets the
+/// `priority` and `facility` fields and the level.
+///
+/// Example: `<34>Oct 11 22:14:15 herzog com.apple.xpc.launchd[1]: message`
+pub fn parse_rfc3164_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let (priority, start) = match bytes.first() {
+        Some(b'<') => {
+            let (priority, start) = parse_priority(bytes)?;
+            (Some(priority), start)
+        }
+        _ => (None, 0),
+    };
+    let entry = parser::parse_short_log_entry(&bytes[start..], ctx)?;
+    let entry = split_header(bytes, start, entry)?;
+    Some(match priority {
+        Some(priority) => entry
+            .with_level(Cow::Borrowed(severity_name(priority % 8)))
+            .with_field("priority", priority.to_string())
+            .with_field("facility", (priority / 8).to_string()),
+        None => entry,
+    })
+}
+
+/// Parses a line printed by `journalctl -o short-precise` or `-o short-iso`.
+///
+/// The hostname and the unit or tag are split off the message like for
+/// [`parse_rfc3164_log_entry`].  To not take over other lines with similar
+/// timestamps, `short-precise` lines need the fraction of the timestamp
+/// and `short-iso` lines a process id after the tag (or the `kernel` tag).
+///
+/// Example: `Jun 01 12:00:00.123456 myhost sshd[123]: Accepted publickey`
+pub fn parse_journal_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let iso = bytes.first()?.is_ascii_digit();
+    let entry = if iso {
+        parser::parse_rfc3339_log_entry(bytes, ctx)?
+    } else {
+        parser::parse_short_log_entry(bytes, ctx)?
+    };
+    journal_from_entry(bytes, iso, entry)
+}
+
+/// Reads the journal header after a short or rfc3339 timestamp.
+fn journal_from_entry<'a>(bytes: &'a [u8], iso: bool, entry: LogEntry<'a>) -> Option<LogEntry<'a>> {
+    let ts = &bytes[entry.timestamp_span()?];
+    if !iso && !ts.contains(&b'.') {
+        return None;
+    }
+    let entry = split_header(bytes, 0, entry)?;
+    if iso && entry.field("procid").is_none() && entry.field("app_name") != Some("kernel") {
+        return None;
+    }
+    Some(entry)
+}
+
+/// Parses a short log line, leaving journal lines to the journal format.
+pub(crate) fn parse_short_unless_journal<'a>(
+    bytes: &'a [u8],
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_short_log_entry(bytes, ctx)?;
+    match journal_from_entry(bytes, false, entry.clone()) {
+        Some(_) => None,
+        None => Some(entry),
+    }
+}
+
+/// Parses an rfc3339 log line, leaving journal lines to the journal format.
+pub(crate) fn parse_rfc3339_unless_journal<'a>(
+    bytes: &'a [u8],
+    ctx: &Context,
+) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_rfc3339_log_entry(bytes, ctx)?;
+    match journal_from_entry(bytes, true, entry.clone()) {
+        Some(_) => None,
+        None => Some(entry),
+    }
+}
+
+#[cfg(test)]
+use crate::format::FormatKind;
+#[cfg(test)]
+use crate::parser::{test_ctx, Parser};
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_rfc5424_log_entry() {
+    let entry = parse_rfc5424_log_entry(
+        br#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application \"A\""][examplePriority@32473 class="high"] An application event"#,
+        &test_ctx(),
+    )
+    .unwrap();
+    assert_debug_snapshot!((&entry, entry.level(), entry.fields().collect::<Vec<_>>()), @r###"
+    (
+        LogEntry {
+            timestamp: Some(
+                Utc(
+                    2003-10-11T22:14:15.003Z,
+                ),
+            ),
+            message: "An application event",
+        },
+        Some(
+            Info,
+        ),
+        [
+            (
+                "priority",
+                "165",
+            ),
+            (
+                "facility",
+                "20",
+            ),
+            (
+                "hostname",
+                "mymachine.example.com",
+            ),
+            (
+                "app_name",
+                "evntslog",
+            ),
+            (
+                "msgid",
+                "ID47",
+            ),
+            (
+                "exampleSDID@32473.iut",
+                "3",
+            ),
+            (
+                "exampleSDID@32473.eventSource",
+                "Application \"A\"",
+            ),
+            (
+                "examplePriority@32473.class",
+                "high",
+            ),
+        ],
+    )
+    "###);
+    let entry = parse_rfc5424_log_entry(b"<34>1 - - su - - -", &test_ctx()).unwrap();
+    assert_debug_snapshot!((&entry, entry.level(), entry.fields().collect::<Vec<_>>()), @r###"
+    (
+        LogEntry {
+            timestamp: None,
+            message: "",
+        },
+        Some(
+            Fatal,
+        ),
+        [
+            (
+                "priority",
+                "34",
+            ),
+            (
+                "facility",
+                "4",
+            ),
+            (
+                "app_name",
+                "su",
+            ),
+        ],
+    )
+    "###);
+    assert!(parse_rfc5424_log_entry(b"<999>1 - - - - - - msg", &test_ctx()).is_none());
+}
+
+#[test]
+fn test_parse_rfc3164_log_entry() {
+    let parse = |line| {
+        let entry = parse_rfc3164_log_entry(line, &test_ctx())?;
+        Some((
+            entry.timestamp_span(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"Nov 20 21:56:01 herzog com.apple.xpc.launchd[1]: Service exited"),
+            parse(b"<34>Oct 11 22:14:15 mymachine su: 'su root' failed"),
+            parse(b"Nov 20 21:56:01 no tag here"),
+        ],
+        @r###"
+    [
+        Some(
+            (
+                Some(
+                    0..15,
+                ),
+                "Service exited",
+                [
+                    "hostname=herzog",
+                    "app_name=com.apple.xpc.launchd",
+                    "procid=1",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    4..19,
+                ),
+                "'su root' failed",
+                [
+                    "hostname=mymachine",
+                    "app_name=su",
+                    "priority=34",
+                    "facility=4",
+                ],
+            ),
+        ),
+        None,
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_parse_journal_log_entry() {
+    let parse = |line| {
+        let entry = parse_journal_log_entry(line, &test_ctx())?;
+        Some((
+            entry.timestamp().cloned(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"Jun 01 12:00:00.123456 myhost sshd[123]: Accepted publickey for root"),
+            parse(b"2021-06-01T12:00:00+0200 myhost systemd[1]: Started Session 1 of user root."),
+            parse(b"2021-06-01T12:00:01.123456+02:00 myhost kernel: usb 1-1: new high-speed USB device"),
+            parse(b"Jun 01 12:00:00 myhost sshd[123]: plain syslog"),
+            parse(b"2021-06-01T12:00:00+0200 ERROR something: failed"),
+        ],
+        @r###"
+    [
+        Some(
+            (
+                Some(
+                    Local(
+                        2017-06-01T12:00:00.123456+02:00,
+                    ),
+                ),
+                "Accepted publickey for root",
+                [
+                    "hostname=myhost",
+                    "app_name=sshd",
+                    "procid=123",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Fixed(
+                        2021-06-01T12:00:00+02:00,
+                    ),
+                ),
+                "Started Session 1 of user root.",
+                [
+                    "hostname=myhost",
+                    "app_name=systemd",
+                    "procid=1",
+                ],
+            ),
+        ),
+        Some(
+            (
+                Some(
+                    Fixed(
+                        2021-06-01T12:00:01.123456+02:00,
+                    ),
+                ),
+                "usb 1-1: new high-speed USB device",
+                [
+                    "hostname=myhost",
+                    "app_name=kernel",
+                ],
+            ),
+        ),
+        None,
+        None,
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_journal_by_default() {
+    let parser = Parser::new();
+    for line in &[
+        &b"Jun 01 12:00:00.123456 myhost sshd[123]: Accepted publickey for root"[..],
+        &b"2021-06-01T12:00:00+0200 myhost sshd[123]: Accepted publickey for root"[..],
+        &b"2021-06-01T12:00:01.123456+02:00 myhost kernel: usb 1-1: new device"[..],
+    ] {
+        let entry = parser.parse(line);
+        assert_eq!(entry.format(), Some(FormatKind::Journal));
+        assert_eq!(entry.hostname(), Some("myhost"));
+    }
+    let entry =
+        parser.parse(b"Jun 01 12:00:00.123456 myhost sshd[123]: Accepted publickey for root");
+    assert_eq!(entry.app_name(), Some("sshd"));
+    assert_eq!(entry.message(), "Accepted publickey for root");
+    let entry = parser.parse(b"Jun 01 12:00:00 myhost sshd[123]: plain syslog");
+    assert_ne!(entry.format(), Some(FormatKind::Journal));
+    let entry = parser.parse(b"2021-06-01T12:00:00+0200 ERROR something: failed");
+    assert_ne!(entry.format(), Some(FormatKind::Journal));
+}
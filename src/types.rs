@@ -1,24 +1,53 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::Range;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use chrono::prelude::*;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::parser;
+use crate::age::Age;
+use crate::category;
+use crate::clock::SystemClock;
+use crate::correlation;
+use crate::error::Error;
+use crate::exception::{self, StackTrace};
+use crate::format::FormatKind;
+use crate::kv;
+use crate::level::{self, Level};
+use crate::location::{self, SourceLocation};
+use crate::parser::{self, Context};
 
 lazy_static! {
     static ref COMPONENT_RE: Regex = Regex::new(r#"^([^:]+): ?(.*)$"#).unwrap();
+    static ref TIME_OF_DAY_RE: regex::bytes::Regex = regex::bytes::Regex::new(
+        r#"([0-9]{1,2})[:.]([0-9]{2})(?:[:.]([0-9]{2})(?:[.,:]([0-9]+))?)?"#
+    )
+    .unwrap();
+    static ref RELATIVE_TIME_RE: regex::bytes::Regex =
+        regex::bytes::Regex::new(r#"^[\[\x20+]*[0-9]+(?:\.([0-9]+))?(s|ms|us|µs|ns)?\]?$"#)
+            .unwrap();
 }
 
-#[derive(Debug)]
+/// A timestamp of a log entry.
+///
+/// The variant tells how the timezone of the timestamp was determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Timestamp {
+    /// The timestamp is in UTC.
     Utc(DateTime<Utc>),
+    /// The line carried no timezone and the timestamp was interpreted in
+    /// the timezone of the parsing machine.
     Local(DateTime<Local>),
+    /// The timestamp is at a known offset.
     Fixed(DateTime<FixedOffset>),
 }
 
 impl Timestamp {
+    /// Returns the timestamp in UTC.
     pub fn to_utc(&self) -> DateTime<Utc> {
         match *self {
             Timestamp::Utc(utc) => utc,
@@ -27,6 +56,7 @@ impl Timestamp {
         }
     }
 
+    /// Returns the timestamp in the timezone of the machine.
     pub fn to_local(&self) -> DateTime<Local> {
         match *self {
             Timestamp::Utc(utc) => utc.with_timezone(&Local),
@@ -34,12 +64,314 @@ impl Timestamp {
             Timestamp::Fixed(fixed) => fixed.with_timezone(&Local),
         }
     }
+
+    /// Returns the offset from UTC of the timestamp.
+    pub fn offset(&self) -> FixedOffset {
+        self.original().timezone()
+    }
+
+    /// Returns `true` if the timestamp is in UTC.
+    pub fn is_utc(&self) -> bool {
+        matches!(*self, Timestamp::Utc(_))
+    }
+
+    /// Returns `true` if the timezone of the timestamp was assumed to be
+    /// the one of the parsing machine.
+    pub fn is_local(&self) -> bool {
+        matches!(*self, Timestamp::Local(_))
+    }
+
+    /// Returns the timestamp at the offset it was recorded in.
+    pub fn original(&self) -> DateTime<FixedOffset> {
+        match *self {
+            Timestamp::Utc(utc) => utc.fixed_offset(),
+            Timestamp::Local(local) => local.fixed_offset(),
+            Timestamp::Fixed(fixed) => fixed,
+        }
+    }
+
+    /// Creates a timestamp from its components.
+    ///
+    /// Without an offset the timestamp is interpreted in the timezone of
+    /// the machine.  Unlike the parsers, which skip timestamps they cannot
+    /// represent, this reports which component was invalid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_components(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+        offset: Option<FixedOffset>,
+    ) -> Result<Timestamp, Error> {
+        let invalid = |what: &str, value: i64| {
+            Error::InvalidTimestamp(format!("{} {} is out of range", what, value))
+        };
+        if !(1..=12).contains(&month) {
+            return Err(invalid("month", month.into()));
+        }
+        let date =
+            NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| invalid("day", day.into()))?;
+        if hour > 23 {
+            return Err(invalid("hour", hour.into()));
+        }
+        if minute > 59 {
+            return Err(invalid("minute", minute.into()));
+        }
+        let naive = date.and_time(
+            parser::naive_time(hour, minute, second)
+                .ok_or_else(|| invalid("second", second.into()))?,
+        );
+        match offset {
+            Some(offset) => Ok(Timestamp::Fixed(
+                offset
+                    .from_local_datetime(&naive)
+                    .single()
+                    .ok_or_else(|| Error::InvalidTimestamp(format!("{} is out of range", naive)))?,
+            )),
+            None => NaiveTimezone::Local.resolve(&naive).ok_or_else(|| {
+                Error::InvalidTimestamp(format!("{} does not exist in the local timezone", naive))
+            }),
+        }
+    }
+
+    /// Returns a timestamp for another point in time in the same timezone.
+    pub(crate) fn with_utc(&self, ts: DateTime<Utc>) -> Timestamp {
+        match *self {
+            Timestamp::Utc(_) => Timestamp::Utc(ts),
+            Timestamp::Local(_) => Timestamp::Local(ts.with_timezone(&Local)),
+            Timestamp::Fixed(fixed) => Timestamp::Fixed(ts.with_timezone(&fixed.timezone())),
+        }
+    }
+}
+
+/// The point in time of a log entry, absolute or relative.
+///
+/// Formats such as the kernel ring buffer, Xorg or game engine logs only
+/// record the time since boot or process start.  Without a boot time these
+/// cannot be turned into a [`Timestamp`], but they still order the entries
+/// of a log.  Times of different kinds do not compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryTime {
+    /// An absolute timestamp.
+    Absolute(Timestamp),
+    /// The time since boot or process start.
+    Relative(Duration),
+}
+
+impl PartialOrd for EntryTime {
+    fn partial_cmp(&self, other: &EntryTime) -> Option<Ordering> {
+        match (self, other) {
+            (EntryTime::Absolute(a), EntryTime::Absolute(b)) => a.to_utc().partial_cmp(&b.to_utc()),
+            (EntryTime::Relative(a), EntryTime::Relative(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+/// The timezone assumed for timestamps that carry no offset.
+///
+/// By default naive timestamps are interpreted in the timezone of the
+/// machine doing the parsing, which is wrong for logs that were written
+/// on servers in other zones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NaiveTimezone {
+    /// The timezone of the parsing machine.
+    #[default]
+    Local,
+    /// Naive timestamps are in UTC.
+    Utc,
+    /// Naive timestamps are at a fixed offset.
+    Fixed(FixedOffset),
+    /// Naive timestamps are in a named timezone, honoring daylight saving.
+    #[cfg(feature = "chrono-tz")]
+    Named(chrono_tz::Tz),
+}
+
+impl From<Option<FixedOffset>> for NaiveTimezone {
+    fn from(offset: Option<FixedOffset>) -> NaiveTimezone {
+        match offset {
+            Some(offset) => NaiveTimezone::Fixed(offset),
+            None => NaiveTimezone::Local,
+        }
+    }
+}
+
+impl NaiveTimezone {
+    /// Returns the wall clock time in this timezone.
+    pub(crate) fn naive_now(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        match *self {
+            NaiveTimezone::Local => now.with_timezone(&Local).naive_local(),
+            NaiveTimezone::Utc => now.naive_utc(),
+            NaiveTimezone::Fixed(offset) => now.with_timezone(&offset).naive_local(),
+            #[cfg(feature = "chrono-tz")]
+            NaiveTimezone::Named(tz) => now.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// Resolves a naive timestamp in this timezone.
+    ///
+    /// Ambiguous times during a backwards transition resolve to the later
+    /// of the two candidates.
+    pub(crate) fn resolve(&self, naive: &NaiveDateTime) -> Option<Timestamp> {
+        Some(match *self {
+            NaiveTimezone::Local => Timestamp::Local(Local.from_local_datetime(naive).latest()?),
+            NaiveTimezone::Utc => Timestamp::Utc(Utc.from_utc_datetime(naive)),
+            NaiveTimezone::Fixed(offset) => {
+                Timestamp::Fixed(offset.from_local_datetime(naive).latest()?)
+            }
+            #[cfg(feature = "chrono-tz")]
+            NaiveTimezone::Named(tz) => {
+                Timestamp::Fixed(tz.from_local_datetime(naive).latest()?.fixed_offset())
+            }
+        })
+    }
+}
+
+/// The precision of a timestamp as it appeared in the line.
+///
+/// Variants are ordered from coarse to fine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Precision {
+    Minute,
+    Second,
+    Milli,
+    Micro,
+    Nano,
+}
+
+impl Precision {
+    pub(crate) fn from_fraction_digits(digits: usize) -> Precision {
+        match digits {
+            0 => Precision::Second,
+            1..=3 => Precision::Milli,
+            4..=6 => Precision::Micro,
+            _ => Precision::Nano,
+        }
+    }
+
+    /// Determines the precision from the text of a timestamp.
+    fn detect(ts: &[u8]) -> Precision {
+        if let Some(caps) = RELATIVE_TIME_RE.captures(ts) {
+            let digits = caps.get(1).map_or(0, |x| x.as_bytes().len());
+            let unit = match caps.get(2).map(|x| x.as_bytes()) {
+                Some(b"ms") => 3,
+                Some(b"us") | Some(b"\xc2\xb5s") => 6,
+                Some(b"ns") => 9,
+                _ => 0,
+            };
+            return Precision::from_fraction_digits(digits + unit);
+        }
+        match TIME_OF_DAY_RE.captures_iter(ts).last() {
+            Some(caps) => match (caps.get(3), caps.get(4)) {
+                (None, _) => Precision::Minute,
+                (Some(_), fraction) => {
+                    Precision::from_fraction_digits(fraction.map_or(0, |x| x.as_bytes().len()))
+                }
+            },
+            None => Precision::Second,
+        }
+    }
+}
+
+/// The message of an entry.
+///
+/// Messages which are a slice of the line are only decoded when they are
+/// first accessed, so consumers that only look at timestamps never pay for
+/// UTF-8 validation.  This also holds for owned entries, which keep a copy
+/// of the raw bytes until the message is accessed.
+#[derive(Clone)]
+struct Message<'a> {
+    raw: Cow<'a, [u8]>,
+    text: OnceLock<Cow<'a, str>>,
+}
+
+impl<'a> Message<'a> {
+    fn raw(raw: &'a [u8]) -> Message<'a> {
+        Message {
+            raw: Cow::Borrowed(raw),
+            text: OnceLock::new(),
+        }
+    }
+
+    fn text(text: Cow<'a, str>) -> Message<'a> {
+        Message {
+            raw: Cow::Borrowed(b""),
+            text: OnceLock::from(text),
+        }
+    }
+
+    fn as_str(&self) -> &Cow<'a, str> {
+        self.text.get_or_init(|| match self.raw {
+            Cow::Borrowed(raw) => String::from_utf8_lossy(raw),
+            Cow::Owned(ref raw) => Cow::Owned(String::from_utf8_lossy(raw).into_owned()),
+        })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self.text.get() {
+            Some(text) => text.as_bytes(),
+            None => &self.raw,
+        }
+    }
+
+    /// Removes an ASCII prefix of the given length.
+    fn strip_prefix(&mut self, len: usize) {
+        match self.text.take() {
+            None => match self.raw {
+                Cow::Borrowed(ref mut raw) => *raw = &raw[len..],
+                Cow::Owned(ref mut raw) => {
+                    raw.drain(..len);
+                }
+            },
+            Some(Cow::Borrowed(text)) => self.text = OnceLock::from(Cow::Borrowed(&text[len..])),
+            Some(Cow::Owned(mut text)) => {
+                text.drain(..len);
+                self.text = OnceLock::from(Cow::Owned(text));
+            }
+        }
+    }
+
+    fn to_mut(&mut self) -> &mut String {
+        self.as_str();
+        self.text.get_mut().unwrap().to_mut()
+    }
+
+    fn into_owned(self) -> Message<'static> {
+        match self.text.into_inner() {
+            Some(text) => Message::text(Cow::Owned(text.into_owned())),
+            None => Message {
+                raw: Cow::Owned(self.raw.into_owned()),
+                text: OnceLock::new(),
+            },
+        }
+    }
 }
 
 /// Represents a parsed log entry.
+///
+/// Entries borrow their message from the parsed line where possible, use
+/// [`LogEntry::into_owned`] to keep them around longer than the line.
+#[derive(Clone)]
 pub struct LogEntry<'a> {
     timestamp: Option<Timestamp>,
-    message: Cow<'a, str>,
+    message: Message<'a>,
+    timestamp_span: Option<Range<usize>>,
+    message_span: Option<Range<usize>>,
+    implausible_timestamp: bool,
+    inherited_timestamp: bool,
+    truncated: bool,
+    relative_time: Option<Duration>,
+    level: Option<Cow<'a, str>>,
+    precision: Option<Precision>,
+    format: Option<FormatKind>,
+    fields: Vec<(Cow<'a, str>, Cow<'a, str>)>,
 }
 
 impl<'a> fmt::Debug for LogEntry<'a> {
@@ -52,48 +384,266 @@ impl<'a> fmt::Debug for LogEntry<'a> {
 }
 
 impl<'a> LogEntry<'a> {
+    fn new(timestamp: Option<Timestamp>, message: Message<'a>) -> LogEntry<'a> {
+        LogEntry {
+            timestamp,
+            message,
+            timestamp_span: None,
+            message_span: None,
+            implausible_timestamp: false,
+            inherited_timestamp: false,
+            truncated: false,
+            relative_time: None,
+            level: None,
+            precision: None,
+            format: None,
+            fields: Vec::new(),
+        }
+    }
+
     /// Parses a well known log line into a log entry.
-    pub fn parse(bytes: &[u8]) -> LogEntry {
-        parser::parse_log_entry(bytes, None).unwrap_or_else(|| LogEntry::from_message_only(bytes))
+    pub fn parse(bytes: &[u8]) -> LogEntry<'_> {
+        LogEntry::parse_with_context(bytes, &Context::new(NaiveTimezone::Local, &SystemClock))
+    }
+
+    /// Like `parse` but also returns the format the line was recognized as.
+    pub fn parse_with_format(bytes: &[u8]) -> (LogEntry<'_>, Option<FormatKind>) {
+        let entry = LogEntry::parse(bytes);
+        let format = entry.format();
+        (entry, format)
     }
 
     /// Similar to `parse` but uses the given timezone for local time.
-    pub fn parse_with_local_timezone(bytes: &[u8], offset: Option<FixedOffset>) -> LogEntry {
-        parser::parse_log_entry(bytes, offset).unwrap_or_else(|| LogEntry::from_message_only(bytes))
+    pub fn parse_with_local_timezone(bytes: &[u8], offset: Option<FixedOffset>) -> LogEntry<'_> {
+        LogEntry::parse_with_context(bytes, &Context::new(offset.into(), &SystemClock))
+    }
+
+    /// Similar to `parse` but resolves missing date components relative to
+    /// a reference time instead of the current time.
+    ///
+    /// The reference is typically the modification time of the logfile
+    /// or the timestamp of the event the log was attached to.
+    pub fn parse_with_reference(bytes: &[u8], reference: DateTime<Utc>) -> LogEntry<'_> {
+        LogEntry::parse_with_context(
+            bytes,
+            &Context::with_reference(NaiveTimezone::Local, reference),
+        )
+    }
+
+    fn parse_with_context<'b>(bytes: &'b [u8], ctx: &Context) -> LogEntry<'b> {
+        let mut entry = parser::parse_log_entry(bytes, ctx)
+            .unwrap_or_else(|| LogEntry::from_message_only(bytes));
+        entry.detect_level_marker(false);
+        entry
+    }
+
+    /// Constructs an owned log entry from an optional timestamp and message.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(timestamp: Option<Timestamp>, message: String) -> LogEntry<'static> {
+        LogEntry::new(timestamp, Message::text(Cow::Owned(message)))
+    }
+
+    /// Constructs a log entry from a resolved timestamp and message.
+    pub(crate) fn from_timestamp(ts: Timestamp, message: &'a [u8]) -> LogEntry<'a> {
+        LogEntry::new(Some(ts), Message::raw(message))
     }
 
     /// Constructs a log entry from a UTC timestamp and message.
     pub fn from_utc_time(ts: DateTime<Utc>, message: &'a [u8]) -> LogEntry<'a> {
-        LogEntry {
-            timestamp: Some(Timestamp::Utc(ts)),
-            message: String::from_utf8_lossy(message),
-        }
+        LogEntry::new(Some(Timestamp::Utc(ts)), Message::raw(message))
     }
 
     /// Constructs a log entry from a local timestamp and message.
     pub fn from_local_time(ts: DateTime<Local>, message: &'a [u8]) -> LogEntry<'a> {
-        LogEntry {
-            timestamp: Some(Timestamp::Local(ts)),
-            message: String::from_utf8_lossy(message),
-        }
+        LogEntry::new(Some(Timestamp::Local(ts)), Message::raw(message))
     }
 
     /// Constructs a log entry from a timestamp in a specific timezone and message.
     pub fn from_fixed_time(ts: DateTime<FixedOffset>, message: &'a [u8]) -> LogEntry<'a> {
-        LogEntry {
-            timestamp: Some(Timestamp::Fixed(ts)),
-            message: String::from_utf8_lossy(message),
-        }
+        LogEntry::new(Some(Timestamp::Fixed(ts)), Message::raw(message))
     }
 
     /// Creates a log entry from only a message.
     pub fn from_message_only(message: &'a [u8]) -> LogEntry<'a> {
+        let mut entry = LogEntry::new(None, Message::raw(message));
+        entry.message_span = Some(0..message.len());
+        entry
+    }
+
+    /// Converts the entry into one that owns its message.
+    ///
+    /// The result no longer borrows the line and can be stored freely.
+    pub fn into_owned(self) -> LogEntry<'static> {
         LogEntry {
-            timestamp: None,
-            message: String::from_utf8_lossy(message),
+            timestamp: self.timestamp,
+            message: self.message.into_owned(),
+            timestamp_span: self.timestamp_span,
+            message_span: self.message_span,
+            implausible_timestamp: self.implausible_timestamp,
+            inherited_timestamp: self.inherited_timestamp,
+            truncated: self.truncated,
+            relative_time: self.relative_time,
+            level: self.level.map(|x| Cow::Owned(x.into_owned())),
+            precision: self.precision,
+            format: self.format,
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned())))
+                .collect(),
+        }
+    }
+
+    /// Records where the timestamp and message were found in the line.
+    pub(crate) fn with_spans(
+        mut self,
+        timestamp_span: Range<usize>,
+        message_span: Range<usize>,
+    ) -> LogEntry<'a> {
+        self.timestamp_span = Some(timestamp_span);
+        self.message_span = Some(message_span);
+        self
+    }
+
+    /// Records where the message was found in the line.
+    pub(crate) fn with_message_span(mut self, message_span: Range<usize>) -> LogEntry<'a> {
+        self.message_span = Some(message_span);
+        self
+    }
+
+    /// Sets the level as it appeared in the line.
+    pub(crate) fn with_level(mut self, level: Cow<'a, str>) -> LogEntry<'a> {
+        self.level = Some(level);
+        self
+    }
+
+    /// Determines the precision from the timestamp in the parsed line
+    /// unless the format already set one.
+    pub(crate) fn detect_precision(&mut self, line: &[u8]) {
+        if self.precision.is_none() {
+            if let Some(ts) = self.timestamp_span.clone().and_then(|x| line.get(x)) {
+                self.precision = Some(Precision::detect(ts));
+            }
+        }
+    }
+
+    /// Adds a named field extracted by the format.
+    pub(crate) fn with_field<K, V>(mut self, key: K, value: V) -> LogEntry<'a>
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    pub(crate) fn with_format(mut self, format: FormatKind) -> LogEntry<'a> {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the precision for timestamps that are not detected from the
+    /// time of day.
+    pub(crate) fn with_precision(mut self, precision: Precision) -> LogEntry<'a> {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Records where the timestamp was found in the line.
+    pub(crate) fn with_timestamp_span(mut self, timestamp_span: Range<usize>) -> LogEntry<'a> {
+        self.timestamp_span = Some(timestamp_span);
+        self
+    }
+
+    /// Detects a severity marker at the start of the message.
+    ///
+    /// If `strip` is set the marker is removed from the message.  Entries
+    /// whose format already provided a level are left alone.
+    pub(crate) fn detect_level_marker(&mut self, strip: bool) {
+        if self.level.is_some() {
+            return;
+        }
+        let (name, len) = match level::find_level_marker(self.message.bytes()) {
+            Some((_, name, len)) => (name.to_string(), len),
+            None => return,
+        };
+        self.level = Some(Cow::Owned(name));
+        if strip {
+            let borrowed = matches!(self.message.text.get(), None | Some(Cow::Borrowed(_)));
+            self.message.strip_prefix(len);
+            self.message_span = match self.message_span.take() {
+                Some(span) if borrowed => Some(span.start + len..span.end),
+                _ => None,
+            };
+        }
+    }
+
+    /// Replaces the message with another slice of the line.
+    pub(crate) fn with_message(mut self, message: &'a [u8]) -> LogEntry<'a> {
+        self.message = Message::raw(message);
+        self
+    }
+
+    /// Replaces the message with one that is not a slice of the line.
+    pub(crate) fn with_owned_message(mut self, message: String) -> LogEntry<'a> {
+        self.message = Message::text(Cow::Owned(message));
+        self.message_span = None;
+        self
+    }
+
+    /// Appends a continuation line to the message.
+    pub(crate) fn append_line(&mut self, line: &str) {
+        let message = self.message.to_mut();
+        message.push('\n');
+        message.push_str(line);
+        self.message_span = None;
+    }
+
+    /// Appends text to the message without a line break.
+    pub(crate) fn append_text(&mut self, text: &str) {
+        self.message.to_mut().push_str(text);
+        self.message_span = None;
+    }
+
+    /// Replaces the value of a field or adds it if it is missing.
+    pub(crate) fn set_field(&mut self, key: &str, value: &str) {
+        let value = Cow::Owned(value.to_string());
+        match self.fields.iter_mut().find(|(k, _)| k == key) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((Cow::Owned(key.to_string()), value)),
         }
     }
 
+    pub(crate) fn clamp_timestamp(&mut self, ts: DateTime<Utc>) {
+        self.timestamp = self.timestamp.as_ref().map(|x| x.with_utc(ts));
+    }
+
+    pub(crate) fn discard_timestamp(&mut self) {
+        self.timestamp = None;
+    }
+
+    pub(crate) fn inherit_timestamp(&mut self, ts: Timestamp) {
+        self.timestamp = Some(ts);
+        self.inherited_timestamp = true;
+    }
+
+    pub(crate) fn flag_implausible_timestamp(&mut self) {
+        self.implausible_timestamp = true;
+    }
+
+    pub(crate) fn mark_truncated(&mut self) {
+        self.truncated = true;
+    }
+
+    pub(crate) fn set_relative_time(&mut self, relative_time: Duration) {
+        self.relative_time = Some(relative_time);
+    }
+
+    /// Returns the timestamp as it was parsed.
+    pub fn timestamp(&self) -> Option<&Timestamp> {
+        self.timestamp.as_ref()
+    }
+
     /// Returns the timestamp in local timezone.
     pub fn local_timestamp(&self) -> Option<DateTime<Local>> {
         self.timestamp.as_ref().map(|x| x.to_local())
@@ -104,13 +654,199 @@ impl<'a> LogEntry<'a> {
         self.timestamp.as_ref().map(|x| x.to_utc())
     }
 
+    /// Returns how long before the reference time the entry was logged.
+    ///
+    /// The age is negative for entries after the reference.  Formatting it
+    /// gives a human readable form like `3m12s before`.
+    pub fn age_from<Tz: TimeZone>(&self, reference: &DateTime<Tz>) -> Option<Age> {
+        Some(Age::new(
+            reference.with_timezone(&Utc) - self.utc_timestamp()?,
+        ))
+    }
+
+    /// Returns the byte range of the timestamp within the parsed line.
+    ///
+    /// This is only available for entries created by parsing.
+    pub fn timestamp_span(&self) -> Option<Range<usize>> {
+        self.timestamp_span.clone()
+    }
+
+    /// Returns the byte range of the message within the parsed line.
+    ///
+    /// This is only available for entries created by parsing where the
+    /// message is a contiguous part of the line.
+    pub fn message_span(&self) -> Option<Range<usize>> {
+        self.message_span.clone()
+    }
+
+    /// Returns `true` if the parsed timestamp was outside of the plausible
+    /// range configured on the parser.
+    pub fn has_implausible_timestamp(&self) -> bool {
+        self.implausible_timestamp
+    }
+
+    /// Returns `true` if the line carried no timestamp and the timestamp
+    /// was taken from the preceding entry of the stream.
+    pub fn has_inherited_timestamp(&self) -> bool {
+        self.inherited_timestamp
+    }
+
+    /// Returns `true` if the line exceeded the maximum line length of the
+    /// parser and was cut off.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the time since boot or process start for formats that log
+    /// relative timestamps.
+    ///
+    /// This is available even if the parser has no boot time configured to
+    /// convert it into an absolute timestamp.
+    pub fn relative_time(&self) -> Option<Duration> {
+        self.relative_time
+    }
+
+    /// Returns the absolute timestamp or, failing that, the relative time.
+    ///
+    /// Unlike [`timestamp`](Self::timestamp) this is set for relative
+    /// timestamps without a boot time, so it can order such entries.
+    pub fn time(&self) -> Option<EntryTime> {
+        match (self.timestamp, self.relative_time) {
+            (Some(ts), _) => Some(EntryTime::Absolute(ts)),
+            (None, Some(relative)) => Some(EntryTime::Relative(relative)),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the precision of the timestamp as it appeared in the line.
+    ///
+    /// A timestamp with seconds but no fraction has a precision of
+    /// [`Precision::Second`] even though it is stored with more precision.
+    pub fn timestamp_precision(&self) -> Option<Precision> {
+        if self.timestamp.is_some() || self.relative_time.is_some() {
+            self.precision
+        } else {
+            None
+        }
+    }
+
     /// Returns the message.
-    pub fn message(&'a self) -> &str {
-        &self.message
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /// Returns the format the line was recognized as.
+    ///
+    /// This is `None` if no format matched and the entry only carries a
+    /// message.
+    pub fn format(&self) -> Option<FormatKind> {
+        self.format
+    }
+
+    /// Returns the value of a field.
+    ///
+    /// Formats with metadata beyond timestamp and message expose it as
+    /// fields, for instance `hostname` and `app_name` for syslog.  See
+    /// [`fields`](Self::fields) for the pairs found in the message.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the host that logged the entry for syslog formats.
+    pub fn hostname(&self) -> Option<&str> {
+        self.field("hostname")
+    }
+
+    /// Returns the application or process tag for syslog formats.
+    pub fn app_name(&self) -> Option<&str> {
+        self.field("app_name")
+    }
+
+    /// Returns the process id for syslog formats.
+    pub fn process_id(&self) -> Option<&str> {
+        self.field("procid")
+    }
+
+    /// Iterates over all fields in the order they appeared in the line.
+    ///
+    /// The fields of the format come first, followed by the `key=value`
+    /// and `key="quoted value"` pairs in the message.  Pairs in the message
+    /// do not override fields of the format with the same key.  Quoted
+    /// values are returned without the quotes but escapes are kept.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        // reborrow so the iterator does not capture the entry lifetime
+        let stored: &[(Cow<'_, str>, Cow<'_, str>)] = &self.fields;
+        let format_fields = stored
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.as_ref()));
+        let message_fields =
+            kv::pairs(self.message()).filter(move |(key, _)| !stored.iter().any(|(k, _)| k == key));
+        format_fields.chain(message_fields)
+    }
+
+    /// Returns the severity of the entry.
+    ///
+    /// The level is taken from the format if it carries one or detected
+    /// from a marker such as `[INFO]`, `ERROR -`, `DEBUG:`, `<warn>` or
+    /// `W/` at the start of the message.
+    pub fn level(&self) -> Option<Level> {
+        self.level.as_deref().and_then(Level::from_name)
+    }
+
+    /// Returns the level as it was written in the line.
+    pub fn level_name(&self) -> Option<&str> {
+        self.level.as_deref()
+    }
+
+    /// Returns a breadcrumb category inferred from the message.
+    ///
+    /// The category is derived from well known programs or tags such as
+    /// `kernel` (`device`) or `sshd` (`auth`) and from HTTP request lines
+    /// (`http`).
+    pub fn category(&self) -> Option<&'static str> {
+        category::infer_category(self.message())
+    }
+
+    /// Returns the source file and line referenced by the message.
+    ///
+    /// Formats that carry the location in the line prefix expose it as the
+    /// `file` and `line` fields, which take precedence.
+    pub fn source_location(&self) -> Option<SourceLocation<'_>> {
+        if let (Some(file), Some(line)) = (self.field("file"), self.field("line")) {
+            if let Ok(line) = line.parse() {
+                return Some(SourceLocation::new(file, line));
+            }
+        }
+        location::find_source_location(self.message())
+    }
+
+    /// Returns a request, trace or session identifier from the message.
+    ///
+    /// Entries with the same identifier usually belong to the same request
+    /// and can be grouped or attached to the matching event.
+    pub fn correlation_id(&self) -> Option<&str> {
+        correlation::find_correlation_id(self.message())
+    }
+
+    /// Returns the stack trace contained in the message.
+    ///
+    /// Multi-line traces are only available if the entry was read from a
+    /// stream with [`ParserBuilder::group_exceptions`] enabled.
+    ///
+    /// [`ParserBuilder::group_exceptions`]: crate::ParserBuilder::group_exceptions
+    pub fn stack_trace(&self) -> Option<StackTrace<'_>> {
+        exception::find_stack_trace(self.message())
+    }
+
+    /// Returns `true` if the entry carries an exception or panic.
+    pub fn is_exception(&self) -> bool {
+        self.stack_trace().is_some()
     }
 
     /// Like `message` but chops off a leading component.
-    pub fn component_and_message(&'a self) -> (Option<&str>, &str) {
+    pub fn component_and_message(&self) -> (Option<&str>, &str) {
         if let Some(caps) = COMPONENT_RE.captures(self.message()) {
             (
                 Some(caps.get(1).unwrap().as_str()),
@@ -122,6 +858,10 @@ impl<'a> LogEntry<'a> {
     }
 }
 
+#[cfg(test)]
+use crate::parser::test_builder;
+#[cfg(test)]
+use crate::parser::Parser;
 #[cfg(test)]
 use insta::assert_debug_snapshot;
 
@@ -145,7 +885,7 @@ fn test_parse_c_log_entry() {
 #[test]
 fn test_parse_short_log_entry() {
     assert_debug_snapshot!(
-    LogEntry::parse(b"Nov 20 21:56:01 herzog com.apple.xpc.launchd[1] (com.apple.preference.displays.MirrorDisplays): Service only ran for 0 seconds. Pushing respawn out by 10 seconds."),
+    test_builder().build().parse(b"Nov 20 21:56:01 herzog com.apple.xpc.launchd[1] (com.apple.preference.displays.MirrorDisplays): Service only ran for 0 seconds. Pushing respawn out by 10 seconds."),
         @r###"
     LogEntry {
         timestamp: Some(
@@ -162,14 +902,14 @@ fn test_parse_short_log_entry() {
 #[test]
 fn test_parse_short_log_entry_extra() {
     assert_debug_snapshot!(
-    LogEntry::parse(
+    test_builder().build().parse(
         b"Mon Nov 20 00:31:19.005 <kernel> en0: Received EAPOL packet (length = 161)",
     ),
         @r###"
     LogEntry {
         timestamp: Some(
             Local(
-                2017-11-20T00:31:19+01:00,
+                2017-11-20T00:31:19.005+01:00,
             ),
         ),
         message: "<kernel> en0: Received EAPOL packet (length = 161)",
@@ -181,7 +921,7 @@ fn test_parse_short_log_entry_extra() {
 #[test]
 fn test_parse_simple_log_entry() {
     assert_debug_snapshot!(
-    LogEntry::parse(
+    test_builder().build().parse(
         b"22:07:10 server  | detected binary path: /Users/mitsuhiko/.virtualenvs/sentry/bin/uwsgi",
     ),
         @r###"
@@ -251,7 +991,7 @@ fn test_parse_unreal_log_entry() {
     LogEntry {
         timestamp: Some(
             Utc(
-                2018-10-29T16:56:37Z,
+                2018-10-29T16:56:37.542Z,
             ),
         ),
         message: "LogInit: Selected Device Profile: [WindowsNoEditor]",
@@ -289,3 +1029,390 @@ fn test_simple_component_extraction() {
     "###
     );
 }
+
+#[test]
+fn test_unreal_component_extraction() {
+    assert_debug_snapshot!(
+    LogEntry::parse(b"[2018.10.29-16.56.37:542][  0]LogInit: Display: Engine started").component_and_message(),
+        @r###"
+    (
+        Some(
+            "LogInit",
+        ),
+        "Display: Engine started",
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_parse_with_reference() {
+    let reference = Utc.with_ymd_and_hms(2019, 3, 5, 0, 10, 0).unwrap();
+    assert_debug_snapshot!(
+    LogEntry::parse_with_reference(b"Dec 30 12:00:00 herzog kernel: shutdown", reference),
+        @r###"
+    LogEntry {
+        timestamp: Some(
+            Local(
+                2018-12-30T12:00:00+01:00,
+            ),
+        ),
+        message: "herzog kernel: shutdown",
+    }
+    "###
+    );
+    assert_debug_snapshot!(
+    LogEntry::parse_with_reference(b"23:50:00 server  | stopping", reference),
+        @r###"
+    LogEntry {
+        timestamp: Some(
+            Local(
+                2019-03-04T23:50:00+01:00,
+            ),
+        ),
+        message: "server  | stopping",
+    }
+    "###
+    );
+}
+
+#[test]
+fn test_spans() {
+    let line = b"[Sun Feb 25 06:11:12.043123448 2018] [:notice] process manager initialized";
+    let entry = LogEntry::parse(line);
+    assert_eq!(
+        &line[entry.timestamp_span().unwrap()],
+        &b"Sun Feb 25 06:11:12.043123448 2018"[..]
+    );
+    assert_eq!(
+        &line[entry.message_span().unwrap()],
+        &b"[:notice] process manager initialized"[..]
+    );
+
+    let entry = LogEntry::parse(b"no timestamp here");
+    assert_eq!(entry.timestamp_span(), None);
+    assert_eq!(entry.message_span(), Some(0..17));
+}
+
+#[test]
+fn test_timestamp_offset() {
+    let describe = |bytes| {
+        let entry = test_builder().build().parse(bytes);
+        entry
+            .timestamp()
+            .map(|ts| (ts.original(), ts.offset(), ts.is_utc(), ts.is_local()))
+    };
+    assert_debug_snapshot!(describe(b"2015-05-13 17:39:16 +0200: with offset"), @r###"
+    Some(
+        (
+            2015-05-13T17:39:16+02:00,
+            +02:00,
+            false,
+            false,
+        ),
+    )
+    "###);
+    assert_debug_snapshot!(describe(b"Tue Nov 21 00:30:05 2017 without offset"), @r###"
+    Some(
+        (
+            2017-11-21T00:30:05+01:00,
+            +01:00,
+            false,
+            true,
+        ),
+    )
+    "###);
+}
+
+#[test]
+fn test_timestamp_precision() {
+    let precision = |bytes| test_builder().build().parse(bytes).timestamp_precision();
+    assert_debug_snapshot!(
+        (
+            precision(b"Tue Nov 21 00:30:05 2017 seconds"),
+            precision(b"Mon Nov 20 00:31:19.005 <kernel> millis"),
+            precision(b"[2018.10.29-16.56.37:542][  0]LogInit: millis"),
+            precision(b"[   12.345678] micros"),
+            precision(b"+250us micros"),
+            precision(b"no timestamp"),
+        ),
+        @r###"
+    (
+        Some(
+            Second,
+        ),
+        Some(
+            Milli,
+        ),
+        Some(
+            Milli,
+        ),
+        Some(
+            Micro,
+        ),
+        Some(
+            Micro,
+        ),
+        None,
+    )
+    "###
+    );
+}
+
+#[test]
+fn test_lazy_message() {
+    let entry = LogEntry::parse(b"2015-05-13 17:39:16 +0200: caf\xc3\xa9");
+    assert!(entry.utc_timestamp().is_some());
+    assert!(entry.message.text.get().is_none());
+    assert_eq!(entry.message(), "café");
+    assert!(entry.message.text.get().is_some());
+    assert_eq!(
+        LogEntry::from_message_only(b"bad \xff")
+            .into_owned()
+            .message(),
+        "bad \u{fffd}"
+    );
+}
+
+#[test]
+fn test_timestamp_from_components() {
+    assert_debug_snapshot!(
+        Timestamp::from_components(2021, 6, 1, 12, 0, 0, FixedOffset::east_opt(7200)),
+        @r###"
+    Ok(
+        Fixed(
+            2021-06-01T12:00:00+02:00,
+        ),
+    )
+    "###
+    );
+    assert_debug_snapshot!(
+        [
+            Timestamp::from_components(2021, 13, 1, 12, 0, 0, None),
+            Timestamp::from_components(2021, 2, 30, 12, 0, 0, None),
+            Timestamp::from_components(2021, 6, 1, 12, 61, 0, None),
+        ]
+        .iter()
+        .map(|x| x.as_ref().map_err(|err| err.to_string()).map(|_| ()))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Err(
+            "invalid timestamp: month 13 is out of range",
+        ),
+        Err(
+            "invalid timestamp: day 30 is out of range",
+        ),
+        Err(
+            "invalid timestamp: minute 61 is out of range",
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_age_from() {
+    let crash = Utc.with_ymd_and_hms(2015, 5, 13, 15, 42, 28).unwrap();
+    assert_debug_snapshot!(
+        [
+            &b"2015-05-13 17:39:16 +0200: long before"[..],
+            &b"2015-05-13 15:42:28 +0000: at the crash"[..],
+            &b"2015-05-13 15:42:30 +0000: after the crash"[..],
+            &b"2015-05-11 11:00:00 +0000: days before"[..],
+            &b"no timestamp"[..],
+        ]
+        .iter()
+        .map(|line| LogEntry::parse(line).age_from(&crash).map(|age| age.to_string()))
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            "3m12s before",
+        ),
+        Some(
+            "at the same time",
+        ),
+        Some(
+            "2s after",
+        ),
+        Some(
+            "2d4h before",
+        ),
+        None,
+    ]
+    "###
+    );
+    let entry = LogEntry::parse(b"2015-05-13 15:42:28 +0000: at the crash");
+    let crash = crash - chrono::Duration::microseconds(250);
+    assert_eq!(entry.age_from(&crash).unwrap().to_string(), "<1ms after");
+}
+
+#[test]
+fn test_parse_with_format() {
+    assert_debug_snapshot!(
+        [
+            &b"Tue Nov 21 00:30:05 2017 More stuff here"[..],
+            &b"Nov 20 21:56:01 herzog sshd[1]: hi"[..],
+            &b"2015-05-13 17:39:16 +0200: Repaired"[..],
+            &b"[2018.10.29-16.56.37:542][  0]LogInit: hi"[..],
+            &b"just a message"[..],
+        ]
+        .iter()
+        .map(|line| LogEntry::parse_with_format(line).1)
+        .collect::<Vec<_>>(),
+        @r###"
+    [
+        Some(
+            C,
+        ),
+        Some(
+            Short,
+        ),
+        Some(
+            Common,
+        ),
+        Some(
+            Ue4,
+        ),
+        None,
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_lazy_owned_message() {
+    let input = &b"2015-05-13 17:39:16 +0200: caf\xc3\xa9\n2015-05-13 17:39:17 +0200: done\n"[..];
+    let entries = Parser::new()
+        .read_entries(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    for entry in &entries {
+        assert!(entry.utc_timestamp().is_some());
+        assert!(entry.message.text.get().is_none());
+    }
+    assert_eq!(entries[0].message(), "caf\u{e9}");
+    assert_eq!(entries[1].message(), "done");
+}
+
+#[test]
+fn test_level_marker() {
+    let parser = test_builder().strip_level_markers(true).build();
+    let line = b"2015-05-13 17:39:16 +0200: [WARN] disk almost full";
+    let entry = parser.parse(line);
+    assert_debug_snapshot!(
+        (
+            entry.level(),
+            entry.level_name(),
+            &entry,
+            String::from_utf8_lossy(&line[entry.message_span().unwrap()]),
+        ),
+        @r###"
+    (
+        Some(
+            Warning,
+        ),
+        Some(
+            "WARN",
+        ),
+        LogEntry {
+            timestamp: Some(
+                Fixed(
+                    2015-05-13T17:39:16+02:00,
+                ),
+            ),
+            message: "disk almost full",
+        },
+        "disk almost full",
+    )
+    "###
+    );
+    let entry = Parser::new().parse(b"ERROR - connection refused");
+    assert_debug_snapshot!((entry.level(), entry.message()), @r###"
+    (
+        Some(
+            Error,
+        ),
+        "ERROR - connection refused",
+    )
+    "###);
+    assert_debug_snapshot!(LogEntry::parse(b"<info> started").level(), @r###"
+    Some(
+        Info,
+    )
+    "###);
+}
+
+#[test]
+fn test_into_owned() {
+    let entries: Vec<LogEntry<'static>> = {
+        let buf = String::from("2015-05-13 17:39:16 +0200: [WARN] first\nsecond");
+        buf.lines()
+            .map(|line| LogEntry::parse(line.as_bytes()).into_owned())
+            .collect()
+    };
+    let copy = entries[0].clone();
+    assert_debug_snapshot!((copy.level(), copy.message_span(), entries), @r###"
+    (
+        Some(
+            Warning,
+        ),
+        Some(
+            27..39,
+        ),
+        [
+            LogEntry {
+                timestamp: Some(
+                    Fixed(
+                        2015-05-13T17:39:16+02:00,
+                    ),
+                ),
+                message: "[WARN] first",
+            },
+            LogEntry {
+                timestamp: None,
+                message: "second",
+            },
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn test_message_fields() {
+    let entry =
+        LogEntry::parse(b"2015-05-13 17:39:16 +0200: request done status=200 path=\"/a b\"");
+    assert_debug_snapshot!(entry.fields().collect::<Vec<_>>(), @r###"
+    [
+        (
+            "status",
+            "200",
+        ),
+        (
+            "path",
+            "/a b",
+        ),
+    ]
+    "###);
+    assert_eq!(entry.field("path"), Some("/a b"));
+}
+
+#[test]
+fn test_entry_time() {
+    let parser = Parser::new();
+    let first = parser.parse(b"[    1.500000] usb 1-1: new device");
+    let second = parser.parse(b"[   12.000000] usb 1-1: disconnect");
+    assert_eq!(first.timestamp(), None);
+    assert_debug_snapshot!(first.time(), @r###"
+    Some(
+        Relative(
+            1.5s,
+        ),
+    )
+    "###);
+    assert!(first.time() < second.time());
+    let absolute = parser.parse(b"2015-05-13 17:39:16 +0200: hello");
+    assert_eq!(first.time().partial_cmp(&absolute.time()), None);
+}
@@ -0,0 +1,123 @@
+use std::str;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref UNITY_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ([0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(?:\.[0-9]{1,9})?Z?)
+            \|(0x[0-9a-f]+)\|
+            (.*)
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of a Unity `Editor.log` or `Player.log` written with the
+/// `-timestamps` command line option.
+///
+/// The thread id is exposed as the `tid` field.  Stack traces printed
+/// after a message are grouped with it when exceptions are grouped.
+///
+/// Example: `2021-06-01T12:00:00.123Z|0x1a2b|message`
+pub fn parse_unity_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = UNITY_RE.captures(bytes)?;
+    let ts = caps.get(1).unwrap();
+    // the timestamp and thread id only match ASCII
+    // reuse the RFC 3339 parser for the timestamp, which needs a message
+    let line = [ts.as_bytes(), b" -"].concat();
+    let timestamp = *parser::parse_rfc3339_log_entry(&line, ctx)?.timestamp()?;
+
+    let message = caps.get(3).unwrap().range();
+    Some(
+        LogEntry::from_timestamp(timestamp, &bytes[message.clone()])
+            .with_spans(ts.range(), message)
+            .with_field(
+                "tid",
+                str::from_utf8(caps.get(2).unwrap().as_bytes()).unwrap(),
+            ),
+    )
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_unity_log_entries() {
+    let input = &b"2021-06-01T12:00:00.123Z|0x1a2b|Loaded scene 'Main'\n2021-06-01T12:00:01.000Z|0x1a2b|NullReferenceException: Object reference not set to an instance of an object\nPlayer.Update () (at Assets/Scripts/Player.cs:42)\nUnityEngine.Debug:LogException(Exception)\n\n(Filename: Assets/Scripts/Player.cs Line: 42)\n2021-06-01T12:00:02.000Z|0x1a2b|Quit\n"[..];
+    let parser = Parser::builder().group_exceptions(true).build();
+    assert_debug_snapshot!(
+        parser
+            .read_entries(input)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (
+                    entry.format(),
+                    entry.timestamp().cloned(),
+                    entry.message().to_string(),
+                    entry.fields().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>(),
+                    entry.stack_trace().map(|x| x.frames().len()),
+                )
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            Some(
+                Unity,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:00.123Z,
+                ),
+            ),
+            "Loaded scene 'Main'",
+            [
+                "tid=0x1a2b",
+            ],
+            None,
+        ),
+        (
+            Some(
+                Unity,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:01Z,
+                ),
+            ),
+            "NullReferenceException: Object reference not set to an instance of an object\nPlayer.Update () (at Assets/Scripts/Player.cs:42)\nUnityEngine.Debug:LogException(Exception)\n\n(Filename: Assets/Scripts/Player.cs Line: 42)",
+            [
+                "tid=0x1a2b",
+            ],
+            Some(
+                2,
+            ),
+        ),
+        (
+            Some(
+                Unity,
+            ),
+            Some(
+                Utc(
+                    2021-06-01T12:00:02Z,
+                ),
+            ),
+            "Quit",
+            [
+                "tid=0x1a2b",
+            ],
+            None,
+        ),
+    ]
+    "###
+    );
+}
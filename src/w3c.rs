@@ -0,0 +1,187 @@
+use std::ops::Range;
+use std::str;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::format::FormatKind;
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref FIELDS_RE: Regex = Regex::new(r#"^#Fields:[\t\x20]*(.*?)[\t\x20]*$"#).unwrap();
+    static ref DATE_RE: Regex = Regex::new(r#"^([0-9]{4})-([0-9]{2})-([0-9]{2})$"#).unwrap();
+    static ref TIME_RE: Regex =
+        Regex::new(r#"^([0-9]{2}):([0-9]{2}):([0-9]{2})(?:\.([0-9]{1,9}))?$"#).unwrap();
+}
+
+/// Returns the column names declared by a `#Fields:` directive.
+pub(crate) fn parse_fields_directive(bytes: &[u8]) -> Option<Vec<String>> {
+    let caps = FIELDS_RE.captures(bytes)?;
+    let columns = String::from_utf8_lossy(&caps[1])
+        .split_whitespace()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        None
+    } else {
+        Some(columns)
+    }
+}
+
+/// Returns the ranges of the space separated columns of a line.
+fn split_columns(bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut columns = Vec::new();
+    let mut start = 0;
+    for pos in memchr::memchr_iter(b' ', bytes).chain(Some(bytes.len())) {
+        columns.push(start..pos);
+        start = pos + 1;
+    }
+    columns
+}
+
+fn parse_timestamp(date: &[u8], time: &[u8]) -> Option<NaiveDateTime> {
+    let date = DATE_RE.captures(date)?;
+    let time = TIME_RE.captures(time)?;
+    let num = |bytes: &[u8]| -> u32 { str::from_utf8(bytes).unwrap().parse().unwrap() };
+    let nanos = time.get(4).map_or(0, |x| {
+        num(x.as_bytes()) * 10u32.pow(9 - x.as_bytes().len() as u32)
+    });
+    Some(
+        naive_datetime(
+            num(&date[1]) as i32,
+            num(&date[2]),
+            num(&date[3]),
+            num(&time[1]),
+            num(&time[2]),
+            num(&time[3]),
+        )? + Duration::nanoseconds(nanos.into()),
+    )
+}
+
+/// Parses a line of a W3C extended log (as written by IIS) using the
+/// columns of the last `#Fields:` directive of the stream.
+///
+/// The `date` and `time` columns, which are in UTC, form the timestamp and
+/// the message is the part of the line after them.  All other columns are
+/// exposed as fields under their W3C names (`c-ip`, `cs-method`,
+/// `sc-status`, ...), except for missing values (`-`).
+pub(crate) fn parse_w3c_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    if bytes.starts_with(b"#") {
+        return None;
+    }
+    ctx.with_w3c_columns(|names| {
+        let columns = split_columns(bytes);
+        if columns.len() != names.len() {
+            return None;
+        }
+        let date = names.iter().position(|x| x == "date")?;
+        let time = names.iter().position(|x| x == "time")?;
+        let naive = parse_timestamp(&bytes[columns[date].clone()], &bytes[columns[time].clone()])?;
+
+        let ts_span =
+            columns[date].start.min(columns[time].start)..columns[date].end.max(columns[time].end);
+        let message = (ts_span.end + 1).min(bytes.len())..bytes.len();
+        let mut entry =
+            LogEntry::from_utc_time(Utc.from_utc_datetime(&naive), &bytes[message.clone()])
+                .with_spans(ts_span, message)
+                .with_format(FormatKind::W3c);
+        for (idx, (name, column)) in names.iter().zip(columns).enumerate() {
+            let value = &bytes[column];
+            if idx != date && idx != time && value != b"-" {
+                entry = entry.with_field(name.clone(), String::from_utf8_lossy(value));
+            }
+        }
+        Some(entry)
+    })?
+}
+
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_w3c_log_entries() {
+    let input = &b"#Software: Microsoft Internet Information Services 10.0\n#Version: 1.0\n#Date: 2021-06-01 12:00:00\n#Fields: date time s-ip cs-method cs-uri-stem cs-uri-query s-port cs-username c-ip sc-status time-taken\n2021-06-01 12:00:01 10.0.0.1 GET /index.html - 80 - 192.168.1.5 200 15\n2021-06-01 12:00:02 10.0.0.1 POST /api q=1 443 bob 192.168.1.6 500 1200\n#Fields: date time cs-method\n2021-06-01 12:00:03 HEAD\n"[..];
+    assert_debug_snapshot!(
+        Parser::new()
+            .read_entries(input)
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.format() == Some(FormatKind::W3c))
+            .map(|entry| {
+                (
+                    entry.timestamp().cloned(),
+                    entry.message().to_string(),
+                    entry
+                        .fields()
+                        .map(|(k, v)| format!("{}={}", k, v))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>(),
+        @r###"
+    [
+        (
+            Some(
+                Utc(
+                    2021-06-01T12:00:01Z,
+                ),
+            ),
+            "10.0.0.1 GET /index.html - 80 - 192.168.1.5 200 15",
+            [
+                "s-ip=10.0.0.1",
+                "cs-method=GET",
+                "cs-uri-stem=/index.html",
+                "s-port=80",
+                "c-ip=192.168.1.5",
+                "sc-status=200",
+                "time-taken=15",
+            ],
+        ),
+        (
+            Some(
+                Utc(
+                    2021-06-01T12:00:02Z,
+                ),
+            ),
+            "10.0.0.1 POST /api q=1 443 bob 192.168.1.6 500 1200",
+            [
+                "s-ip=10.0.0.1",
+                "cs-method=POST",
+                "cs-uri-stem=/api",
+                "cs-uri-query=q=1",
+                "s-port=443",
+                "cs-username=bob",
+                "c-ip=192.168.1.6",
+                "sc-status=500",
+                "time-taken=1200",
+                "q=1",
+            ],
+        ),
+        (
+            Some(
+                Utc(
+                    2021-06-01T12:00:03Z,
+                ),
+            ),
+            "HEAD",
+            [
+                "cs-method=HEAD",
+            ],
+        ),
+    ]
+    "###
+    );
+}
+
+#[test]
+fn test_disable_w3c() {
+    let input = &b"#Fields: date time cs-method\n2021-06-01 12:00:03 HEAD\n"[..];
+    let parser = Parser::builder().disable_format(FormatKind::W3c).build();
+    let entry = parser.read_entries(input).last().unwrap().unwrap();
+    assert_eq!(entry.message(), "HEAD");
+    assert_ne!(entry.format(), Some(FormatKind::W3c));
+}
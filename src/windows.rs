@@ -0,0 +1,236 @@
+use std::borrow::Cow;
+use std::str;
+
+use chrono::Duration;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{naive_datetime, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref CBS_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            (
+                ([0-9]{4})-([0-9]{2})-([0-9]{2})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})
+            )
+            ,\x20(Info|Warning|Error|Perf)
+            \x20+([A-Za-z]+)
+            (?:\x20+|$)(.*)
+        $
+    "#
+    )
+    .unwrap();
+    static ref SETUPAPI_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ((?:>>>|<<<)\x20+Section\x20(?:start|end))
+            \x20(
+                ([0-9]{4})/([0-9]{2})/([0-9]{2})
+                \x20([0-9]{2}):([0-9]{2}):([0-9]{2})\.([0-9]{3})
+            )
+        $
+    "#
+    )
+    .unwrap();
+    static ref TRANSCRIPT_RE: Regex = Regex::new(
+        r#"(?x)
+        ^
+            ((?:Start|End|Command\x20start)\x20time):
+            \x20(
+                ([0-9]{4})([0-9]{2})([0-9]{2})
+                ([0-9]{2})([0-9]{2})([0-9]{2})
+            )
+        $
+    "#
+    )
+    .unwrap();
+}
+
+/// Parses a line of the Windows component based servicing log (`CBS.log`).
+///
+/// The level sets the level of the entry and the component (`CBS`, `CSI`,
+/// `DPX`, ...) is exposed as the `component` field.
+///
+/// Example: `2021-06-01 12:00:00, Info                  CBS    Session initialized`
+pub fn parse_cbs_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = CBS_RE.captures(bytes)?;
+    // all groups except the message only match ASCII
+    let text = |idx| str::from_utf8(caps.get(idx).unwrap().as_bytes()).unwrap();
+    let num = |idx| -> u32 { text(idx).parse().unwrap() };
+
+    let naive = naive_datetime(num(2) as i32, num(3), num(4), num(5), num(6), num(7))?;
+    let message = caps.get(10).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(1).unwrap().range(), message)
+            .with_level(Cow::Borrowed(text(8)))
+            .with_field("component", text(9)),
+    )
+}
+
+/// Parses the section boundaries of the Windows device setup log
+/// (`setupapi.dev.log`).
+///
+/// Only the `Section start` and `Section end` lines carry a full timestamp,
+/// the lines between them are left to inherit it.  The message is the
+/// marker before the timestamp.
+///
+/// Example: `>>>  Section start 2021/06/01 12:00:00.123`
+pub fn parse_setupapi_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = SETUPAPI_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let naive = naive_datetime(num(3) as i32, num(4), num(5), num(6), num(7), num(8))?
+        + Duration::milliseconds(num(9).into());
+    let message = caps.get(1).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(2).unwrap().range(), message),
+    )
+}
+
+/// Parses the timestamps of a PowerShell transcript (`Start-Transcript`).
+///
+/// The transcript header and footer carry the `Start time` and `End time`
+/// lines, and with `-IncludeInvocationHeader` every command is preceded by
+/// a `Command start time` line.  The prompts and output in between are
+/// left to inherit the timestamp.  The message is the label before the
+/// timestamp.
+///
+/// Example: `Command start time: 20210601120000`
+pub fn parse_powershell_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let caps = TRANSCRIPT_RE.captures(bytes)?;
+    // the timestamp only matches ASCII
+    let num = |idx| -> u32 { str::from_utf8(&caps[idx]).unwrap().parse().unwrap() };
+
+    let naive = naive_datetime(num(3) as i32, num(4), num(5), num(6), num(7), num(8))?;
+    let message = caps.get(1).unwrap().range();
+    Some(
+        ctx.local_entry(naive, &bytes[message.clone()])?
+            .with_spans(caps.get(2).unwrap().range(), message),
+    )
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_windows_log_entries() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.local_timestamp().map(|x| x.naive_local().to_string()),
+            entry.level(),
+            entry.message().to_string(),
+            entry
+                .fields()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(&b"2021-06-01 12:00:00, Info                  CBS    Session: 30912345_123456789 initialized by client WindowsUpdateAgent."[..]),
+            parse(b"2021-06-01 12:00:01, Error                 CSI    00000001 (F) STATUS_SXS_ASSEMBLY_MISSING"),
+            parse(b">>>  Section start 2021/06/01 12:00:02.123"),
+            parse(b"<<<  Section end 2021/06/01 12:00:03.456"),
+            parse(b"Start time: 20210601120004"),
+            parse(b"Command start time: 20210601120005"),
+            parse(b"End time: 20210601120006"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Cbs,
+            ),
+            Some(
+                "2021-06-01 12:00:00",
+            ),
+            Some(
+                Info,
+            ),
+            "Session: 30912345_123456789 initialized by client WindowsUpdateAgent.",
+            [
+                "component=CBS",
+            ],
+        ),
+        (
+            Some(
+                Cbs,
+            ),
+            Some(
+                "2021-06-01 12:00:01",
+            ),
+            Some(
+                Error,
+            ),
+            "00000001 (F) STATUS_SXS_ASSEMBLY_MISSING",
+            [
+                "component=CSI",
+            ],
+        ),
+        (
+            Some(
+                Setupapi,
+            ),
+            Some(
+                "2021-06-01 12:00:02.123",
+            ),
+            None,
+            ">>>  Section start",
+            [],
+        ),
+        (
+            Some(
+                Setupapi,
+            ),
+            Some(
+                "2021-06-01 12:00:03.456",
+            ),
+            None,
+            "<<<  Section end",
+            [],
+        ),
+        (
+            Some(
+                Powershell,
+            ),
+            Some(
+                "2021-06-01 12:00:04",
+            ),
+            None,
+            "Start time",
+            [],
+        ),
+        (
+            Some(
+                Powershell,
+            ),
+            Some(
+                "2021-06-01 12:00:05",
+            ),
+            None,
+            "Command start time",
+            [],
+        ),
+        (
+            Some(
+                Powershell,
+            ),
+            Some(
+                "2021-06-01 12:00:06",
+            ),
+            None,
+            "End time",
+            [],
+        ),
+    ]
+    "###
+    );
+}
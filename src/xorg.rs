@@ -0,0 +1,131 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+use crate::parser::{self, Context};
+use crate::types::LogEntry;
+
+lazy_static! {
+    static ref MARKER_RE: Regex =
+        Regex::new(r#"^\((--|\*\*|==|\+\+|!!|II|WW|EE|NI|\?\?)\)(?:\x20|$)"#).unwrap();
+}
+
+/// Parses a line of the X.Org server log (`Xorg.0.log`).
+///
+/// The bracketed seconds since server start are handled like
+/// [`parse_uptime_log_entry`](parser::parse_uptime_log_entry).  The
+/// `(II)`, `(WW)` and `(EE)` markers set the level, `(!!)` is a notice and
+/// `(NI)` (not implemented) a warning.  The markers for probed, configured
+/// and default values are informational.
+///
+/// Example: `[    45.123] (II) modeset(0): Output eDP-1 connected`
+pub fn parse_xorg_log_entry<'a>(bytes: &'a [u8], ctx: &Context) -> Option<LogEntry<'a>> {
+    let entry = parser::parse_uptime_log_entry(bytes, ctx)?;
+    let ts_span = entry.timestamp_span()?;
+    let message_span = entry.message_span()?;
+    let caps = MARKER_RE.captures(&bytes[message_span.clone()])?;
+
+    let level = match &caps[1] {
+        b"EE" => Some("error"),
+        b"WW" | b"NI" => Some("warning"),
+        b"!!" => Some("notice"),
+        b"??" => None,
+        _ => Some("info"),
+    };
+    let message = message_span.start + caps.get(0).unwrap().end()..bytes.len();
+    let entry = entry
+        .with_message(&bytes[message.clone()])
+        .with_spans(ts_span, message);
+    Some(match level {
+        Some(level) => entry.with_level(Cow::Borrowed(level)),
+        None => entry,
+    })
+}
+
+#[cfg(test)]
+use insta::assert_debug_snapshot;
+
+#[test]
+fn test_parse_xorg_log_entry() {
+    let parse = |line| {
+        let entry = LogEntry::parse(line);
+        (
+            entry.format(),
+            entry.relative_time(),
+            entry.level(),
+            entry.message().to_string(),
+        )
+    };
+    assert_debug_snapshot!(
+        [
+            parse(b"[    45.123] (II) modeset(0): Output eDP-1 connected"),
+            parse(b"[    45.200] (WW) Falling back to old probe method for vesa"),
+            parse(b"[    45.201] (EE) Failed to load module \"fbdev\" (module does not exist, 0)"),
+            parse(b"[    45.202] (**) Option \"fd\" \"25\""),
+            parse(b"[    45.203]    ABI class: X.Org Video Driver, version 24.1"),
+        ],
+        @r###"
+    [
+        (
+            Some(
+                Xorg,
+            ),
+            Some(
+                45.123s,
+            ),
+            Some(
+                Info,
+            ),
+            "modeset(0): Output eDP-1 connected",
+        ),
+        (
+            Some(
+                Xorg,
+            ),
+            Some(
+                45.2s,
+            ),
+            Some(
+                Warning,
+            ),
+            "Falling back to old probe method for vesa",
+        ),
+        (
+            Some(
+                Xorg,
+            ),
+            Some(
+                45.201s,
+            ),
+            Some(
+                Error,
+            ),
+            "Failed to load module \"fbdev\" (module does not exist, 0)",
+        ),
+        (
+            Some(
+                Xorg,
+            ),
+            Some(
+                45.202s,
+            ),
+            Some(
+                Info,
+            ),
+            "Option \"fd\" \"25\"",
+        ),
+        (
+            Some(
+                Uptime,
+            ),
+            Some(
+                45.203s,
+            ),
+            None,
+            "   ABI class: X.Org Video Driver, version 24.1",
+        ),
+    ]
+    "###
+    );
+}